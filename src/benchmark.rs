@@ -0,0 +1,406 @@
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::hittable::accelerator::ACCELERATOR_KINDS;
+use crate::hittable::axis_aligned_box::AxisAlignedBox;
+use crate::hittable::bvh_node::DEFAULT_MAX_LEAF_SIZE;
+use crate::hittable::bvh_node::construct_bvh_root;
+use crate::hittable::bvh_node::construct_sah_bvh_root;
+use crate::hittable::Hittable;
+use crate::hittable::instance::Instance;
+use crate::hittable::linear_bvh::LinearBvh;
+use crate::hittable::linear_bvh::Primitive;
+use crate::hittable::sphere::Sphere;
+use crate::hittable::top_level_bvh::TopLevelBvh;
+use crate::hittable::wide_bvh::WideBvh;
+use crate::material::Material;
+use crate::quaternion::Quaternion;
+use crate::ray::PACKET_SIZE;
+use crate::ray::Ray;
+use crate::ray::RayPacket;
+use crate::scene::HittableHandle;
+use crate::scene::NAMED_SCENES;
+use crate::scene::SceneBuilder;
+use crate::scene::construct_book1_spheres;
+use crate::scene::construct_named_scene;
+use crate::texture::monochrome::Monochrome;
+use crate::transform::Transform;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The number of Rays traced through each BVH.
+const RAY_COUNT: usize = 1_000_000;
+
+/// The number of Rays traced through each Accelerator.
+const ACCELERATOR_RAY_COUNT: usize = 250_000;
+
+/// The number of frames the animation benchmark steps through.
+const ANIMATION_FRAME_COUNT: usize = 30;
+
+/// The number of boxes that tumble through the animation benchmark.
+const ANIMATION_BOX_COUNT: usize = 100;
+
+/// The number of spheres that circle overhead in the animation benchmark.
+const ANIMATION_SWARM_SIZE: usize = 64;
+
+/// Builds a BVH over the given spheres.
+type Builder = Box<dyn Fn(Vec<Sphere>) -> Box<dyn Hittable>>;
+
+///
+/// Prints how long it takes to build a BVH over the book 1 scene and trace
+/// Rays through it, for the random median builder, the SAH builder with a few
+/// different leaf sizes, the LinearBvh and the WideBvh. Half of the Rays come
+/// from the book 1 camera and the other half start at random points in the
+/// scene and go in random directions, like bounced Rays do. Then prints how
+/// fast primary Rays are traced through the WideBvh one at a time and in
+/// RayPackets.
+///
+pub fn run_bvh_benchmark() {
+
+    let rays = generate_rays();
+
+    println!("{} hittables, {} rays", construct_book1_spheres(&mut StdRng::seed_from_u64(0)).len(), rays.len());
+    println!("{:<16} {:>12} {:>12} {:>12} {:>10}", "builder", "build (ms)", "trace (ms)", "Mrays/s", "hits");
+
+    let builders: Vec<(&str, Builder)> = vec![
+        ("random median", Box::new(|s| { let n = s.len(); Box::new(construct_bvh_root(&mut to_hittables(s), 0, n)) })),
+        ("sah, leaf 1", Box::new(|s| { let n = s.len(); Box::new(construct_sah_bvh_root(&mut to_hittables(s), 0, n, 1)) })),
+        ("sah, leaf 2", Box::new(|s| { let n = s.len(); Box::new(construct_sah_bvh_root(&mut to_hittables(s), 0, n, 2)) })),
+        ("sah, leaf 4", Box::new(|s| { let n = s.len(); Box::new(construct_sah_bvh_root(&mut to_hittables(s), 0, n, 4)) })),
+        ("sah, leaf 8", Box::new(|s| { let n = s.len(); Box::new(construct_sah_bvh_root(&mut to_hittables(s), 0, n, 8)) })),
+        ("linear, leaf 1", Box::new(|s| Box::new(LinearBvh::new(s.into_iter().map(Primitive::Sphere).collect(), 1)))),
+        ("linear, leaf 4", Box::new(|s| Box::new(LinearBvh::new(s.into_iter().map(Primitive::Sphere).collect(), 4)))),
+        ("wide, leaf 1", Box::new(|s| Box::new(WideBvh::new(s.into_iter().map(Primitive::Sphere).collect(), 1)))),
+        ("wide, leaf 4", Box::new(|s| Box::new(WideBvh::new(s.into_iter().map(Primitive::Sphere).collect(), 4)))),
+    ];
+
+    for (name, builder) in builders {
+
+        // Every builder gets the same spheres.
+        let spheres = construct_book1_spheres(&mut StdRng::seed_from_u64(0));
+        let start = Instant::now();
+        let root = builder(spheres);
+        let build_time = start.elapsed();
+
+        let start = Instant::now();
+        let mut hits = 0;
+        for ray in &rays {
+            if root.get_hit(*ray, 0.001, 1000.0).is_some() { hits += 1; }
+        }
+        let trace_time = start.elapsed();
+
+        println!(
+            "{:<16} {:>12.3} {:>12.1} {:>12.3} {:>10}",
+            name,
+            to_millis(build_time),
+            to_millis(trace_time),
+            rays.len() as f64 / trace_time.as_secs_f64() / 1e6,
+            hits,
+        );
+    }
+
+    run_packet_benchmark();
+}
+
+///
+/// Prints how fast primary Rays through 2x2 blocks of pixels are traced
+/// through a WideBvh over the book 1 scene, one Ray at a time and as
+/// RayPackets. Checks that both give exactly the same Hits.
+///
+fn run_packet_benchmark() {
+
+    let packets = generate_packets();
+    let rays: Vec<Ray> = packets.iter().flatten().copied().collect();
+    let spheres = construct_book1_spheres(&mut StdRng::seed_from_u64(0));
+    let bvh = WideBvh::new(spheres.into_iter().map(Primitive::Sphere).collect(), 4);
+
+    println!();
+    println!("{} primary rays in packets of {}", rays.len(), PACKET_SIZE);
+    println!("{:<16} {:>12} {:>12} {:>10}", "traversal", "trace (ms)", "Mrays/s", "hits");
+
+    let start = Instant::now();
+    let single_hits: Vec<Option<f64>> = rays.iter().map(|ray| bvh.get_hit(*ray, 0.001, 1000.0).map(|h| h.distance)).collect();
+    let single_time = start.elapsed();
+
+    let start = Instant::now();
+    let packet_hits: Vec<Option<f64>> = packets.iter()
+        .flat_map(|packet| bvh.get_packet_hits(packet, 0.001, 1000.0).map(|hit| hit.map(|h| h.distance)))
+        .collect();
+    let packet_time = start.elapsed();
+
+    for (name, hits, time) in [("wide, single", &single_hits, single_time), ("wide, packets", &packet_hits, packet_time)] {
+        println!(
+            "{:<16} {:>12.1} {:>12.3} {:>10}",
+            name,
+            to_millis(time),
+            rays.len() as f64 / time.as_secs_f64() / 1e6,
+            hits.iter().filter(|h| h.is_some()).count(),
+        );
+    }
+
+    let mismatches = single_hits.iter().zip(&packet_hits).filter(|(a, b)| a != b).count();
+    println!("{} rays hit differently in packets", mismatches);
+}
+
+///
+/// Returns the RayPackets traced by the packet benchmark. Each one holds the
+/// Rays through a random 2x2 block of pixels of a 1920x1080 image.
+///
+fn generate_packets() -> Vec<RayPacket> {
+
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let look_from = Vector3::new(13.0, 2.0, 4.0);
+    let look_to = Vector3::new(0.0, 0.5, 0.0);
+    let up = Vector3::new(0.0, 1.0, 0.0);
+    let cam = Camera::new(look_from, look_to, up, 20.0, 16.0 / 9.0, 0.0, 10.0);
+
+    let (width, height) = (1920.0, 1080.0);
+
+    (0..RAY_COUNT / 2 / PACKET_SIZE).map(|_| {
+        let col = rng.gen_range(0.0..width - 1.0_f64).floor();
+        let row = rng.gen_range(0.0..height - 1.0_f64).floor();
+        cam.get_ray_packet(std::array::from_fn(|i| {
+            let w = (col + (i % 2) as f64 + rng.gen::<f64>()) / width;
+            let h = (row + (i / 2) as f64 + rng.gen::<f64>()) / height;
+            (w, h)
+        }), (1.0 / width, 1.0 / height))
+    }).collect()
+}
+
+///
+/// Prints how long it takes to build each kind of Accelerator over the
+/// hittables of the scene with the given name, how much memory it takes up,
+/// and how fast Rays are traced through it. The Rays start at random points
+/// near random hittables and go in random directions, like bounced Rays do.
+/// Also checks that every kind of Accelerator finds the same Hits.
+///
+/// # Arguments
+/// * `scene_name` - The name of the scene in NAMED_SCENES.
+///
+pub fn run_accelerator_benchmark(scene_name: &str) {
+
+    let scene = match construct_named_scene(scene_name) {
+        Some(scene) => scene,
+        None => {
+            let names: Vec<&str> = NAMED_SCENES.iter().map(|(name, _)| *name).collect();
+            eprintln!("Unknown scene {}. The scenes are: {}", scene_name, names.join(", "));
+            return;
+        },
+    };
+    let hittables = scene.get_hittables();
+    if hittables.is_empty() {
+        eprintln!("The scene {} is empty", scene_name);
+        return;
+    }
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let rays: Vec<Ray> = (0..ACCELERATOR_RAY_COUNT).map(|_| {
+        // Start somewhere in or just around a random hittable's AABB.
+        let b = hittables[rng.gen_range(0..hittables.len())].get_aabb();
+        let center = b.centroid();
+        let half_size = 0.75 * (b.max - b.min);
+        let origin = center + Vector3::new(
+            half_size.x * rng.gen_range(-1.0..1.0),
+            half_size.y * rng.gen_range(-1.0..1.0),
+            half_size.z * rng.gen_range(-1.0..1.0),
+        );
+        let direction = Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+        Ray::new(origin, direction)
+    }).collect();
+
+    println!("{}: {} hittables, {} rays", scene_name, hittables.len(), rays.len());
+    println!("{:<12} {:>12} {:>12} {:>12} {:>12} {:>10} {:>10}", "accelerator", "build (ms)", "memory (KiB)", "trace (ms)", "Mrays/s", "hits", "mismatches");
+
+    let mut first_hits: Option<Vec<Option<f64>>> = None;
+
+    for kind in ACCELERATOR_KINDS {
+
+        let start = Instant::now();
+        let accelerator = kind.build(hittables.clone(), DEFAULT_MAX_LEAF_SIZE);
+        let build_time = start.elapsed();
+
+        let start = Instant::now();
+        let hits: Vec<Option<f64>> = rays.iter().map(|ray| accelerator.get_hit(*ray, 0.001, f64::INFINITY).map(|h| h.distance)).collect();
+        let trace_time = start.elapsed();
+
+        // Every kind is compared against the first.
+        let expected = first_hits.get_or_insert_with(|| hits.clone());
+        let mismatches = expected.iter().zip(&hits).filter(|(a, b)| a != b).count();
+
+        println!(
+            "{:<12} {:>12.3} {:>12.1} {:>12.1} {:>12.3} {:>10} {:>10}",
+            kind.get_name(),
+            to_millis(build_time),
+            accelerator.get_memory_size() as f64 / 1024.0,
+            to_millis(trace_time),
+            rays.len() as f64 / trace_time.as_secs_f64() / 1e6,
+            hits.iter().filter(|h| h.is_some()).count(),
+            mismatches,
+        );
+    }
+}
+
+///
+/// Prints how long it takes to bring a Scene up to date for each frame of an
+/// animation, and to trace Rays through it afterwards. Boxes tumble over the
+/// book 1 scene as Instances that the Scene's BVH is refit around, and a ring
+/// of spheres in a TopLevelBvh circles above them. Every frame, a spark is
+/// dropped into the Scene, and sparks that have fallen through the ground are
+/// taken out again. Each frame's Hits are checked against a Scene built from
+/// scratch over the same hittables.
+///
+pub fn run_animation_benchmark() {
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let rays: Vec<Ray> = generate_rays().into_iter().step_by(10).collect();
+    let get_material = || Material::new(Box::new(Monochrome::new(Color::new(0.8, 0.6, 0.2))), None, None);
+
+    let mut builder = SceneBuilder::new();
+    for sphere in construct_book1_spheres(&mut rng) {
+        builder.push(Rc::new(sphere));
+    }
+
+    // Each box slides a little way while turning to a random orientation.
+    let size = Vector3::new(0.4, 0.4, 0.4);
+    let cube: Rc<dyn Hittable> = Rc::new(AxisAlignedBox::new(Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.5, 0.5), get_material()));
+    let mut boxes = vec![];
+    for _ in 0..ANIMATION_BOX_COUNT {
+        let start = Vector3::new(rng.gen_range(-10.0..10.0), rng.gen_range(1.0..3.0), rng.gen_range(-10.0..10.0));
+        let end = start + Vector3::new(rng.gen_range(-2.0..2.0), 0.0, rng.gen_range(-2.0..2.0));
+        let turn = Quaternion::from_euler(rng.gen_range(-180.0..180.0), rng.gen_range(-180.0..180.0), rng.gen_range(-180.0..180.0));
+        let instance = Rc::new(Instance::new(Rc::clone(&cube), Transform::from_trs(start, Quaternion::identity(), size)));
+        builder.push(Rc::clone(&instance) as Rc<dyn Hittable>);
+        boxes.push((instance, start, end, turn));
+    }
+
+    // The swarm goes a quarter of the way around its ring.
+    let get_swarm_transform = |i: usize, t: f64| {
+        let angle = 2.0 * PI * (i as f64 / ANIMATION_SWARM_SIZE as f64 + 0.25 * t);
+        Transform::translation(Vector3::new(6.0 * angle.cos(), 4.0, 6.0 * angle.sin()))
+    };
+    let ball: Rc<dyn Hittable> = Rc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 0.3, get_material()));
+    let mut swarm = TopLevelBvh::new();
+    for i in 0..ANIMATION_SWARM_SIZE {
+        swarm.push(Rc::clone(&ball), get_swarm_transform(i, 0.0));
+    }
+    let swarm = Rc::new(swarm);
+    builder.push(Rc::clone(&swarm) as Rc<dyn Hittable>);
+
+    let mut scene = builder.build();
+    let mut sparks: VecDeque<(HittableHandle, Rc<Instance>)> = VecDeque::new();
+
+    println!("{} frames, {} rays per frame", ANIMATION_FRAME_COUNT, rays.len());
+    println!("{:<8} {:>12} {:>12} {:>12} {:>10} {:>10}", "frame", "update (ms)", "trace (ms)", "Mrays/s", "hits", "mismatches");
+
+    for frame in 0..ANIMATION_FRAME_COUNT {
+
+        let t = frame as f64 / (ANIMATION_FRAME_COUNT - 1) as f64;
+        let start_time = Instant::now();
+
+        for (instance, start, end, turn) in &boxes {
+            let position = *start + t * (*end - *start);
+            instance.set_transform(Transform::from_trs(position, Quaternion::identity().slerp(*turn, t), size));
+        }
+        for i in 0..swarm.len() {
+            swarm.set_transform(i, get_swarm_transform(i, t));
+        }
+        for (_, spark) in &sparks {
+            spark.set_transform(Transform::translation(Vector3::new(0.0, -0.5, 0.0)) * spark.get_transform());
+        }
+        scene.refit();
+
+        while let Some(&(handle, _)) = sparks.front() {
+            let is_fallen = scene.get(handle).is_none_or(|spark| spark.get_aabb().max.y < 0.0);
+            if !is_fallen { break; }
+            scene.remove(handle);
+            sparks.pop_front();
+        }
+        let spark = Rc::new(Instance::new(Rc::clone(&ball), Transform::translation(Vector3::new(rng.gen_range(-8.0..8.0), 6.0, rng.gen_range(-8.0..8.0)))));
+        sparks.push_back((scene.push(Rc::clone(&spark) as Rc<dyn Hittable>), spark));
+
+        // The first Ray pays for any rebuild the Scene has put off, so that
+        // is counted as part of the update.
+        scene.is_occluded(rays[0], 0.001, f64::INFINITY);
+        let update_time = start_time.elapsed();
+
+        let start_time = Instant::now();
+        let hits: Vec<Option<f64>> = rays.iter().map(|ray| scene.get_intersect(*ray, 0.001, f64::INFINITY).map(|h| h.distance)).collect();
+        let trace_time = start_time.elapsed();
+
+        let mut expected = SceneBuilder::new();
+        for hittable in scene.get_hittables() {
+            expected.push(hittable);
+        }
+        let expected = expected.build();
+        let mismatches = rays.iter().zip(&hits)
+            .filter(|(ray, hit)| expected.get_intersect(**ray, 0.001, f64::INFINITY).map(|h| h.distance) != **hit)
+            .count();
+
+        println!(
+            "{:<8} {:>12.3} {:>12.1} {:>12.3} {:>10} {:>10}",
+            frame,
+            to_millis(update_time),
+            to_millis(trace_time),
+            rays.len() as f64 / trace_time.as_secs_f64() / 1e6,
+            hits.iter().filter(|h| h.is_some()).count(),
+            mismatches,
+        );
+    }
+}
+
+///
+/// Returns the Rays traced by the benchmark. A fixed seed is used so that
+/// every run traces the same Rays.
+///
+fn generate_rays() -> Vec<Ray> {
+
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let look_from = Vector3::new(13.0, 2.0, 4.0);
+    let look_to = Vector3::new(0.0, 0.5, 0.0);
+    let up = Vector3::new(0.0, 1.0, 0.0);
+    let cam = Camera::new(look_from, look_to, up, 20.0, 16.0 / 9.0, 0.0, 10.0);
+
+    let mut rays = Vec::with_capacity(RAY_COUNT);
+
+    for _ in 0..RAY_COUNT / 2 {
+        rays.push(cam.get_ray(rng.gen(), rng.gen(), (1.0 / 1920.0, 1.0 / 1080.0)));
+    }
+
+    for _ in RAY_COUNT / 2..RAY_COUNT {
+        let origin = Point3::new(rng.gen_range(-15.0..10.0), rng.gen_range(0.0..2.0), rng.gen_range(-10.0..5.0));
+        let direction = Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+        rays.push(Ray::new(origin, direction));
+    }
+
+    rays
+}
+
+///
+/// Returns the given Spheres as a list of Hittables.
+///
+/// # Arguments
+/// * `spheres` - The Spheres.
+///
+fn to_hittables(spheres: Vec<Sphere>) -> Vec<Rc<dyn Hittable>> {
+    spheres.into_iter().map(|s| Rc::new(s) as Rc<dyn Hittable>).collect()
+}
+
+///
+/// Returns the given Duration in milliseconds.
+///
+/// # Arguments
+/// * `duration` - The Duration.
+///
+fn to_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -1,4 +1,9 @@
+use crate::matrix4::Matrix4;
+use crate::ray::PACKET_SIZE;
 use crate::ray::Ray;
+use crate::ray::RayDifferentials;
+use crate::ray::RayPacket;
+use crate::transform::Transform;
 use crate::vector3::Point3;
 use crate::vector3::rand_vector2;
 use crate::vector3::Vector3;
@@ -24,6 +29,10 @@ pub struct Camera {
     view_horizontal: Vector3,
     /// The radius of the camera's lens.
     lens_radius: f64,
+    /// Takes points from world space into the camera's own space.
+    world_to_camera: Transform,
+    /// The camera's perspective projection, from its own space onto its view.
+    projection: Matrix4,
 }
 
 impl Camera {
@@ -55,9 +64,10 @@ impl Camera {
         let view_height = 2.0 * (theta / 2.0).tan();
         let view_width = aspect_ratio * view_height;
 
-        let view_direction = (look_from - look_at).unit();
-        let view_vertical = up.cross(view_direction).unit();
-        let view_horizontal = view_direction.cross(view_vertical);
+        let camera_to_world = Transform::look_at(look_from, look_at, up);
+        let view_vertical = camera_to_world.transform_vector(Vector3::new(1.0, 0.0, 0.0));
+        let view_horizontal = camera_to_world.transform_vector(Vector3::new(0.0, 1.0, 0.0));
+        let view_direction = camera_to_world.transform_vector(Vector3::new(0.0, 0.0, 1.0));
 
         let origin = look_from;
         let horizontal = focus_distance * view_width * view_vertical;
@@ -73,18 +83,25 @@ impl Camera {
             view_vertical: view_vertical,
             view_horizontal: view_horizontal,
             lens_radius: aperature / 2.0,
+            world_to_camera: camera_to_world.inverse(),
+            // Only x and y are projected, so the clipping planes don't matter.
+            projection: Matrix4::perspective(vfov_degrees, aspect_ratio, 1.0, 2.0),
         }  
     }
 
     ///
-    /// Returns a Ray through the camera's view at the given width and height.
+    /// Returns a Ray through the camera's view at the given width and height,
+    /// with differentials through the neighbouring pixels.
     ///
     /// # Arguments
     /// `&self` - The camera that the ray is originating from.
     /// `width_ratio` - How far horizontally the ray is applied to the image.
     /// `height_ratio` - How far vertically the ray is applied to the image.
+    /// `pixel_size` - The width and height of a pixel, as ratios of the image.
+    /// Shrinking it when many samples are taken per pixel keeps textures from
+    /// being blurred more than the samples already blur them.
     ///
-    pub fn get_ray(&self, width_ratio: f64, height_ratio: f64) -> Ray {
+    pub fn get_ray(&self, width_ratio: f64, height_ratio: f64, pixel_size: (f64, f64)) -> Ray {
 
         let rd = self.lens_radius * rand_vector2();
         let origin_offset = self.view_horizontal * rd.x + self.view_vertical * rd.y;
@@ -94,6 +111,69 @@ impl Camera {
         let v_offset = height_ratio * self.vertical;
         let direction = self.lower_left_corner + h_offset + v_offset - origin;
 
-        Ray::new(origin, direction)
+        // The neighbouring Rays go through the same point on the lens, so they
+        // only differ by where they cross the focus plane.
+        let differentials = RayDifferentials {
+            x_origin: origin,
+            x_direction: direction + pixel_size.0 * self.horizontal,
+            y_origin: origin,
+            y_direction: direction + pixel_size.1 * self.vertical,
+        };
+
+        Ray::with_differentials(origin, direction, differentials)
+    }
+
+    ///
+    /// Returns a RayPacket of Rays through the camera's view, one for each of
+    /// the given (width, height) ratios. The ratios should be close together,
+    /// such as neighbouring pixels, so that the Rays can be traced together.
+    ///
+    /// # Arguments
+    /// `&self` - The camera that the rays are originating from.
+    /// `ratios` - How far horizontally and vertically each ray is applied to the image.
+    /// `pixel_size` - The width and height of a pixel, as ratios of the image.
+    ///
+    pub fn get_ray_packet(&self, ratios: [(f64, f64); PACKET_SIZE], pixel_size: (f64, f64)) -> RayPacket {
+        ratios.map(|(width_ratio, height_ratio)| self.get_ray(width_ratio, height_ratio, pixel_size))
+    }
+
+    ///
+    /// Returns the width and height ratios that get_ray would take to aim a Ray
+    /// through the camera's lens center at the given point, or None if the
+    /// point is not in front of the camera. Ratios outside of [0, 1] are
+    /// outside of the camera's view.
+    ///
+    /// # Arguments
+    /// `&self` - The camera the point is seen from.
+    /// `point` - The point in space.
+    ///
+    pub fn project(&self, point: Point3) -> Option<(f64, f64)> {
+
+        // The camera looks down its negative z axis, and its view is projected
+        // onto [-1, 1] across and up.
+        let point = self.world_to_camera.transform_point(point);
+        if 0.0 <= point.z {
+            return None;
+        }
+
+        let point = self.projection.transform_point(point);
+        Some(((point.x + 1.0) / 2.0, (point.y + 1.0) / 2.0))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn project_undoes_get_ray() {
+        let camera = Camera::new(Point3::new(3.0, 2.0, 4.0), Point3::new(0.0, 0.5, -1.0), Vector3::new(0.0, 1.0, 0.0), 35.0, 1.5, 0.0, 6.0);
+        for (width_ratio, height_ratio) in [(0.5, 0.5), (0.1, 0.8), (0.9, 0.05), (-0.2, 1.3)] {
+            let ray = camera.get_ray(width_ratio, height_ratio, (0.0, 0.0));
+            let (u, v) = camera.project(ray.get_point(2.7)).unwrap();
+            assert!((u - width_ratio).abs() < 1e-9 && (v - height_ratio).abs() < 1e-9);
+        }
+        let behind = camera.get_ray(0.5, 0.5, (0.0, 0.0)).get_point(-1.0);
+        assert!(camera.project(behind).is_none());
+    }
+}
@@ -74,6 +74,24 @@ impl Color {
             b: self.b.sqrt(),
         }
     }
+
+    ///
+    /// Returns the given color decoded from sRGB, the way 8 bit images are
+    /// usually stored, to linear values that can be lit and blended.
+    ///
+    /// # Arguments
+    /// * `&self` - The sRGB encoded color.
+    ///
+    pub fn srgb_to_linear(&self) -> Self {
+        let decode = |value: f64| {
+            if value <= 0.04045 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+        };
+        Self {
+            r: decode(self.r),
+            g: decode(self.g),
+            b: decode(self.b),
+        }
+    }
 }
 
 impl fmt::Display for Color {
@@ -149,6 +167,17 @@ impl Mul<Color> for f64 {
     }
 }
 
+impl Mul for Color {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            r: self.r * rhs.r,
+            g: self.g * rhs.g,
+            b: self.b * rhs.b,
+        }
+    }
+}
+
 impl MulAssign<f64> for Color {
     fn mul_assign(&mut self, rhs: f64) {
         *self = Self {
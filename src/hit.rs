@@ -7,6 +7,7 @@ use rand::Rng;
 use rand::rngs::ThreadRng;
 
 /// Represents the intersection between a Ray and a Hittable.
+#[derive(Clone, Copy)]
 pub struct Hit {
     /// The Ray that intersects the Hittable.
     pub ray: Ray,
@@ -2,8 +2,6 @@ use crate::hittable::Hittable;
 use crate::Ray;
 use crate::vector3::Point3;
 
-use std::mem::swap;
-
 /// Represents an axis-aligned bounding box in 3D space.
 #[derive(Clone, Copy)]
 pub struct AxisAlignedBoundingBox {
@@ -31,6 +29,109 @@ impl AABB {
             max: max,
         }
     }
+
+    ///
+    /// Returns an AABB constructed from two opposite corners. Unlike new, the
+    /// corners do not need to be the minimum and maximum corners of the box.
+    ///
+    /// # Arguments
+    /// * `a` - One corner of the AABB.
+    /// * `b` - The opposite corner of the AABB.
+    ///
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Self {
+            min: Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    ///
+    /// Returns a copy of the AABB where every side is at least a small, fixed
+    /// width. This keeps planar Hittables (which have zero thickness along one
+    /// axis) from producing degenerate boxes that a Ray can never hit.
+    ///
+    /// # Arguments
+    /// * `&self` - The AABB.
+    ///
+    pub fn pad(&self) -> Self {
+        let delta = 0.0001;
+        let mut min = self.min;
+        let mut max = self.max;
+
+        if max.x - min.x < delta { min.x -= delta / 2.0; max.x += delta / 2.0; }
+        if max.y - min.y < delta { min.y -= delta / 2.0; max.y += delta / 2.0; }
+        if max.z - min.z < delta { min.z -= delta / 2.0; max.z += delta / 2.0; }
+
+        Self::new(min, max)
+    }
+
+    ///
+    /// Returns the point in the middle of the AABB.
+    ///
+    /// # Arguments
+    /// * `&self` - The AABB.
+    ///
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (self.min + self.max)
+    }
+
+    ///
+    /// Returns the total area of the AABB's six sides. A Ray that passes
+    /// through a larger box is more likely to hit a smaller box inside it in
+    /// proportion to their surface areas, which is what the surface area
+    /// heuristic is built on.
+    ///
+    /// # Arguments
+    /// * `&self` - The AABB.
+    ///
+    pub fn surface_area(&self) -> f64 {
+        let size = self.max - self.min;
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    ///
+    /// Returns Some((entry, exit)) with the distances along the given Ray where
+    /// it enters and leaves the AABB, clamped to the given range. Returns None
+    /// if the Ray misses the AABB within the range.
+    ///
+    /// Uses the slab method: the range is narrowed to where the Ray is between
+    /// each pair of opposite sides in turn. A Ray parallel to a pair of sides
+    /// never crosses them, so it is either always or never between them.
+    /// Checking for that separately avoids the NaNs that come from 0 * inf.
+    ///
+    /// # Arguments
+    /// * `&self` - The AABB.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    pub fn get_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<(f64, f64)> {
+
+        let mut entry = min_dist;
+        let mut exit = max_dist;
+
+        for axis in 0..3 {
+
+            let origin = ray.origin[axis];
+            let direction = ray.direction[axis];
+
+            if direction == 0.0 {
+                if origin < self.min[axis] || self.max[axis] < origin { return None; }
+                continue;
+            }
+
+            let inverse_direction = 1.0 / direction;
+            let mut near = (self.min[axis] - origin) * inverse_direction;
+            let mut far = (self.max[axis] - origin) * inverse_direction;
+            if inverse_direction < 0.0 { std::mem::swap(&mut near, &mut far); }
+
+            entry = entry.max(near);
+            exit = exit.min(far);
+            if exit < entry { return None; }
+        }
+
+        Some((entry, exit))
+    }
 }
 
 ///
@@ -65,29 +166,8 @@ impl Hittable for AABB {
     /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
     /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
     ///
-    #[allow(unused_variables)]
     fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
-
-        let inverse_x_direction = 1.0 / ray.direction.x;
-        let mut x_min_dist = (self.min.x - ray.origin.x) * inverse_x_direction;
-        let mut x_max_dist = (self.max.x - ray.origin.x) * inverse_x_direction;
-        if inverse_x_direction < 0.0 { swap(&mut x_min_dist, &mut x_max_dist) }
-
-        let inverse_y_direction = 1.0 / ray.direction.y;
-        let mut y_min_dist = (self.min.y - ray.origin.y) * inverse_y_direction;
-        let mut y_max_dist = (self.max.y - ray.origin.y) * inverse_y_direction;
-        if inverse_y_direction < 0.0 { swap(&mut y_min_dist, &mut y_max_dist) }
-
-        let inverse_z_direction = 1.0 / ray.direction.z;
-        let mut z_min_dist = (self.min.z - ray.origin.z) * inverse_z_direction;
-        let mut z_max_dist = (self.max.z - ray.origin.z) * inverse_z_direction;
-        if inverse_z_direction < 0.0 { swap(&mut z_min_dist, &mut z_max_dist) }
-
-        let xy_overlap = x_min_dist < y_max_dist && y_min_dist < x_max_dist;
-        let yz_overlap = y_min_dist < z_max_dist && z_min_dist < y_max_dist;
-        let zx_overlap = z_min_dist < x_max_dist && x_min_dist < z_max_dist;
-
-        xy_overlap && yz_overlap && zx_overlap
+        self.get_intersect(ray, min_dist, max_dist).is_some()
     }
 
     ///
@@ -99,4 +179,56 @@ impl Hittable for AABB {
     fn get_aabb(&self) -> AABB {
         *self
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::vector3::Vector3;
+
+    fn get_box() -> AABB {
+        AABB::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0))
+    }
+
+    fn assert_intersect_eq(intersect: Option<(f64, f64)>, expected: (f64, f64)) {
+        let (entry, exit) = intersect.unwrap();
+        assert!((entry - expected.0).abs() < 1e-12 && (exit - expected.1).abs() < 1e-12, "{:?} != {:?}", (entry, exit), expected);
+    }
+
+    #[test]
+    fn rays_parallel_to_a_slab_hit_only_from_between_it() {
+        let inside = Ray::new(Point3::new(-5.0, 0.5, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_intersect_eq(get_box().get_intersect(inside, 0.0, f64::INFINITY), (4.0, 6.0));
+        // On a side counts as between the slab's sides.
+        let touching = Ray::new(Point3::new(-5.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_intersect_eq(get_box().get_intersect(touching, 0.0, f64::INFINITY), (4.0, 6.0));
+        let outside = Ray::new(Point3::new(-5.0, 1.5, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert!(get_box().get_intersect(outside, 0.0, f64::INFINITY).is_none());
+        assert!(!get_box().is_hit(outside, 0.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn boxes_behind_the_origin_are_missed() {
+        let ray = Ray::new(Point3::new(5.0, 0.2, -0.3), Vector3::new(1.0, 0.1, 0.0));
+        assert!(get_box().get_intersect(ray, 0.0, f64::INFINITY).is_none());
+        // The whole line still crosses the box.
+        assert!(get_box().get_intersect(ray, f64::NEG_INFINITY, f64::INFINITY).is_some());
+    }
+
+    #[test]
+    fn entry_is_clamped_to_min_dist_from_inside() {
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 2.0));
+        assert_intersect_eq(get_box().get_intersect(ray, 0.0, f64::INFINITY), (0.0, 0.5));
+        assert_intersect_eq(get_box().get_intersect(ray, 0.1, f64::INFINITY), (0.1, 0.5));
+    }
+
+    #[test]
+    fn range_clips_the_intersection() {
+        let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_intersect_eq(get_box().get_intersect(ray, 0.0, f64::INFINITY), (4.0, 6.0));
+        assert_intersect_eq(get_box().get_intersect(ray, 4.5, 5.5), (4.5, 5.5));
+        assert_intersect_eq(get_box().get_intersect(ray, 0.0, 4.0), (4.0, 4.0));
+        assert!(get_box().get_intersect(ray, 0.0, 3.9).is_none());
+        assert!(get_box().get_intersect(ray, 6.1, f64::INFINITY).is_none());
+    }
+}
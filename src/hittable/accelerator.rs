@@ -0,0 +1,104 @@
+use crate::hittable::grid::Grid;
+use crate::hittable::Hittable;
+use crate::hittable::kd_tree::KdTree;
+use crate::hittable::linear_bvh::LinearBvh;
+use crate::hittable::linear_bvh::Primitive;
+use crate::hittable::wide_bvh::WideBvh;
+
+use std::rc::Rc;
+
+/// A structure that finds which of many Hittables a Ray hits first without
+/// checking every one of them. A Scene is built around one. Accelerators are
+/// Hittables themselves, so they can also be nested inside other Hittables.
+pub trait Accelerator: Hittable {
+
+    ///
+    /// Returns true if refit keeps the Accelerator correct after the
+    /// Hittables in it have moved. Accelerators that can't be refit have to
+    /// be rebuilt instead.
+    ///
+    /// # Arguments
+    /// * `&self` - The Accelerator.
+    ///
+    fn can_refit(&self) -> bool {
+        false
+    }
+}
+
+/// The kinds of Accelerator a Scene can be built around.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AcceleratorKind {
+    /// A LinearBvh, built with the surface area heuristic. Spheres and Quads
+    /// are stored in it by value. Good all round, and the only kind that can
+    /// be refit.
+    Bvh,
+    /// A WideBvh, built with the surface area heuristic and stored like a
+    /// Bvh, but with up to BVH_WIDTH children per node. Visits fewer nodes
+    /// than a Bvh, but can't be refit.
+    WideBvh,
+    /// A k-d tree, built with the surface area heuristic. Often the fastest to
+    /// trace, but the slowest to build and the largest.
+    KdTree,
+    /// A hierarchical grid. The fastest to build, and good for Hittables that
+    /// are spread evenly through the scene.
+    Grid,
+}
+
+/// Every kind of Accelerator.
+pub const ACCELERATOR_KINDS: [AcceleratorKind; 4] = [
+    AcceleratorKind::Bvh,
+    AcceleratorKind::WideBvh,
+    AcceleratorKind::KdTree,
+    AcceleratorKind::Grid,
+];
+
+impl AcceleratorKind {
+
+    ///
+    /// Returns the AcceleratorKind with the given name, as returned by
+    /// get_name, or None if there is no such kind.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the AcceleratorKind.
+    ///
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACCELERATOR_KINDS.into_iter().find(|kind| kind.get_name() == name)
+    }
+
+    ///
+    /// Returns the name of the AcceleratorKind, as used on the command line.
+    ///
+    /// # Arguments
+    /// * `&self` - The AcceleratorKind.
+    ///
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            AcceleratorKind::Bvh => "bvh",
+            AcceleratorKind::WideBvh => "widebvh",
+            AcceleratorKind::KdTree => "kdtree",
+            AcceleratorKind::Grid => "grid",
+        }
+    }
+
+    ///
+    /// Returns an Accelerator of this kind built over the given Hittables.
+    ///
+    /// # Arguments
+    /// * `&self` - The AcceleratorKind.
+    /// * `hittables` - The Hittables to build the Accelerator over. Must not be empty.
+    /// * `max_leaf_size` - The most Hittables a leaf of a BVH may hold.
+    ///
+    pub fn build(&self, hittables: Vec<Rc<dyn Hittable>>, max_leaf_size: usize) -> Box<dyn Accelerator> {
+        let get_primitives = |hittables: Vec<Rc<dyn Hittable>>| -> Vec<Primitive> {
+            hittables.into_iter()
+                .map(|hittable| hittable.get_primitive().unwrap_or(Primitive::Hittable(hittable)))
+                .collect()
+        };
+        match self {
+            AcceleratorKind::Bvh => Box::new(LinearBvh::new(get_primitives(hittables), max_leaf_size)),
+            AcceleratorKind::WideBvh => Box::new(WideBvh::new(get_primitives(hittables), max_leaf_size)),
+            AcceleratorKind::KdTree => Box::new(KdTree::new(hittables)),
+            AcceleratorKind::Grid => Box::new(Grid::new(hittables)),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::get_distance_past;
+use crate::hittable::get_opaque_intersect;
+use crate::hittable::Hittable;
+use crate::hittable::quad::Quad;
+use crate::material::Material;
+use crate::Ray;
+use crate::texture::SurfacePoint;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use std::rc::Rc;
+
+/// Represents a solid, axis-aligned box in 3D space made up of six Quads.
+pub struct AxisAlignedBox {
+    /// The box's minimum corner.
+    pub min: Point3,
+    /// The box's maximum corner.
+    pub max: Point3,
+    /// The six sides of the box.
+    sides: Vec<Quad>,
+}
+
+impl AxisAlignedBox {
+
+    ///
+    /// Returns an AxisAlignedBox with the given opposite corners. Every side
+    /// of the box shares the given material.
+    ///
+    /// # Arguments
+    /// * `a` - One corner of the box.
+    /// * `b` - The corner of the box opposite to a.
+    /// * `material` - The material of the box's sides.
+    ///
+    pub fn new(a: Point3, b: Point3, material: Material) -> Self {
+
+        let bounds = AABB::from_points(a, b);
+        let min = bounds.min;
+        let max = bounds.max;
+
+        let dx = Vector3::new(max.x - min.x, 0.0, 0.0);
+        let dy = Vector3::new(0.0, max.y - min.y, 0.0);
+        let dz = Vector3::new(0.0, 0.0, max.z - min.z);
+
+        let material = Rc::new(material);
+        let sides = vec![
+            Quad::with_shared_material(Point3::new(min.x, min.y, max.z), dx, dy, Rc::clone(&material)), // front
+            Quad::with_shared_material(Point3::new(max.x, min.y, max.z), -dz, dy, Rc::clone(&material)), // right
+            Quad::with_shared_material(Point3::new(max.x, min.y, min.z), -dx, dy, Rc::clone(&material)), // back
+            Quad::with_shared_material(Point3::new(min.x, min.y, min.z), dz, dy, Rc::clone(&material)), // left
+            Quad::with_shared_material(Point3::new(min.x, max.y, max.z), dx, -dz, Rc::clone(&material)), // top
+            Quad::with_shared_material(Point3::new(min.x, min.y, min.z), dx, dz, material), // bottom
+        ];
+
+        Self {
+            min: min,
+            max: max,
+            sides: sides,
+        }
+    }
+
+    ///
+    /// Returns the (distance, side, (u, v)) of the closest intersection between
+    /// the box's sides and the given Ray that isn't cut out by the box's
+    /// opacity mask. Every side's object_point is relative to the box's minimum
+    /// corner, so solid textures line up across the edges.
+    ///
+    /// # Arguments
+    /// * `&self` - The AxisAlignedBox.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_opaque_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<(f64, &Quad, (f64, f64))> {
+
+        let get_intersect = |min_dist: f64| {
+            let mut closest_distance = max_dist;
+            let mut closest: Option<(f64, &Quad, (f64, f64))> = None;
+            for side in &self.sides {
+                if let Some((distance, uv)) = side.get_intersect(ray, min_dist, closest_distance) {
+                    closest_distance = distance;
+                    closest = Some((distance, side, uv));
+                }
+            }
+            closest
+        };
+
+        // Every side shares the same material.
+        get_opaque_intersect(
+            &self.sides[0].material,
+            ray,
+            min_dist,
+            get_intersect,
+            |(distance, side, uv)| {
+                let point = ray.get_point(distance);
+                SurfacePoint::new(uv, point, point - self.min, side.u.cross(side.v).unit())
+            },
+            |(distance, _, _)| get_distance_past(distance),
+        )
+    }
+}
+
+impl Hittable for AxisAlignedBox {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the AxisAlignedBox and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The AxisAlignedBox intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let (distance, side, uv) = self.get_opaque_intersect(ray, min_dist, max_dist)?;
+        let point = ray.get_point(distance);
+        let mut normal = side.u.cross(side.v).unit();
+        let surface = SurfacePoint::new(uv, point, point - self.min, normal)
+            .with_derivatives(ray, false, |point| side.get_uv(point));
+        let is_front = ray.direction.dot(normal) < 0.0;
+        normal = side.material.get_normal(surface);
+        if !is_front { normal = -normal };
+
+        Some(Hit::new(
+            ray,
+            distance,
+            normal,
+            is_front,
+            side.material.texture.get_color(surface),
+            side.material.get_reflectivity(surface),
+            side.material.get_refraction_index(surface),
+        ))
+    }
+
+    ///
+    /// Returns true if there is a Hit between the AxisAlignedBox and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The AxisAlignedBox intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.get_opaque_intersect(ray, min_dist, max_dist).is_some()
+    }
+
+    ///
+    /// Returns an AABB that surrounds the AxisAlignedBox.
+    ///
+    /// # Arguments
+    /// * `&self` - The AxisAlignedBox.
+    ///
+    fn get_aabb(&self) -> AABB {
+        AABB::new(self.min, self.max).pad()
+    }
+}
@@ -1,17 +1,36 @@
 use crate::hittable::aabb::AABB;
 use crate::hittable::aabb::surrounding_box;
+use crate::hittable::accelerator::Accelerator;
 use crate::hit::Hit;
 use crate::hittable::Hittable;
+use crate::hittable::hittable_list::HittableList;
 use crate::ray::Ray;
+use crate::vector3::Point3;
 
 use rand::Rng;
+use std::cell::Cell;
 use std::rc::Rc;
 use std::cmp::Ordering;
+use std::mem::size_of;
+
+/// The number of bins Hittables are sorted into along each axis when looking
+/// for the best split with the surface area heuristic.
+const SAH_BINS: usize = 16;
+/// The cost of checking a Ray against a node's AABB, relative to the cost of
+/// checking a Ray against a Hittable.
+pub const TRAVERSAL_COST: f64 = 1.0;
+/// The cost of checking a Ray against a Hittable.
+pub const INTERSECTION_COST: f64 = 1.0;
+/// The most Hittables a BVH leaf holds unless a different size is asked for.
+pub const DEFAULT_MAX_LEAF_SIZE: usize = 4;
+/// How many times its SAH cost when it was built a refitted BVH may get to
+/// before it is worth rebuilding.
+pub const MAX_REFIT_COST_GROWTH: f64 = 1.5;
 
 /// Represents a node within a bounding volume hierarchy.
 pub struct BoundingVolumeHierarchyNode {
-    /// This node's AABB.
-    bounding_box: AABB,
+    /// This node's AABB. In a Cell so that it can be refit.
+    bounding_box: Cell<AABB>,
     /// This node's left branch.
     left: Rc<dyn Hittable>,
     /// This node's right branch.
@@ -33,7 +52,7 @@ impl BvhNode {
     ///
     pub fn new(bounding_box: AABB, left: Rc<dyn Hittable>, right: Rc<dyn Hittable>) -> Self {
         Self {
-            bounding_box: bounding_box,
+            bounding_box: Cell::new(bounding_box),
             left: left,
             right: right,
         }
@@ -112,7 +131,192 @@ pub fn construct_bvh(src_hittables: &mut Vec<Rc<dyn Hittable>>, start: usize, en
 }
 
 ///
-/// Returns an Ordering based on the x positions of the given Hittable's AABBs.
+/// Returns a BvhNode that is the root of a BVH built with the surface area
+/// heuristic (SAH). The BVH is constructed from the values in src_hittables
+/// that are between the start and end indices. At each node, every axis is
+/// tried and the split is placed where it is expected to be cheapest to
+/// trace a Ray through. Groups of up to max_leaf_size Hittables become leaves
+/// when checking them all is cheaper than splitting them any further.
+///
+/// # Arguments
+/// * `src_hittables` - The Hittables that will make up the leaves of the hierarchy.
+/// * `start` - Where to start looking for values in src_hittables.
+/// * `end` - Where to stop looking for values in src_hittables.
+/// * `max_leaf_size` - The most Hittables a leaf may hold.
+///
+pub fn construct_sah_bvh_root(src_hittables: &mut [Rc<dyn Hittable>], start: usize, end: usize, max_leaf_size: usize) -> BvhNode {
+
+    let hittables = &mut src_hittables[start..end].to_vec();
+
+    let left: Rc<dyn Hittable>;
+    let right: Rc<dyn Hittable>;
+
+    if hittables.len() == 1 {
+        left = Rc::clone(&hittables[0]);
+        right = Rc::clone(&hittables[0]);
+    } else {
+        // The root always splits, since a BvhNode needs two branches.
+        let (middle, _) = partition_sah(hittables, &|h: &Rc<dyn Hittable>| h.get_aabb());
+        left = construct_sah_bvh(&mut hittables[..middle], max_leaf_size);
+        right = construct_sah_bvh(&mut hittables[middle..], max_leaf_size);
+    }
+
+    let bounding_box = surrounding_box(left.get_aabb(), right.get_aabb());
+    BvhNode::new(bounding_box, left, right)
+}
+
+///
+/// Returns a Rc<dyn Hittable> that is either a leaf or a BvhNode built with
+/// the surface area heuristic. See construct_sah_bvh_root.
+///
+/// # Arguments
+/// * `hittables` - The Hittables that will make up the leaves of the hierarchy.
+/// * `max_leaf_size` - The most Hittables a leaf may hold.
+///
+fn construct_sah_bvh(hittables: &mut [Rc<dyn Hittable>], max_leaf_size: usize) -> Rc<dyn Hittable> {
+
+    if hittables.len() == 1 { return Rc::clone(&hittables[0]); }
+
+    let middle = match get_sah_split(hittables, max_leaf_size) {
+        Some(middle) => middle,
+        None => return Rc::new(HittableList::new(hittables.to_vec())),
+    };
+
+    let left = construct_sah_bvh(&mut hittables[..middle], max_leaf_size);
+    let right = construct_sah_bvh(&mut hittables[middle..], max_leaf_size);
+    let bounding_box = surrounding_box(left.get_aabb(), right.get_aabb());
+    Rc::new(BvhNode::new(bounding_box, left, right))
+}
+
+///
+/// Returns the index to split the given Hittables at with the surface area
+/// heuristic, after reordering them as partition_sah does, or None if they
+/// should stay together as a leaf. Groups of more than max_leaf_size
+/// Hittables are always split.
+///
+/// # Arguments
+/// * `hittables` - The Hittables to split. There must be at least two.
+/// * `max_leaf_size` - The most Hittables a leaf may hold.
+///
+fn get_sah_split(hittables: &mut [Rc<dyn Hittable>], max_leaf_size: usize) -> Option<usize> {
+
+    let (middle, split_cost) = partition_sah(hittables, &|h: &Rc<dyn Hittable>| h.get_aabb());
+    let leaf_cost = hittables.len() as f64 * INTERSECTION_COST;
+
+    if hittables.len() <= max_leaf_size && leaf_cost <= split_cost { None } else { Some(middle) }
+}
+
+///
+/// Returns the (index, cost) of the best split of the given items found with
+/// the binned surface area heuristic, after reordering the items so that
+/// everything before the index goes in the left branch and everything after
+/// goes in the right branch. The cost is the expected cost of tracing a Ray
+/// through a node with those two branches, with each branch treated as a leaf.
+///
+/// The items' centroids are sorted into a fixed number of evenly spaced bins
+/// along each axis, and only the boundaries between bins are tried as split
+/// positions. If every centroid is in the same place, the items are split in
+/// half with an infinite cost.
+///
+/// # Arguments
+/// * `items` - The items to split. There must be at least two.
+/// * `get_aabb` - Returns the AABB of an item.
+///
+pub fn partition_sah<T>(items: &mut [T], get_aabb: &dyn Fn(&T) -> AABB) -> (usize, f64) {
+
+    let boxes: Vec<AABB> = items.iter().map(get_aabb).collect();
+    let mut centroids: Vec<Point3> = boxes.iter().map(|b| b.centroid()).collect();
+    let bounding_box = boxes.iter().copied().reduce(surrounding_box).unwrap();
+    let centroid_box = centroids.iter().map(|&c| AABB::new(c, c)).reduce(surrounding_box).unwrap();
+
+    let bin_of = |centroid: f64, axis: usize| {
+        let extent = centroid_box.max[axis] - centroid_box.min[axis];
+        let bin = ((centroid - centroid_box.min[axis]) / extent * SAH_BINS as f64) as usize;
+        bin.min(SAH_BINS - 1)
+    };
+
+    // The best split as (cost, axis, bin), splitting after the given bin.
+    let mut best: Option<(f64, usize, usize)> = None;
+
+    for axis in 0..3 {
+
+        if centroid_box.max[axis] - centroid_box.min[axis] <= 0.0 { continue; }
+
+        let mut counts = [0_usize; SAH_BINS];
+        let mut bin_boxes: [Option<AABB>; SAH_BINS] = [None; SAH_BINS];
+        for (b, centroid) in boxes.iter().zip(&centroids) {
+            let bin = bin_of(centroid[axis], axis);
+            counts[bin] += 1;
+            bin_boxes[bin] = Some(bin_boxes[bin].map_or(*b, |bin_box| surrounding_box(bin_box, *b)));
+        }
+
+        // The count and surface area of everything to the right of each split.
+        let mut right_counts = [0_usize; SAH_BINS];
+        let mut right_areas = [0.0; SAH_BINS];
+        let mut right_box: Option<AABB> = None;
+        let mut right_count = 0;
+        for bin in (1..SAH_BINS).rev() {
+            right_count += counts[bin];
+            right_box = merge(right_box, bin_boxes[bin]);
+            right_counts[bin - 1] = right_count;
+            right_areas[bin - 1] = right_box.map_or(0.0, |b| b.surface_area());
+        }
+
+        let mut left_box: Option<AABB> = None;
+        let mut left_count = 0;
+        for bin in 0..SAH_BINS - 1 {
+            left_count += counts[bin];
+            left_box = merge(left_box, bin_boxes[bin]);
+            if left_count == 0 || right_counts[bin] == 0 { continue; }
+
+            let left_area = left_box.map_or(0.0, |b| b.surface_area());
+            let cost = TRAVERSAL_COST + INTERSECTION_COST
+                * (left_count as f64 * left_area + right_counts[bin] as f64 * right_areas[bin])
+                / bounding_box.surface_area();
+
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, axis, bin));
+            }
+        }
+    }
+
+    let (cost, axis, split_bin) = match best {
+        Some(b) => b,
+        None => return (items.len() / 2, f64::INFINITY),
+    };
+
+    // Move every item in a bin at or before the split to the front.
+    let mut middle = 0;
+    for i in 0..items.len() {
+        if bin_of(centroids[i][axis], axis) <= split_bin {
+            items.swap(i, middle);
+            centroids.swap(i, middle);
+            middle += 1;
+        }
+    }
+
+    (middle, cost)
+}
+
+///
+/// Returns an AABB that surrounds both of the given AABBs, where None means
+/// an empty box.
+///
+/// # Arguments
+/// * `b1` - One of the surrounded AABBs.
+/// * `b2` - One of the surrounded AABBs.
+///
+fn merge(b1: Option<AABB>, b2: Option<AABB>) -> Option<AABB> {
+    match (b1, b2) {
+        (Some(b1), Some(b2)) => Some(surrounding_box(b1, b2)),
+        (b1, None) => b1,
+        (None, b2) => b2,
+    }
+}
+
+///
+/// Returns an Ordering based on the minimum x positions of the given Hittable's
+/// AABBs. Uses total_cmp so that the Ordering is a total order, even with NaN.
 /// This is a utility function used by construct_bvh_root to sort its list of
 /// Rc<dyn Hittable> pointers.
 ///
@@ -121,17 +325,12 @@ pub fn construct_bvh(src_hittables: &mut Vec<Rc<dyn Hittable>>, start: usize, en
 /// * `h2` - The second Hittable to have its AABB compared.
 ///
 fn bounding_box_compare_x(h1: &Rc<dyn Hittable>, h2: &Rc<dyn Hittable>) -> Ordering {
-    if h1.get_aabb().min.x < h2.get_aabb().min.x {
-        Ordering::Less
-    } else if h1.get_aabb().max.x > h2.get_aabb().max.x {
-        Ordering::Greater
-    } else {
-        Ordering::Equal
-    }
+    h1.get_aabb().min.x.total_cmp(&h2.get_aabb().min.x)
 }
 
 ///
-/// Returns an Ordering based on the y positions of the given Hittable's AABBs.
+/// Returns an Ordering based on the minimum y positions of the given Hittable's
+/// AABBs. Uses total_cmp so that the Ordering is a total order, even with NaN.
 /// This is a utility function used by construct_bvh_root to sort its list of
 /// Rc<dyn Hittable> pointers.
 ///
@@ -140,17 +339,12 @@ fn bounding_box_compare_x(h1: &Rc<dyn Hittable>, h2: &Rc<dyn Hittable>) -> Order
 /// * `h2` - The second Hittable to have its AABB compared.
 ///
 fn bounding_box_compare_y(h1: &Rc<dyn Hittable>, h2: &Rc<dyn Hittable>) -> Ordering {
-    if h1.get_aabb().min.y < h2.get_aabb().min.y {
-        Ordering::Less
-    } else if h1.get_aabb().max.y > h2.get_aabb().max.y {
-        Ordering::Greater
-    } else {
-        Ordering::Equal
-    }
+    h1.get_aabb().min.y.total_cmp(&h2.get_aabb().min.y)
 }
 
 ///
-/// Returns an Ordering based on the z positions of the given Hittable's AABBs.
+/// Returns an Ordering based on the minimum z positions of the given Hittable's
+/// AABBs. Uses total_cmp so that the Ordering is a total order, even with NaN.
 /// This is a utility function used by construct_bvh_root to sort its list of
 /// Rc<dyn Hittable> pointers.
 ///
@@ -159,19 +353,16 @@ fn bounding_box_compare_y(h1: &Rc<dyn Hittable>, h2: &Rc<dyn Hittable>) -> Order
 /// * `h2` - The second Hittable to have its AABB compared.
 ///
 fn bounding_box_compare_z(h1: &Rc<dyn Hittable>, h2: &Rc<dyn Hittable>) -> Ordering {
-    if h1.get_aabb().min.z < h2.get_aabb().min.z {
-        Ordering::Less
-    } else if h1.get_aabb().max.z > h2.get_aabb().max.z {
-        Ordering::Greater
-    } else {
-        Ordering::Equal
-    }
+    h1.get_aabb().min.z.total_cmp(&h2.get_aabb().min.z)
 }
 
 impl Hittable for BvhNode {
 
     ///
     /// Returns Some(Hit) if there is a Hit between the BvhNode and a given Ray.
+    /// The child whose AABB the Ray enters first is checked first. A Hit in
+    /// that child shortens the range checked in the other child, and the
+    /// other child is skipped entirely if the Ray only enters it past the Hit.
     ///
     /// # Arguments
     /// * `&self` - The BvhNode intersected by the Ray.
@@ -181,33 +372,59 @@ impl Hittable for BvhNode {
     ///
     fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
 
-        if !self.bounding_box.is_hit(ray, min_dist, max_dist) { return None; }
-        let mut closest_distance = max_dist;
-        let mut hit: Option<Hit> = None;
+        // There is no need to check this node's own AABB, since a Ray that
+        // misses it misses both children's AABBs as well.
+        let left_entry = self.left.get_aabb().get_intersect(ray, min_dist, max_dist).map(|(entry, _)| entry);
+        // Nodes built from a single Hittable use it for both children.
+        let right_entry = if Rc::ptr_eq(&self.left, &self.right) {
+            None
+        } else {
+            self.right.get_aabb().get_intersect(ray, min_dist, max_dist).map(|(entry, _)| entry)
+        };
 
-        match self.left.get_hit(ray, min_dist, max_dist) {
-            Some(h) => { 
-                if h.distance < closest_distance {
-                    closest_distance = h.distance;
-                    hit = Some(h);
-                }
-            },
-            None => (),
+        let mut children = [(left_entry, &self.left), (right_entry, &self.right)];
+        if let (Some(left), Some(right)) = (left_entry, right_entry) {
+            if right < left { children.swap(0, 1); }
         }
 
-        match self.right.get_hit(ray, min_dist, max_dist) {
-            Some(h) => { 
-                if h.distance < closest_distance {
-                    // closest_distance = h.distance;
-                    hit = Some(h);
-                } 
-            },
-            None => (),
+        let mut closest_distance = max_dist;
+        let mut hit: Option<Hit> = None;
+
+        for (entry, child) in children {
+            match entry {
+                Some(entry) if entry <= closest_distance => {
+                    if let Some(h) = child.get_hit(ray, min_dist, closest_distance) {
+                        closest_distance = h.distance;
+                        hit = Some(h);
+                    }
+                },
+                _ => (),
+            }
         }
 
         hit
     }
 
+    ///
+    /// Returns true if there is a Hit between the BvhNode and a given Ray. Any
+    /// Hit will do, so the children are checked in order and the right child
+    /// is skipped entirely if the left one is hit.
+    ///
+    /// # Arguments
+    /// * `&self` - The BvhNode intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        let is_hit_child = |child: &Rc<dyn Hittable>| {
+            child.get_aabb().is_hit(ray, min_dist, max_dist) && child.is_hit(ray, min_dist, max_dist)
+        };
+
+        // Nodes built from a single Hittable use it for both children.
+        is_hit_child(&self.left) || (!Rc::ptr_eq(&self.left, &self.right) && is_hit_child(&self.right))
+    }
+
     ///
     /// Returns an AABB that surrounds the BvhNode.
     ///
@@ -215,6 +432,151 @@ impl Hittable for BvhNode {
     /// * `&self` - The BvhNode.
     ///
     fn get_aabb(&self) -> AABB {
-        self.bounding_box
+        self.bounding_box.get()
+    }
+
+    ///
+    /// Refits both branches, then sets this node's AABB to surround them.
+    ///
+    /// # Arguments
+    /// * `&self` - The BvhNode.
+    ///
+    fn refit(&self) -> AABB {
+        let left = self.left.refit();
+        let right = if Rc::ptr_eq(&self.left, &self.right) { left } else { self.right.refit() };
+        self.bounding_box.set(surrounding_box(left, right));
+        self.bounding_box.get()
+    }
+
+    ///
+    /// Returns the SAH cost of the BvhNode: the cost of checking its AABB,
+    /// plus the cost of each branch weighted by the chance that a Ray that
+    /// hits this node's AABB also hits the branch's AABB.
+    ///
+    /// # Arguments
+    /// * `&self` - The BvhNode.
+    ///
+    fn get_sah_cost(&self) -> f64 {
+
+        let area = self.get_aabb().surface_area();
+        let weight = |child: &Rc<dyn Hittable>| {
+            if area <= 0.0 { 1.0 } else { child.get_aabb().surface_area() / area }
+        };
+
+        let mut cost = TRAVERSAL_COST + weight(&self.left) * self.left.get_sah_cost();
+        if !Rc::ptr_eq(&self.left, &self.right) {
+            cost += weight(&self.right) * self.right.get_sah_cost();
+        }
+        cost
     }
-}
\ No newline at end of file
+
+    ///
+    /// Returns how many bytes this node and every node below it take up.
+    ///
+    /// # Arguments
+    /// * `&self` - The BvhNode.
+    ///
+    fn get_memory_size(&self) -> usize {
+        let mut size = size_of::<Self>() + self.left.get_memory_size();
+        if !Rc::ptr_eq(&self.left, &self.right) {
+            size += self.right.get_memory_size();
+        }
+        size
+    }
+}
+
+impl Accelerator for BvhNode {
+
+    ///
+    /// Returns true, since a BVH can be refit.
+    ///
+    /// # Arguments
+    /// * `&self` - The BvhNode.
+    ///
+    fn can_refit(&self) -> bool {
+        true
+    }
+}
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::sphere::Sphere;
+    use crate::material::Material;
+    use crate::texture::monochrome::Monochrome;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn get_point(rng: &mut StdRng, size: f64) -> Point3 {
+        Point3::new(rng.gen_range(-size..size), rng.gen_range(-size..size), rng.gen_range(-size..size))
+    }
+
+    /// Returns scattered Spheres, along with clusters of Spheres that share a
+    /// center and so can't be split by the surface area heuristic.
+    fn get_hittables(rng: &mut StdRng) -> Vec<Rc<dyn Hittable>> {
+        let get_sphere = |center: Point3, radius: f64| -> Rc<dyn Hittable> {
+            let material = Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None);
+            Rc::new(Sphere::new(center, radius, material))
+        };
+        let mut hittables: Vec<Rc<dyn Hittable>> = (0..200).map(|_| {
+            let center = get_point(rng, 10.0);
+            get_sphere(center, rng.gen_range(0.1..0.8))
+        }).collect();
+        for _ in 0..5 {
+            let center = get_point(rng, 10.0);
+            hittables.extend((0..7).map(|i| get_sphere(center, 0.2 + 0.1 * i as f64)));
+        }
+        hittables
+    }
+
+    /// Splits the given Hittables the way construct_sah_bvh does, and adds
+    /// the size of every leaf to leaf_sizes.
+    fn get_leaf_sizes(hittables: &mut [Rc<dyn Hittable>], max_leaf_size: usize, leaf_sizes: &mut Vec<usize>) {
+        if hittables.len() == 1 {
+            leaf_sizes.push(1);
+            return;
+        }
+        match get_sah_split(hittables, max_leaf_size) {
+            Some(middle) => {
+                assert!(0 < middle && middle < hittables.len());
+                get_leaf_sizes(&mut hittables[..middle], max_leaf_size, leaf_sizes);
+                get_leaf_sizes(&mut hittables[middle..], max_leaf_size, leaf_sizes);
+            },
+            None => leaf_sizes.push(hittables.len()),
+        }
+    }
+
+    #[test]
+    fn sah_leaves_never_exceed_max_leaf_size() {
+        let mut rng = StdRng::seed_from_u64(33);
+        let mut hittables = get_hittables(&mut rng);
+        for max_leaf_size in [1, 2, 4, 8] {
+            let mut leaf_sizes = vec![];
+            get_leaf_sizes(&mut hittables, max_leaf_size, &mut leaf_sizes);
+            assert_eq!(leaf_sizes.iter().sum::<usize>(), hittables.len());
+            assert!(leaf_sizes.iter().all(|&size| size <= max_leaf_size), "{:?}", leaf_sizes);
+            // The clusters can only be leaves, so leaves fill up when allowed.
+            assert_eq!(leaf_sizes.iter().any(|&size| 1 < size), 1 < max_leaf_size);
+        }
+    }
+
+    #[test]
+    fn sah_and_random_median_bvhs_hit_the_same() {
+        let mut rng = StdRng::seed_from_u64(34);
+        let mut hittables = get_hittables(&mut rng);
+        let length = hittables.len();
+        let median_bvh = construct_bvh_root(&mut hittables, 0, length);
+        let sah_bvhs: Vec<BvhNode> = [1, 4].into_iter().map(|size| construct_sah_bvh_root(&mut hittables, 0, length, size)).collect();
+
+        for _ in 0..500 {
+            let ray = Ray::new(get_point(&mut rng, 15.0), get_point(&mut rng, 1.0));
+            let expected = median_bvh.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance);
+            for bvh in &sah_bvhs {
+                assert_eq!(bvh.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance), expected);
+                assert_eq!(bvh.is_hit(ray, 0.001, 8.0), median_bvh.is_hit(ray, 0.001, 8.0));
+            }
+        }
+    }
+}
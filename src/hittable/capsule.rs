@@ -0,0 +1,262 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::aabb::surrounding_box;
+use crate::hittable::get_distance_past;
+use crate::hittable::get_opaque_intersect;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::polynomial::solve_quadratic;
+use crate::Ray;
+use crate::texture::SurfacePoint;
+use crate::vector3::orthonormal_basis;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use std::f64::consts::PI;
+
+/// Represents a capsule in 3D space. A cylinder with a hemisphere on each end.
+pub struct Capsule {
+    /// The center of the capsule's bottom hemisphere.
+    pub start: Point3,
+    /// The center of the capsule's top hemisphere.
+    pub end: Point3,
+    /// The capsule's radius.
+    pub radius: f64,
+    /// The capsule's material.
+    pub material: Material,
+    /// The unit vector pointing from the start to the end.
+    axis: Vector3,
+    /// The distance from the start to the end.
+    length: f64,
+    /// A unit vector orthogonal to the axis, used as the zero angle for texture coordinates.
+    tangent: Vector3,
+    /// A unit vector orthogonal to both the axis and the tangent.
+    bitangent: Vector3,
+}
+
+impl Capsule {
+
+    ///
+    /// Returns a Capsule constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `start` - The Capsule's start field.
+    /// * `end` - The Capsule's end field.
+    /// * `radius` - The Capsule's radius field.
+    /// * `material` - The Capsule's material field.
+    ///
+    pub fn new(start: Point3, end: Point3, radius: f64, material: Material) -> Self {
+        let (tangent, bitangent) = orthonormal_basis(end - start);
+        Self {
+            start: start,
+            end: end,
+            radius: radius,
+            material: material,
+            axis: (end - start).unit(),
+            length: (end - start).mag(),
+            tangent: tangent,
+            bitangent: bitangent,
+        }
+    }
+
+    ///
+    /// Returns a (f64, f64) tuple of the Capsule's (u, v) texture coordinates at
+    /// the given point.
+    ///
+    /// u is an f64 between [0, 1] that is the angle around the axis
+    /// v is an f64 between [0, 1] that is the height from the bottom of the
+    /// start hemisphere to the top of the end hemisphere
+    ///
+    /// # Arguments
+    /// * `&self` - The Capsule.
+    /// * `point` - The point on the capsule's surface relative to its start.
+    ///
+    fn get_uv(&self, point: Point3) -> (f64, f64) {
+        let height = point.dot(self.axis);
+        let radial = point - height * self.axis;
+        let phi = radial.dot(self.bitangent).atan2(radial.dot(self.tangent)) + PI;
+        let u = phi / (2.0 * PI);
+        let v = (height + self.radius) / (self.length + 2.0 * self.radius);
+        (u, v)
+    }
+
+    ///
+    /// Returns the distance along the given Ray to its nearest intersection
+    /// with the Capsule, if there is one.
+    ///
+    /// # Arguments
+    /// * `&self` - The Capsule.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<f64> {
+
+        let oc = ray.origin - self.start;
+        let d_axis = ray.direction.dot(self.axis);
+        let oc_axis = oc.dot(self.axis);
+        let d_perp = ray.direction - d_axis * self.axis;
+        let oc_perp = oc - oc_axis * self.axis;
+        let radius_squared = self.radius * self.radius;
+
+        let mut closest: Option<f64> = None;
+        let mut closest_distance = max_dist;
+
+        // The cylindrical body, only valid between the two hemispheres.
+        let roots = solve_quadratic(d_perp.mag_squared(), 2.0 * d_perp.dot(oc_perp), oc_perp.mag_squared() - radius_squared);
+        for root in roots {
+            if root < min_dist || closest_distance < root { continue; }
+            let height = oc_axis + root * d_axis;
+            if height < 0.0 || self.length < height { continue; }
+            closest = Some(root);
+            closest_distance = root;
+            break;
+        }
+
+        // The hemispheres, each only valid on its own side of the body.
+        for (center, is_start) in [(self.start, true), (self.end, false)] {
+            let oc = ray.origin - center;
+            let roots = solve_quadratic(ray.direction.mag_squared(), 2.0 * oc.dot(ray.direction), oc.mag_squared() - radius_squared);
+            for root in roots {
+                if root < min_dist || closest_distance < root { continue; }
+                let height = (oc + root * ray.direction).dot(self.axis);
+                if (is_start && 0.0 < height) || (!is_start && height < 0.0) { continue; }
+                closest = Some(root);
+                closest_distance = root;
+                break;
+            }
+        }
+
+        closest
+    }
+
+    ///
+    /// Returns the point sampled SurfacePoint at the given distance along the
+    /// Ray, in the same space as the Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Capsule.
+    /// * `ray` - The intersecting Ray.
+    /// * `distance` - The distance along the Ray to the intersection.
+    ///
+    fn get_surface(&self, ray: Ray, distance: f64) -> SurfacePoint {
+        let point = ray.get_point(distance) - self.start;
+        let spine_point = point.dot(self.axis).clamp(0.0, self.length) * self.axis;
+        let normal = (point - spine_point) / self.radius;
+        SurfacePoint::new(self.get_uv(point), point + self.start, point, normal)
+    }
+
+    ///
+    /// Returns the distance along the given Ray to its nearest intersection
+    /// with the Capsule that isn't cut out by the Capsule's opacity mask.
+    ///
+    /// # Arguments
+    /// * `&self` - The Capsule.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_opaque_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<f64> {
+        get_opaque_intersect(
+            &self.material,
+            ray,
+            min_dist,
+            |min_dist| self.get_intersect(ray, min_dist, max_dist),
+            |distance| self.get_surface(ray, distance),
+            get_distance_past,
+        )
+    }
+}
+
+impl Hittable for Capsule {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Capsule and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Capsule intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let distance = self.get_opaque_intersect(ray, min_dist, max_dist)?;
+        let surface = self.get_surface(ray, distance);
+        let is_front = ray.direction.dot(surface.normal) < 0.0;
+        let surface = surface.with_derivatives(ray, true, |point| self.get_uv(point - self.start));
+        let mut normal = self.material.get_normal(surface);
+        if !is_front { normal = -normal };
+
+        Some(Hit::new(
+            ray,
+            distance,
+            normal,
+            is_front,
+            self.material.texture.get_color(surface),
+            self.material.get_reflectivity(surface),
+            self.material.get_refraction_index(surface),
+        ))
+    }
+
+    ///
+    /// Returns true if there is a Hit between the Capsule and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Capsule intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.get_opaque_intersect(ray, min_dist, max_dist).is_some()
+    }
+
+    ///
+    /// Returns an AABB that surrounds the Capsule.
+    ///
+    /// # Arguments
+    /// * `&self` - The Capsule.
+    ///
+    fn get_aabb(&self) -> AABB {
+        let radius_vector = Vector3::new(self.radius, self.radius, self.radius);
+        let start_box = AABB::new(self.start - radius_vector, self.start + radius_vector);
+        let end_box = AABB::new(self.end - radius_vector, self.end + radius_vector);
+        surrounding_box(start_box, end_box)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::texture::monochrome::Monochrome;
+
+    fn assert_hit(hit: Option<Hit>, distance: f64, normal: Vector3) {
+        let hit = hit.unwrap();
+        assert!((hit.distance - distance).abs() < 1e-9, "{} != {}", hit.distance, distance);
+        assert!((hit.normal - normal).mag() < 1e-9);
+        assert!(hit.is_front);
+    }
+
+    fn get_material() -> Material {
+        Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None)
+    }
+
+    #[test]
+    fn rays_hit_the_side_and_caps() {
+        let capsule = Capsule::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 2.0, 0.0), 1.0, get_material());
+
+        let side_ray = Ray::new(Point3::new(5.0, 1.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+        assert_hit(capsule.get_hit(side_ray, 0.0, f64::INFINITY), 4.0, Vector3::new(1.0, 0.0, 0.0));
+
+        let top_ray = Ray::new(Point3::new(0.0, 6.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        assert_hit(capsule.get_hit(top_ray, 0.0, f64::INFINITY), 3.0, Vector3::new(0.0, 1.0, 0.0));
+        let bottom_ray = Ray::new(Point3::new(0.0, -6.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        assert_hit(capsule.get_hit(bottom_ray, 0.0, f64::INFINITY), 5.0, Vector3::new(0.0, -1.0, 0.0));
+
+        // Beside the end cap, where the cylinder would have been hit.
+        assert!(!capsule.is_hit(Ray::new(Point3::new(5.0, 2.9, 0.9), Vector3::new(-1.0, 0.0, 0.0)), 0.0, f64::INFINITY));
+    }
+}
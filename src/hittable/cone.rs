@@ -0,0 +1,269 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::aabb::surrounding_box;
+use crate::hittable::get_distance_past;
+use crate::hittable::get_opaque_intersect;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::polynomial::solve_quadratic;
+use crate::Ray;
+use crate::texture::SurfacePoint;
+use crate::vector3::orthonormal_basis;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use std::f64::consts::PI;
+
+/// Represents a finite cone in 3D space, closed off by a flat cap at its base.
+pub struct Cone {
+    /// The center of the cone's circular base.
+    pub base: Point3,
+    /// The tip of the cone.
+    pub apex: Point3,
+    /// The radius of the cone's base.
+    pub radius: f64,
+    /// The cone's material.
+    pub material: Material,
+    /// The unit vector pointing from the apex to the base.
+    axis: Vector3,
+    /// The distance from the apex to the base.
+    height: f64,
+    /// The squared cosine of the angle between the axis and the cone's side.
+    cos_squared: f64,
+    /// A unit vector orthogonal to the axis, used as the zero angle for texture coordinates.
+    tangent: Vector3,
+    /// A unit vector orthogonal to both the axis and the tangent.
+    bitangent: Vector3,
+}
+
+impl Cone {
+
+    ///
+    /// Returns a Cone constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `base` - The Cone's base field.
+    /// * `apex` - The Cone's apex field.
+    /// * `radius` - The Cone's radius field.
+    /// * `material` - The Cone's material field.
+    ///
+    pub fn new(base: Point3, apex: Point3, radius: f64, material: Material) -> Self {
+        let height = (base - apex).mag();
+        let (tangent, bitangent) = orthonormal_basis(base - apex);
+        Self {
+            base: base,
+            apex: apex,
+            radius: radius,
+            material: material,
+            axis: (base - apex).unit(),
+            height: height,
+            cos_squared: (height * height) / (height * height + radius * radius),
+            tangent: tangent,
+            bitangent: bitangent,
+        }
+    }
+
+    ///
+    /// Returns a (f64, f64) tuple of the Cone's (u, v) texture coordinates at
+    /// the given point.
+    ///
+    /// On the side, u is the angle around the axis and v is the distance from
+    /// the apex along the axis. On the base, u is the angle around the axis and
+    /// v is the distance from the base's center. All values are between [0, 1].
+    ///
+    /// # Arguments
+    /// * `&self` - The Cone.
+    /// * `point` - The point on the cone's surface relative to its apex.
+    /// * `is_cap` - True if the point is on the cone's base.
+    ///
+    fn get_uv(&self, point: Point3, is_cap: bool) -> (f64, f64) {
+        let height = point.dot(self.axis);
+        let radial = point - height * self.axis;
+        let phi = radial.dot(self.bitangent).atan2(radial.dot(self.tangent)) + PI;
+        let u = phi / (2.0 * PI);
+        let v = if is_cap { radial.mag() / self.radius } else { height / self.height };
+        (u, v)
+    }
+
+    ///
+    /// Returns the (distance, outward normal, is_cap) of the nearest
+    /// intersection between the Cone and the given Ray, if there is one.
+    ///
+    /// # Arguments
+    /// * `&self` - The Cone.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<(f64, Vector3, bool)> {
+
+        let co = ray.origin - self.apex;
+        let d_axis = ray.direction.dot(self.axis);
+        let co_axis = co.dot(self.axis);
+
+        // The closest (distance, outward normal, is_cap) found so far.
+        let mut closest: Option<(f64, Vector3, bool)> = None;
+        let mut closest_distance = max_dist;
+
+        let a = d_axis * d_axis - self.cos_squared * ray.direction.mag_squared();
+        let b = 2.0 * (d_axis * co_axis - self.cos_squared * ray.direction.dot(co));
+        let c = co_axis * co_axis - self.cos_squared * co.mag_squared();
+
+        for root in solve_quadratic(a, b, c) {
+            if root < min_dist || closest_distance < root { continue; }
+            // Reject the mirrored cone on the other side of the apex.
+            let height = co_axis + root * d_axis;
+            if height < 0.0 || self.height < height { continue; }
+            let point = co + root * ray.direction;
+            let normal = (self.cos_squared * point - height * self.axis).unit();
+            closest = Some((root, normal, false));
+            closest_distance = root;
+            break;
+        }
+
+        if d_axis.abs() > 1e-8 {
+            let root = (self.height - co_axis) / d_axis;
+            if min_dist <= root && root <= closest_distance {
+                let radial = co + root * ray.direction - self.height * self.axis;
+                if radial.mag_squared() <= self.radius * self.radius {
+                    closest = Some((root, self.axis, true));
+                }
+            }
+        }
+
+        closest
+    }
+
+    ///
+    /// Returns the point sampled SurfacePoint of an intersection from
+    /// get_intersect, in the same space as the Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Cone.
+    /// * `ray` - The intersecting Ray.
+    /// * `intersect` - The (distance, outward normal, is_cap) of the intersection.
+    ///
+    fn get_surface(&self, ray: Ray, (distance, normal, is_cap): (f64, Vector3, bool)) -> SurfacePoint {
+        let point = ray.get_point(distance);
+        SurfacePoint::new(self.get_uv(point - self.apex, is_cap), point, point - self.apex, normal)
+    }
+
+    ///
+    /// Returns the (distance, outward normal, is_cap) of the nearest
+    /// intersection between the Cone and the given Ray that isn't cut out by
+    /// the Cone's opacity mask.
+    ///
+    /// # Arguments
+    /// * `&self` - The Cone.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_opaque_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<(f64, Vector3, bool)> {
+        get_opaque_intersect(
+            &self.material,
+            ray,
+            min_dist,
+            |min_dist| self.get_intersect(ray, min_dist, max_dist),
+            |intersect| self.get_surface(ray, intersect),
+            |(distance, _, _)| get_distance_past(distance),
+        )
+    }
+}
+
+impl Hittable for Cone {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Cone and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Cone intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let intersect = self.get_opaque_intersect(ray, min_dist, max_dist)?;
+        let (distance, outward_normal, is_cap) = intersect;
+        let surface = self.get_surface(ray, intersect)
+            .with_derivatives(ray, true, |point| self.get_uv(point - self.apex, is_cap));
+        let is_front = ray.direction.dot(outward_normal) < 0.0;
+        let mut normal = self.material.get_normal(surface);
+        if !is_front { normal = -normal };
+
+        Some(Hit::new(
+            ray,
+            distance,
+            normal,
+            is_front,
+            self.material.texture.get_color(surface),
+            self.material.get_reflectivity(surface),
+            self.material.get_refraction_index(surface),
+        ))
+    }
+
+    ///
+    /// Returns true if there is a Hit between the Cone and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Cone intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.get_opaque_intersect(ray, min_dist, max_dist).is_some()
+    }
+
+    ///
+    /// Returns an AABB that surrounds the Cone.
+    ///
+    /// # Arguments
+    /// * `&self` - The Cone.
+    ///
+    fn get_aabb(&self) -> AABB {
+        let a = self.axis;
+        let extent = Vector3::new(
+            self.radius * (1.0 - a.x * a.x).max(0.0).sqrt(),
+            self.radius * (1.0 - a.y * a.y).max(0.0).sqrt(),
+            self.radius * (1.0 - a.z * a.z).max(0.0).sqrt(),
+        );
+        let base_box = AABB::new(self.base - extent, self.base + extent);
+        surrounding_box(base_box, AABB::new(self.apex, self.apex)).pad()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::texture::monochrome::Monochrome;
+
+    fn assert_hit(hit: Option<Hit>, distance: f64, normal: Vector3) {
+        let hit = hit.unwrap();
+        assert!((hit.distance - distance).abs() < 1e-9, "{} != {}", hit.distance, distance);
+        assert!((hit.normal - normal).mag() < 1e-9);
+        assert!(hit.is_front);
+    }
+
+    fn get_material() -> Material {
+        Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None)
+    }
+
+    #[test]
+    fn rays_hit_the_side_and_cap() {
+        let cone = Cone::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 2.0, 0.0), 1.0, get_material());
+
+        // Halfway up, the side is half as far from the axis as the base's edge.
+        let side_ray = Ray::new(Point3::new(5.0, 1.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+        assert_hit(cone.get_hit(side_ray, 0.0, f64::INFINITY), 4.5, Vector3::new(2.0, 1.0, 0.0).unit());
+
+        let cap_ray = Ray::new(Point3::new(0.3, -5.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        assert_hit(cone.get_hit(cap_ray, 0.0, f64::INFINITY), 5.0, Vector3::new(0.0, -1.0, 0.0));
+
+        // The mirrored cone above the apex isn't part of the Cone.
+        assert!(!cone.is_hit(Ray::new(Point3::new(5.0, 3.0, 0.0), Vector3::new(-1.0, 0.0, 0.0)), 0.0, f64::INFINITY));
+    }
+}
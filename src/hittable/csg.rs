@@ -0,0 +1,241 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::aabb::surrounding_box;
+use crate::hittable::Hittable;
+use crate::Ray;
+use crate::vector3::Point3;
+
+use std::rc::Rc;
+
+/// The boolean operations that can combine two solids.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CsgOperation {
+    /// Everything inside either solid.
+    Union,
+    /// Everything inside both solids.
+    Intersection,
+    /// Everything inside the left solid but not the right solid.
+    Difference,
+}
+
+impl CsgOperation {
+
+    ///
+    /// Returns true if a point is inside the combined solid.
+    ///
+    /// # Arguments
+    /// * `&self` - The operation.
+    /// * `is_inside_left` - True if the point is inside the left solid.
+    /// * `is_inside_right` - True if the point is inside the right solid.
+    ///
+    fn is_inside(&self, is_inside_left: bool, is_inside_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => is_inside_left || is_inside_right,
+            CsgOperation::Intersection => is_inside_left && is_inside_right,
+            CsgOperation::Difference => is_inside_left && !is_inside_right,
+        }
+    }
+}
+
+/// Represents a solid made by combining two closed Hittables with a boolean operation.
+pub struct ConstructiveSolidGeometry {
+    /// How the two solids are combined.
+    pub operation: CsgOperation,
+    /// The left solid.
+    pub left: Rc<dyn Hittable>,
+    /// The right solid.
+    pub right: Rc<dyn Hittable>,
+}
+
+/// Acronym for ConstructiveSolidGeometry.
+pub type Csg = ConstructiveSolidGeometry;
+
+impl Csg {
+
+    ///
+    /// Returns a Csg constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `operation` - The Csg's operation field.
+    /// * `left` - The Csg's left field.
+    /// * `right` - The Csg's right field.
+    ///
+    pub fn new(operation: CsgOperation, left: Rc<dyn Hittable>, right: Rc<dyn Hittable>) -> Self {
+        Self {
+            operation: operation,
+            left: left,
+            right: right,
+        }
+    }
+}
+
+impl Hittable for Csg {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Csg and a given Ray.
+    /// The Hit is the first boundary of the combined solid within the range,
+    /// which may belong to either child.
+    ///
+    /// # Arguments
+    /// * `&self` - The Csg intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        if !self.get_aabb().is_hit(ray, min_dist, max_dist) { return None; }
+
+        self.get_intervals(ray)
+            .into_iter()
+            .flat_map(|(entry, exit)| [entry, exit])
+            .find(|hit| min_dist <= hit.distance && hit.distance <= max_dist)
+    }
+
+    ///
+    /// Returns every interval along the given Ray where the Ray is inside the
+    /// Csg. The children's intervals are merged by sweeping over all of their
+    /// boundaries in order and tracking which children the Ray is inside of.
+    ///
+    /// Hit normals already face against the Ray, so a child's boundary can be
+    /// reused as is. Only is_front changes, since leaving the right solid of a
+    /// difference means entering the combined solid.
+    ///
+    /// # Arguments
+    /// * `&self` - The Csg intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    ///
+    fn get_intervals(&self, ray: Ray) -> Vec<(Hit, Hit)> {
+
+        // Every boundary as (hit, is_entry, is_left).
+        let mut boundaries: Vec<(Hit, bool, bool)> = vec![];
+        for (entry, exit) in self.left.get_intervals(ray) {
+            boundaries.push((entry, true, true));
+            boundaries.push((exit, false, true));
+        }
+        for (entry, exit) in self.right.get_intervals(ray) {
+            boundaries.push((entry, true, false));
+            boundaries.push((exit, false, false));
+        }
+        boundaries.sort_by(|a, b| a.0.distance.total_cmp(&b.0.distance));
+
+        let mut intervals: Vec<(Hit, Hit)> = vec![];
+        let mut is_inside_left = false;
+        let mut is_inside_right = false;
+        let mut entry: Option<Hit> = None;
+
+        for (hit, is_entry, is_left) in boundaries {
+
+            let was_inside = self.operation.is_inside(is_inside_left, is_inside_right);
+            if is_left { is_inside_left = is_entry } else { is_inside_right = is_entry }
+            let is_inside = self.operation.is_inside(is_inside_left, is_inside_right);
+
+            if !was_inside && is_inside {
+                let mut h = hit;
+                h.is_front = true;
+                entry = Some(h);
+            } else if was_inside && !is_inside {
+                if let Some(e) = entry.take() {
+                    let mut h = hit;
+                    h.is_front = false;
+                    intervals.push((e, h));
+                }
+            }
+        }
+
+        intervals
+    }
+
+    ///
+    /// Returns an AABB that surrounds the Csg.
+    ///
+    /// # Arguments
+    /// * `&self` - The Csg.
+    ///
+    fn get_aabb(&self) -> AABB {
+        let left = self.left.get_aabb();
+        let right = self.right.get_aabb();
+        match self.operation {
+            CsgOperation::Union => surrounding_box(left, right),
+            CsgOperation::Intersection => AABB::new(
+                Point3::new(left.min.x.max(right.min.x), left.min.y.max(right.min.y), left.min.z.max(right.min.z)),
+                Point3::new(left.max.x.min(right.max.x), left.max.y.min(right.max.y), left.max.z.min(right.max.z)),
+            ),
+            CsgOperation::Difference => left,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::sphere::Sphere;
+    use crate::material::Material;
+    use crate::texture::monochrome::Monochrome;
+    use crate::vector3::Vector3;
+
+    /// Two unit spheres overlapping between x = -0.5 and x = 0.5.
+    fn get_csg(operation: CsgOperation) -> Csg {
+        let get_sphere = |x: f64| -> Rc<dyn Hittable> {
+            let material = Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None);
+            Rc::new(Sphere::new(Point3::new(x, 0.0, 0.0), 1.0, material))
+        };
+        Csg::new(operation, get_sphere(-0.5), get_sphere(0.5))
+    }
+
+    fn get_distances(intervals: Vec<(Hit, Hit)>) -> Vec<(f64, f64)> {
+        intervals.iter().map(|(entry, exit)| {
+            assert!(entry.is_front && !exit.is_front);
+            (entry.distance, exit.distance)
+        }).collect()
+    }
+
+    fn assert_distances_eq(a: Vec<(f64, f64)>, b: Vec<(f64, f64)>) {
+        assert_eq!(a.len(), b.len(), "{:?} != {:?}", a, b);
+        for (a, b) in a.iter().zip(&b) {
+            assert!((a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn operations_merge_overlapping_intervals() {
+
+        // The left sphere spans [3.5, 5.5] along the Ray and the right sphere [4.5, 6.5].
+        let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        for (operation, expected) in [
+            (CsgOperation::Union, (3.5, 6.5)),
+            (CsgOperation::Intersection, (4.5, 5.5)),
+            (CsgOperation::Difference, (3.5, 4.5)),
+        ] {
+            let csg = get_csg(operation);
+            assert_distances_eq(get_distances(csg.get_intervals(ray)), vec![expected]);
+
+            let entry = csg.get_hit(ray, 0.0, f64::INFINITY).unwrap();
+            assert!((entry.distance - expected.0).abs() < 1e-6 && entry.is_front);
+            let exit = csg.get_hit(ray, expected.0 + 0.1, f64::INFINITY).unwrap();
+            assert!((exit.distance - expected.1).abs() < 1e-6 && !exit.is_front);
+            assert!(csg.get_hit(ray, expected.1 + 0.1, f64::INFINITY).is_none());
+        }
+
+        // Spheres that don't overlap have no intersection.
+        let ray = Ray::new(Point3::new(-5.0, 0.9, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert!(get_csg(CsgOperation::Intersection).get_hit(ray, 0.0, f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn ray_starting_inside_difference_hits_its_exit() {
+
+        // Inside the left sphere, but not the right one.
+        let ray = Ray::new(Point3::new(-1.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let csg = get_csg(CsgOperation::Difference);
+
+        // The whole line is searched, so the entry is behind the Ray's origin.
+        assert_distances_eq(get_distances(csg.get_intervals(ray)), vec![(-0.5, 0.5)]);
+
+        let exit = csg.get_hit(ray, 0.0, f64::INFINITY).unwrap();
+        assert!((exit.distance - 0.5).abs() < 1e-6);
+        assert!(!exit.is_front);
+    }
+}
@@ -0,0 +1,267 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::get_distance_past;
+use crate::hittable::get_opaque_intersect;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::polynomial::solve_quadratic;
+use crate::Ray;
+use crate::texture::SurfacePoint;
+use crate::vector3::orthonormal_basis;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use std::f64::consts::PI;
+
+/// Represents a finite cylinder in 3D space, closed off by a flat cap on each end.
+pub struct Cylinder {
+    /// The center of the cylinder's bottom cap.
+    pub base: Point3,
+    /// The center of the cylinder's top cap.
+    pub top: Point3,
+    /// The cylinder's radius.
+    pub radius: f64,
+    /// The cylinder's material.
+    pub material: Material,
+    /// The unit vector pointing from the base to the top.
+    axis: Vector3,
+    /// The distance from the base to the top.
+    height: f64,
+    /// A unit vector orthogonal to the axis, used as the zero angle for texture coordinates.
+    tangent: Vector3,
+    /// A unit vector orthogonal to both the axis and the tangent.
+    bitangent: Vector3,
+}
+
+impl Cylinder {
+
+    ///
+    /// Returns a Cylinder constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `base` - The Cylinder's base field.
+    /// * `top` - The Cylinder's top field.
+    /// * `radius` - The Cylinder's radius field.
+    /// * `material` - The Cylinder's material field.
+    ///
+    pub fn new(base: Point3, top: Point3, radius: f64, material: Material) -> Self {
+        let (tangent, bitangent) = orthonormal_basis(top - base);
+        Self {
+            base: base,
+            top: top,
+            radius: radius,
+            material: material,
+            axis: (top - base).unit(),
+            height: (top - base).mag(),
+            tangent: tangent,
+            bitangent: bitangent,
+        }
+    }
+
+    ///
+    /// Returns a (f64, f64) tuple of the Cylinder's (u, v) texture coordinates at
+    /// the given point.
+    ///
+    /// On the side, u is the angle around the axis and v is the height along it.
+    /// On the caps, u is the angle around the axis and v is the distance from
+    /// the cap's center. All values are between [0, 1].
+    ///
+    /// # Arguments
+    /// * `&self` - The Cylinder.
+    /// * `point` - The point on the cylinder's surface relative to its base.
+    /// * `is_cap` - True if the point is on one of the cylinder's caps.
+    ///
+    fn get_uv(&self, point: Point3, is_cap: bool) -> (f64, f64) {
+        let height = point.dot(self.axis);
+        let radial = point - height * self.axis;
+        let phi = radial.dot(self.bitangent).atan2(radial.dot(self.tangent)) + PI;
+        let u = phi / (2.0 * PI);
+        let v = if is_cap { radial.mag() / self.radius } else { height / self.height };
+        (u, v)
+    }
+
+    ///
+    /// Returns the (distance, outward normal, is_cap) of the nearest
+    /// intersection between the Cylinder and the given Ray, if there is one.
+    ///
+    /// # Arguments
+    /// * `&self` - The Cylinder.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<(f64, Vector3, bool)> {
+
+        let oc = ray.origin - self.base;
+        let d_axis = ray.direction.dot(self.axis);
+        let oc_axis = oc.dot(self.axis);
+        let d_perp = ray.direction - d_axis * self.axis;
+        let oc_perp = oc - oc_axis * self.axis;
+
+        // The closest (distance, outward normal, is_cap) found so far.
+        let mut closest: Option<(f64, Vector3, bool)> = None;
+        let mut closest_distance = max_dist;
+
+        let a = d_perp.mag_squared();
+        let b = 2.0 * d_perp.dot(oc_perp);
+        let c = oc_perp.mag_squared() - self.radius * self.radius;
+
+        for root in solve_quadratic(a, b, c) {
+            if root < min_dist || closest_distance < root { continue; }
+            let height = oc_axis + root * d_axis;
+            if height < 0.0 || self.height < height { continue; }
+            let normal = (oc_perp + root * d_perp) / self.radius;
+            closest = Some((root, normal, false));
+            closest_distance = root;
+            break;
+        }
+
+        if d_axis.abs() > 1e-8 {
+            for (cap_height, cap_normal) in [(0.0, -self.axis), (self.height, self.axis)] {
+                let root = (cap_height - oc_axis) / d_axis;
+                if root < min_dist || closest_distance < root { continue; }
+                let radial = oc_perp + root * d_perp;
+                if self.radius * self.radius < radial.mag_squared() { continue; }
+                closest = Some((root, cap_normal, true));
+                closest_distance = root;
+            }
+        }
+
+        closest
+    }
+
+    ///
+    /// Returns the point sampled SurfacePoint of an intersection from
+    /// get_intersect, in the same space as the Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Cylinder.
+    /// * `ray` - The intersecting Ray.
+    /// * `intersect` - The (distance, outward normal, is_cap) of the intersection.
+    ///
+    fn get_surface(&self, ray: Ray, (distance, normal, is_cap): (f64, Vector3, bool)) -> SurfacePoint {
+        let point = ray.get_point(distance);
+        SurfacePoint::new(self.get_uv(point - self.base, is_cap), point, point - self.base, normal)
+    }
+
+    ///
+    /// Returns the (distance, outward normal, is_cap) of the nearest
+    /// intersection between the Cylinder and the given Ray that isn't cut out by
+    /// the Cylinder's opacity mask.
+    ///
+    /// # Arguments
+    /// * `&self` - The Cylinder.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_opaque_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<(f64, Vector3, bool)> {
+        get_opaque_intersect(
+            &self.material,
+            ray,
+            min_dist,
+            |min_dist| self.get_intersect(ray, min_dist, max_dist),
+            |intersect| self.get_surface(ray, intersect),
+            |(distance, _, _)| get_distance_past(distance),
+        )
+    }
+}
+
+impl Hittable for Cylinder {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Cylinder and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Cylinder intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let intersect = self.get_opaque_intersect(ray, min_dist, max_dist)?;
+        let (distance, outward_normal, is_cap) = intersect;
+        let surface = self.get_surface(ray, intersect)
+            .with_derivatives(ray, true, |point| self.get_uv(point - self.base, is_cap));
+        let is_front = ray.direction.dot(outward_normal) < 0.0;
+        let mut normal = self.material.get_normal(surface);
+        if !is_front { normal = -normal };
+
+        Some(Hit::new(
+            ray,
+            distance,
+            normal,
+            is_front,
+            self.material.texture.get_color(surface),
+            self.material.get_reflectivity(surface),
+            self.material.get_refraction_index(surface),
+        ))
+    }
+
+    ///
+    /// Returns true if there is a Hit between the Cylinder and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Cylinder intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.get_opaque_intersect(ray, min_dist, max_dist).is_some()
+    }
+
+    ///
+    /// Returns an AABB that surrounds the Cylinder.
+    ///
+    /// # Arguments
+    /// * `&self` - The Cylinder.
+    ///
+    fn get_aabb(&self) -> AABB {
+        let a = self.axis;
+        let extent = Vector3::new(
+            self.radius * (1.0 - a.x * a.x).max(0.0).sqrt(),
+            self.radius * (1.0 - a.y * a.y).max(0.0).sqrt(),
+            self.radius * (1.0 - a.z * a.z).max(0.0).sqrt(),
+        );
+        let bounds = AABB::from_points(self.base, self.top);
+        AABB::new(bounds.min - extent, bounds.max + extent).pad()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::texture::monochrome::Monochrome;
+
+    fn assert_hit(hit: Option<Hit>, distance: f64, normal: Vector3) {
+        let hit = hit.unwrap();
+        assert!((hit.distance - distance).abs() < 1e-9, "{} != {}", hit.distance, distance);
+        assert!((hit.normal - normal).mag() < 1e-9);
+        assert!(hit.is_front);
+    }
+
+    fn get_material() -> Material {
+        Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None)
+    }
+
+    #[test]
+    fn rays_hit_the_side_and_caps() {
+        let cylinder = Cylinder::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 2.0, 0.0), 1.0, get_material());
+
+        let side_ray = Ray::new(Point3::new(5.0, 1.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+        assert_hit(cylinder.get_hit(side_ray, 0.0, f64::INFINITY), 4.0, Vector3::new(1.0, 0.0, 0.0));
+
+        let top_ray = Ray::new(Point3::new(0.5, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        assert_hit(cylinder.get_hit(top_ray, 0.0, f64::INFINITY), 3.0, Vector3::new(0.0, 1.0, 0.0));
+        let bottom_ray = Ray::new(Point3::new(0.5, -5.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        assert_hit(cylinder.get_hit(bottom_ray, 0.0, f64::INFINITY), 5.0, Vector3::new(0.0, -1.0, 0.0));
+
+        // Past the end of the side, and past the edge of the caps.
+        assert!(!cylinder.is_hit(Ray::new(Point3::new(5.0, 2.5, 0.0), Vector3::new(-1.0, 0.0, 0.0)), 0.0, f64::INFINITY));
+        assert!(!cylinder.is_hit(Ray::new(Point3::new(1.5, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0)), 0.0, f64::INFINITY));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::get_distance_past;
+use crate::hittable::get_opaque_intersect;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::Ray;
+use crate::texture::SurfacePoint;
+use crate::vector3::orthonormal_basis;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use std::f64::consts::PI;
+
+/// Represents a flat, circular disk in 3D space.
+pub struct Disk {
+    /// The disk's center.
+    pub center: Point3,
+    /// The disk's unit normal.
+    pub normal: Vector3,
+    /// The disk's radius.
+    pub radius: f64,
+    /// The disk's material.
+    pub material: Material,
+    /// A unit vector in the disk's plane, used as the zero angle for texture coordinates.
+    tangent: Vector3,
+    /// A unit vector in the disk's plane orthogonal to the tangent.
+    bitangent: Vector3,
+}
+
+impl Disk {
+
+    ///
+    /// Returns a Disk constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `center` - The Disk's center field.
+    /// * `normal` - The Disk's normal field. Does not need to be a unit vector.
+    /// * `radius` - The Disk's radius field.
+    /// * `material` - The Disk's material field.
+    ///
+    pub fn new(center: Point3, normal: Vector3, radius: f64, material: Material) -> Self {
+        let (tangent, bitangent) = orthonormal_basis(normal);
+        Self {
+            center: center,
+            normal: normal.unit(),
+            radius: radius,
+            material: material,
+            tangent: tangent,
+            bitangent: bitangent,
+        }
+    }
+
+    ///
+    /// Returns a (f64, f64) tuple of the Disk's (u, v) texture coordinates at
+    /// the given point.
+    ///
+    /// u is an f64 between [0, 1] that is the angle around the disk's normal
+    /// v is an f64 between [0, 1] that is the distance from the disk's center
+    ///
+    /// # Arguments
+    /// * `&self` - The Disk.
+    /// * `point` - The point on the disk's surface relative to its center.
+    ///
+    fn get_uv(&self, point: Point3) -> (f64, f64) {
+        let phi = point.dot(self.bitangent).atan2(point.dot(self.tangent)) + PI;
+        let u = phi / (2.0 * PI);
+        let v = point.mag() / self.radius;
+        (u, v)
+    }
+
+    ///
+    /// Returns the (distance, point) of the intersection between the Disk and
+    /// the given Ray, if there is one. The point is relative to the Disk's center.
+    ///
+    /// # Arguments
+    /// * `&self` - The Disk.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<(f64, Vector3)> {
+
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < 1e-8 { return None; }
+
+        let distance = (self.center - ray.origin).dot(self.normal) / denominator;
+        if distance < min_dist || max_dist < distance { return None; }
+
+        let point = ray.get_point(distance) - self.center;
+        if self.radius * self.radius < point.mag_squared() { return None; }
+
+        Some((distance, point))
+    }
+
+    ///
+    /// Returns the (distance, point) of the intersection between the Disk and
+    /// the given Ray, if there is one that isn't cut out by the Disk's opacity
+    /// mask. The point is relative to the Disk's center.
+    ///
+    /// # Arguments
+    /// * `&self` - The Disk.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_opaque_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<(f64, Vector3)> {
+        get_opaque_intersect(
+            &self.material,
+            ray,
+            min_dist,
+            |min_dist| self.get_intersect(ray, min_dist, max_dist),
+            |(_, point)| SurfacePoint::new(self.get_uv(point), point + self.center, point, self.normal),
+            |(distance, _)| get_distance_past(distance),
+        )
+    }
+}
+
+impl Hittable for Disk {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Disk and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Disk intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let (distance, point) = self.get_opaque_intersect(ray, min_dist, max_dist)?;
+
+        let surface = SurfacePoint::new(self.get_uv(point), point + self.center, point, self.normal)
+            .with_derivatives(ray, true, |point| self.get_uv(point - self.center));
+        let is_front = ray.direction.dot(self.normal) < 0.0;
+        let mut normal = self.material.get_normal(surface);
+        if !is_front { normal = -normal };
+
+        Some(Hit::new(
+            ray,
+            distance,
+            normal,
+            is_front,
+            self.material.texture.get_color(surface),
+            self.material.get_reflectivity(surface),
+            self.material.get_refraction_index(surface),
+        ))
+    }
+
+    ///
+    /// Returns true if there is a Hit between the Disk and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Disk intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.get_opaque_intersect(ray, min_dist, max_dist).is_some()
+    }
+
+    ///
+    /// Returns an AABB that surrounds the Disk.
+    ///
+    /// # Arguments
+    /// * `&self` - The Disk.
+    ///
+    fn get_aabb(&self) -> AABB {
+        let n = self.normal;
+        let extent = Vector3::new(
+            self.radius * (1.0 - n.x * n.x).max(0.0).sqrt(),
+            self.radius * (1.0 - n.y * n.y).max(0.0).sqrt(),
+            self.radius * (1.0 - n.z * n.z).max(0.0).sqrt(),
+        );
+        AABB::new(self.center - extent, self.center + extent).pad()
+    }
+}
@@ -0,0 +1,418 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::aabb::surrounding_box;
+use crate::hittable::accelerator::Accelerator;
+use crate::hittable::Hittable;
+use crate::Ray;
+use crate::vector3::Point3;
+
+use std::mem::size_of;
+use std::rc::Rc;
+
+/// How many cells a grid level aims to have for each Hittable in it.
+const CELLS_PER_HITTABLE: f64 = 2.0;
+
+/// The most cells a grid level may have along each axis.
+const MAX_RESOLUTION: usize = 64;
+
+/// A cell with more Hittables than this gets a grid of its own.
+const MAX_CELL_HITTABLES: usize = 8;
+
+/// The most levels a Grid may have, counting the top level.
+const MAX_LEVELS: usize = 3;
+
+/// One level of a Grid: a box cut into equal cells, each of which lists the
+/// Hittables that overlap it or holds a finer grid of its own.
+struct GridLevel {
+    /// The level's AABB.
+    bounding_box: AABB,
+    /// The number of cells along each axis.
+    resolution: [usize; 3],
+    /// The size of a cell along each axis.
+    cell_size: [f64; 3],
+    /// Where each cell's Hittables start in cell_hittables. The Hittables of
+    /// cell i are at cell_starts[i]..cell_starts[i + 1].
+    cell_starts: Vec<u32>,
+    /// The Hittables in each cell, as indices into the Grid's hittables.
+    cell_hittables: Vec<u32>,
+    /// The finer grid of each crowded cell, or None for the rest. Empty if no
+    /// cell has one.
+    subgrids: Vec<Option<Box<GridLevel>>>,
+}
+
+impl GridLevel {
+
+    ///
+    /// Returns a GridLevel over the given box, with a finer GridLevel for
+    /// each cell that ends up with more than MAX_CELL_HITTABLES Hittables.
+    ///
+    /// # Arguments
+    /// * `bounding_box` - The level's AABB.
+    /// * `indices` - The indices of the Hittables that overlap the box.
+    /// * `boxes` - The AABB of every Hittable in the Grid.
+    /// * `level` - Which level this is, where the top level is 0.
+    ///
+    fn new(bounding_box: AABB, indices: &[u32], boxes: &[AABB], level: usize) -> Self {
+
+        let extent = bounding_box.max - bounding_box.min;
+        let extent = [extent.x, extent.y, extent.z];
+
+        // Cells are kept close to cubes. Flat boxes are given a little depth
+        // so that their volume isn't zero.
+        let largest = extent.iter().copied().fold(0.0, f64::max);
+        let volume: f64 = extent.iter().map(|e| e.max(1e-3 * largest)).product();
+        let cells_per_unit = if volume <= 0.0 { 0.0 } else { (CELLS_PER_HITTABLE * indices.len() as f64 / volume).cbrt() };
+        let resolution = extent.map(|e| ((e * cells_per_unit).round() as usize).clamp(1, MAX_RESOLUTION));
+        let cell_size: [f64; 3] = std::array::from_fn(|axis| extent[axis] / resolution[axis] as f64);
+
+        let mut grid = Self {
+            bounding_box: bounding_box,
+            resolution: resolution,
+            cell_size: cell_size,
+            cell_starts: vec![],
+            cell_hittables: vec![],
+            subgrids: vec![],
+        };
+
+        // Count the Hittables in each cell, then fill the cells in.
+        let cell_count = resolution.iter().product::<usize>();
+        let mut counts = vec![0_u32; cell_count + 1];
+        for &i in indices {
+            grid.for_each_cell(boxes[i as usize], |cell| counts[cell] += 1);
+        }
+        let mut start = 0;
+        for count in counts.iter_mut() {
+            let cell_count = *count;
+            *count = start;
+            start += cell_count;
+        }
+        let mut cell_hittables = vec![0_u32; start as usize];
+        let mut next = counts.clone();
+        for &i in indices {
+            grid.for_each_cell(boxes[i as usize], |cell| {
+                cell_hittables[next[cell] as usize] = i;
+                next[cell] += 1;
+            });
+        }
+        grid.cell_starts = counts;
+        grid.cell_hittables = cell_hittables;
+
+        if level + 1 < MAX_LEVELS {
+            let subgrids: Vec<Option<Box<GridLevel>>> = (0..cell_count).map(|cell| {
+                let hittables = grid.get_cell_hittables(cell);
+                if hittables.len() <= MAX_CELL_HITTABLES { return None; }
+                Some(Box::new(GridLevel::new(grid.get_cell_aabb(cell), hittables, boxes, level + 1)))
+            }).collect();
+            if subgrids.iter().any(|subgrid| subgrid.is_some()) { grid.subgrids = subgrids; }
+        }
+
+        grid
+    }
+
+    ///
+    /// Returns the cell that holds the given part of a point along an axis,
+    /// clamped to the grid.
+    ///
+    /// # Arguments
+    /// * `&self` - The GridLevel.
+    /// * `axis` - The axis, where 0 is x, 1 is y and 2 is z.
+    /// * `value` - The part of the point along the axis.
+    ///
+    fn get_axis_cell(&self, axis: usize, value: f64) -> usize {
+        let cell = ((value - self.bounding_box.min[axis]) / self.cell_size[axis]).floor();
+        if cell.is_nan() { return 0; }
+        (cell.max(0.0) as usize).min(self.resolution[axis] - 1)
+    }
+
+    ///
+    /// Returns the index of the cell at the given position in the grid.
+    ///
+    /// # Arguments
+    /// * `&self` - The GridLevel.
+    /// * `cell` - The cell's position along each axis.
+    ///
+    fn get_cell_index(&self, cell: [usize; 3]) -> usize {
+        (cell[2] * self.resolution[1] + cell[1]) * self.resolution[0] + cell[0]
+    }
+
+    ///
+    /// Calls f with the index of every cell the given AABB overlaps.
+    ///
+    /// # Arguments
+    /// * `&self` - The GridLevel.
+    /// * `bounding_box` - The AABB.
+    /// * `f` - The function to call.
+    ///
+    fn for_each_cell(&self, bounding_box: AABB, mut f: impl FnMut(usize)) {
+        let first: [usize; 3] = std::array::from_fn(|axis| self.get_axis_cell(axis, bounding_box.min[axis]));
+        let last: [usize; 3] = std::array::from_fn(|axis| self.get_axis_cell(axis, bounding_box.max[axis]));
+        for z in first[2]..=last[2] {
+            for y in first[1]..=last[1] {
+                for x in first[0]..=last[0] {
+                    f(self.get_cell_index([x, y, z]));
+                }
+            }
+        }
+    }
+
+    ///
+    /// Returns the Hittables in the cell with the given index.
+    ///
+    /// # Arguments
+    /// * `&self` - The GridLevel.
+    /// * `cell` - The index of the cell.
+    ///
+    fn get_cell_hittables(&self, cell: usize) -> &[u32] {
+        &self.cell_hittables[self.cell_starts[cell] as usize..self.cell_starts[cell + 1] as usize]
+    }
+
+    ///
+    /// Returns the AABB of the cell with the given index.
+    ///
+    /// # Arguments
+    /// * `&self` - The GridLevel.
+    /// * `cell` - The index of the cell.
+    ///
+    fn get_cell_aabb(&self, cell: usize) -> AABB {
+        let [x_count, y_count, _] = self.resolution;
+        let position = [cell % x_count, cell / x_count % y_count, cell / x_count / y_count];
+        let min: [f64; 3] = std::array::from_fn(|axis| self.bounding_box.min[axis] + position[axis] as f64 * self.cell_size[axis]);
+        let max: [f64; 3] = std::array::from_fn(|axis| {
+            // The last cell ends exactly at the grid's edge.
+            if position[axis] + 1 == self.resolution[axis] { self.bounding_box.max[axis] } else { min[axis] + self.cell_size[axis] }
+        });
+        AABB::new(Point3::new(min[0], min[1], min[2]), Point3::new(max[0], max[1], max[2]))
+    }
+
+    ///
+    /// Hands the Hittables in every cell the given Ray passes through to
+    /// visit_cell, in order, using a 3D digital differential analyzer. Stops
+    /// once the closest Hit so far is inside the current cell, or once
+    /// visit_cell returns true. Returns true if visit_cell did.
+    ///
+    /// # Arguments
+    /// * `&self` - The GridLevel.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `closest_distance` - The distance of the closest Hit so far, which visit_cell may update.
+    /// * `visit_cell` - Checks the Ray against the Hittables in a cell.
+    ///
+    fn walk(&self, ray: Ray, min_dist: f64, closest_distance: &mut f64, visit_cell: &mut dyn FnMut(&[u32], &mut f64) -> bool) -> bool {
+
+        let (entry, exit) = match self.bounding_box.get_intersect(ray, min_dist, *closest_distance) {
+            Some(range) => range,
+            None => return false,
+        };
+
+        let entry_point = ray.get_point(entry);
+        let mut cell: [usize; 3] = std::array::from_fn(|axis| self.get_axis_cell(axis, entry_point[axis]));
+        let mut next_crossing = [f64::INFINITY; 3];
+        let mut crossing_step = [0.0; 3];
+
+        for axis in 0..3 {
+            let direction = ray.direction[axis];
+            if direction == 0.0 { continue; }
+            let min = self.bounding_box.min[axis];
+            let boundary = if 0.0 < direction { cell[axis] + 1 } else { cell[axis] };
+            next_crossing[axis] = (min + boundary as f64 * self.cell_size[axis] - ray.origin[axis]) / direction;
+            crossing_step[axis] = self.cell_size[axis] / direction.abs();
+        }
+
+        loop {
+
+            let cell_index = self.get_cell_index(cell);
+            let is_done = match self.subgrids.get(cell_index) {
+                Some(Some(subgrid)) => subgrid.walk(ray, min_dist, closest_distance, visit_cell),
+                _ => visit_cell(self.get_cell_hittables(cell_index), closest_distance),
+            };
+            if is_done { return true; }
+
+            // Step into the next cell along the axis whose boundary is crossed first.
+            let axis = (0..3).min_by(|&a, &b| next_crossing[a].total_cmp(&next_crossing[b])).unwrap();
+            let cell_exit = next_crossing[axis];
+            // Every Hit in a later cell is further away than this cell's exit.
+            if *closest_distance <= cell_exit || exit < cell_exit { return false; }
+
+            if 0.0 < ray.direction[axis] {
+                if cell[axis] + 1 == self.resolution[axis] { return false; }
+                cell[axis] += 1;
+            } else {
+                if cell[axis] == 0 { return false; }
+                cell[axis] -= 1;
+            }
+            next_crossing[axis] += crossing_step[axis];
+        }
+    }
+
+    ///
+    /// Returns how many bytes this level and every finer level take up.
+    ///
+    /// # Arguments
+    /// * `&self` - The GridLevel.
+    ///
+    fn get_memory_size(&self) -> usize {
+        size_of::<Self>()
+            + self.cell_starts.capacity() * size_of::<u32>()
+            + self.cell_hittables.capacity() * size_of::<u32>()
+            + self.subgrids.capacity() * size_of::<Option<Box<GridLevel>>>()
+            + self.subgrids.iter().flatten().map(|subgrid| subgrid.get_memory_size()).sum::<usize>()
+    }
+}
+
+/// Represents a hierarchical grid. The scene's AABB is cut into equal cells,
+/// and a Ray walks through the cells it passes in order, checking only the
+/// Hittables that overlap each one. Cells that end up crowded, such as where
+/// lots of small Hittables sit next to a large one, are cut into a finer grid
+/// of their own, up to MAX_LEVELS deep.
+pub struct Grid {
+    /// The Hittables in the Grid.
+    hittables: Vec<Rc<dyn Hittable>>,
+    /// The top level of the Grid.
+    root: GridLevel,
+}
+
+impl Grid {
+
+    ///
+    /// Returns a Grid built over the given Hittables.
+    ///
+    /// # Arguments
+    /// * `hittables` - The Hittables in the Grid. Must not be empty.
+    ///
+    pub fn new(hittables: Vec<Rc<dyn Hittable>>) -> Self {
+
+        assert!(!hittables.is_empty(), "a Grid needs at least one Hittable");
+
+        let boxes: Vec<AABB> = hittables.iter().map(|h| h.get_aabb()).collect();
+        let bounding_box = boxes.iter().copied().reduce(surrounding_box).unwrap();
+        let indices: Vec<u32> = (0..hittables.len() as u32).collect();
+
+        Self {
+            root: GridLevel::new(bounding_box, &indices, &boxes, 0),
+            hittables: hittables,
+        }
+    }
+}
+
+impl Hittable for Grid {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Grid and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Grid intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let mut closest_distance = max_dist;
+        let mut hit: Option<Hit> = None;
+
+        self.root.walk(ray, min_dist, &mut closest_distance, &mut |cell_hittables, closest_distance| {
+            for &i in cell_hittables {
+                if let Some(h) = self.hittables[i as usize].get_hit(ray, min_dist, *closest_distance) {
+                    *closest_distance = h.distance;
+                    hit = Some(h);
+                }
+            }
+            false
+        });
+
+        hit
+    }
+
+    ///
+    /// Returns true if there is a Hit between the Grid and a given Ray. The
+    /// walk through the cells stops at the first Hittable hit.
+    ///
+    /// # Arguments
+    /// * `&self` - The Grid intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        let mut max_dist = max_dist;
+        self.root.walk(ray, min_dist, &mut max_dist, &mut |cell_hittables, max_dist| {
+            cell_hittables.iter().any(|&i| self.hittables[i as usize].is_hit(ray, min_dist, *max_dist))
+        })
+    }
+
+    ///
+    /// Returns an AABB that surrounds the Grid.
+    ///
+    /// # Arguments
+    /// * `&self` - The Grid.
+    ///
+    fn get_aabb(&self) -> AABB {
+        self.root.bounding_box
+    }
+
+    ///
+    /// Returns how many bytes the Grid's cells and list of Hittables take up.
+    ///
+    /// # Arguments
+    /// * `&self` - The Grid.
+    ///
+    fn get_memory_size(&self) -> usize {
+        size_of::<Self>() - size_of::<GridLevel>()
+            + self.root.get_memory_size()
+            + self.hittables.capacity() * size_of::<Rc<dyn Hittable>>()
+    }
+}
+
+impl Accelerator for Grid {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::bvh_node::construct_sah_bvh_root;
+    use crate::hittable::quad::Quad;
+    use crate::hittable::sphere::Sphere;
+    use crate::material::Material;
+    use crate::texture::monochrome::Monochrome;
+
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    fn get_material() -> Material {
+        Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None)
+    }
+
+    fn get_point(rng: &mut StdRng, size: f64) -> Point3 {
+        Point3::new(rng.gen_range(-size..size), rng.gen_range(-size..size), rng.gen_range(-size..size))
+    }
+
+    #[test]
+    fn hits_match_a_bvh() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut hittables: Vec<Rc<dyn Hittable>> = (0..300).map(|i| -> Rc<dyn Hittable> {
+            let center = get_point(&mut rng, 10.0);
+            if i % 3 == 0 {
+                Rc::new(Quad::new(center, get_point(&mut rng, 2.0), get_point(&mut rng, 2.0), get_material()))
+            } else {
+                Rc::new(Sphere::new(center, rng.gen_range(0.1..2.0), get_material()))
+            }
+        }).collect();
+        let grid = Grid::new(hittables.clone());
+        let length = hittables.len();
+        let bvh = construct_sah_bvh_root(&mut hittables, 0, length, 1);
+
+        for i in 0..2000 {
+            // Half of the Rays start inside the bounds, among the hittables.
+            let origin = get_point(&mut rng, if i % 2 == 0 { 8.0 } else { 15.0 });
+            let mut direction = get_point(&mut rng, 1.0);
+            if i % 5 == 0 { direction.y = 0.0; }
+            let ray = Ray::new(origin, direction);
+
+            let expected = bvh.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance);
+            assert_eq!(grid.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance), expected);
+            assert_eq!(grid.is_hit(ray, 0.001, f64::INFINITY), expected.is_some());
+        }
+    }
+}
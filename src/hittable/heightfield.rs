@@ -0,0 +1,548 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::get_distance_past;
+use crate::hittable::get_opaque_intersect;
+use crate::hittable::Hittable;
+use crate::hittable::triangle::get_triangle_intersect;
+use crate::material::Material;
+use crate::Ray;
+use crate::texture::SurfacePoint;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use std::fs;
+use std::io;
+
+/// Represents terrain made from a regular grid of heights. Each grid cell is
+/// split into two triangles, and the normals at the grid's vertices are
+/// interpolated across them so the terrain looks smooth.
+pub struct Heightfield {
+    /// The number of height samples along the x axis.
+    columns: usize,
+    /// The world space heights of every sample, stored row by row.
+    heights: Vec<f64>,
+    /// The unit normal at every sample, stored row by row.
+    normals: Vec<Vector3>,
+    /// The (min, max) heights of the cells at each level of detail. Level 0 has
+    /// one entry per grid cell, and each level above covers 2x2 cells of the
+    /// level below it, until the last level covers the whole grid.
+    levels: Vec<MinMaxLevel>,
+    /// The corner of the terrain with the smallest x and z values.
+    origin: Point3,
+    /// The width of a grid cell along the x axis.
+    cell_width: f64,
+    /// The depth of a grid cell along the z axis.
+    cell_depth: f64,
+    /// The terrain's world space width, height scale, and depth.
+    size: Vector3,
+    /// The heightfield's bounding box.
+    bounding_box: AABB,
+    /// The terrain's material.
+    pub material: Material,
+}
+
+/// One level of a Heightfield's min/max mipmap.
+struct MinMaxLevel {
+    /// The number of cells along the x axis.
+    columns: usize,
+    /// The number of cells along the z axis.
+    rows: usize,
+    /// The (min, max) height of every cell, stored row by row.
+    bounds: Vec<(f64, f64)>,
+}
+
+impl Heightfield {
+
+    ///
+    /// Returns a Heightfield constructed from a grid of heights.
+    ///
+    /// # Arguments
+    /// * `heights` - The heights of every sample, stored row by row along the x axis. Usually between [0, 1].
+    /// * `columns` - The number of samples along the x axis. Must be at least 2.
+    /// * `rows` - The number of samples along the z axis. Must be at least 2.
+    /// * `origin` - The corner of the terrain with the smallest x and z values.
+    /// * `size` - The terrain's world space width, height scale, and depth. Heights are multiplied by size.y.
+    /// * `material` - The terrain's material.
+    ///
+    pub fn new(heights: Vec<f64>, columns: usize, rows: usize, origin: Point3, size: Vector3, material: Material) -> Self {
+
+        assert!(2 <= columns && 2 <= rows && heights.len() == columns * rows);
+
+        let heights: Vec<f64> = heights.into_iter().map(|h| origin.y + h * size.y).collect();
+        let cell_width = size.x / (columns - 1) as f64;
+        let cell_depth = size.z / (rows - 1) as f64;
+
+        // Smooth vertex normals from central differences of the heights.
+        let height_at = |x: usize, z: usize| heights[z * columns + x];
+        let mut normals = Vec::with_capacity(columns * rows);
+        for z in 0..rows {
+            for x in 0..columns {
+                let (x0, x1) = (x.saturating_sub(1), (x + 1).min(columns - 1));
+                let (z0, z1) = (z.saturating_sub(1), (z + 1).min(rows - 1));
+                let dx = (height_at(x1, z) - height_at(x0, z)) / ((x1 - x0) as f64 * cell_width);
+                let dz = (height_at(x, z1) - height_at(x, z0)) / ((z1 - z0) as f64 * cell_depth);
+                normals.push(Vector3::new(-dx, 1.0, -dz).unit());
+            }
+        }
+
+        let levels = construct_min_max_levels(&heights, columns, rows);
+
+        let (min_height, max_height) = levels[levels.len() - 1].bounds[0];
+        let bounding_box = AABB::new(
+            Point3::new(origin.x, min_height, origin.z),
+            Point3::new(origin.x + size.x, max_height, origin.z + size.z),
+        ).pad();
+
+        Self {
+            columns: columns,
+            heights: heights,
+            normals: normals,
+            levels: levels,
+            origin: origin,
+            cell_width: cell_width,
+            cell_depth: cell_depth,
+            size: size,
+            bounding_box: bounding_box,
+            material: material,
+        }
+    }
+
+    ///
+    /// Returns a Heightfield whose heights are sampled from a function, such as
+    /// layered Perlin noise.
+    ///
+    /// # Arguments
+    /// * `function` - Returns the height at the given (u, v) coordinates, which are both between [0, 1].
+    /// * `columns` - The number of samples along the x axis. Must be at least 2.
+    /// * `rows` - The number of samples along the z axis. Must be at least 2.
+    /// * `origin` - The corner of the terrain with the smallest x and z values.
+    /// * `size` - The terrain's world space width, height scale, and depth.
+    /// * `material` - The terrain's material.
+    ///
+    pub fn from_function(function: &dyn Fn(f64, f64) -> f64, columns: usize, rows: usize, origin: Point3, size: Vector3, material: Material) -> Self {
+        let mut heights = Vec::with_capacity(columns * rows);
+        for z in 0..rows {
+            for x in 0..columns {
+                heights.push(function(x as f64 / (columns - 1) as f64, z as f64 / (rows - 1) as f64));
+            }
+        }
+        Self::new(heights, columns, rows, origin, size, material)
+    }
+
+    ///
+    /// Returns a Heightfield whose heights are read from a grayscale PGM image
+    /// (either the P2 plain text or P5 binary format). Black is a height of 0
+    /// and white is a height of 1. The top row of the image is the far edge of
+    /// the terrain along the z axis.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the image file.
+    /// * `origin` - The corner of the terrain with the smallest x and z values.
+    /// * `size` - The terrain's world space width, height scale, and depth.
+    /// * `material` - The terrain's material.
+    ///
+    pub fn from_pgm(path: &str, origin: Point3, size: Vector3, material: Material) -> io::Result<Self> {
+        let (heights, columns, rows) = read_pgm(&fs::read(path)?)?;
+        Ok(Self::new(heights, columns, rows, origin, size, material))
+    }
+
+    ///
+    /// Returns the world space position of the sample at the given grid coordinates.
+    ///
+    /// # Arguments
+    /// * `&self` - The Heightfield.
+    /// * `x` - The sample's column.
+    /// * `z` - The sample's row.
+    ///
+    fn get_vertex(&self, x: usize, z: usize) -> Point3 {
+        Point3::new(
+            self.origin.x + x as f64 * self.cell_width,
+            self.heights[z * self.columns + x],
+            self.origin.z + z as f64 * self.cell_depth,
+        )
+    }
+
+    ///
+    /// Returns the (distance, normal, is_front) of the closest intersection
+    /// between the given Ray and the two triangles of a grid cell.
+    ///
+    /// # Arguments
+    /// * `&self` - The Heightfield.
+    /// * `ray` - The intersecting Ray.
+    /// * `x` - The cell's column.
+    /// * `z` - The cell's row.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_cell_intersect(&self, ray: Ray, x: usize, z: usize, min_dist: f64, max_dist: f64) -> Option<(f64, Vector3, bool)> {
+
+        let corners = [(x, z), (x + 1, z), (x + 1, z + 1), (x, z + 1)];
+        let triangles = [[corners[0], corners[2], corners[1]], [corners[0], corners[3], corners[2]]];
+
+        let mut closest: Option<(f64, Vector3, bool)> = None;
+        let mut closest_distance = max_dist;
+
+        for triangle in triangles {
+
+            let p0 = self.get_vertex(triangle[0].0, triangle[0].1);
+            let p1 = self.get_vertex(triangle[1].0, triangle[1].1);
+            let p2 = self.get_vertex(triangle[2].0, triangle[2].1);
+
+            let (distance, b1, b2) = match get_triangle_intersect(ray, [p0, p1, p2], min_dist, closest_distance) {
+                Some(intersect) => intersect,
+                None => continue,
+            };
+
+            let n0 = self.normals[triangle[0].1 * self.columns + triangle[0].0];
+            let n1 = self.normals[triangle[1].1 * self.columns + triangle[1].0];
+            let n2 = self.normals[triangle[2].1 * self.columns + triangle[2].0];
+            let normal = ((1.0 - b1 - b2) * n0 + b1 * n1 + b2 * n2).unit();
+
+            // The triangles are wound so that their geometric normal faces up.
+            let is_front = ray.direction.dot((p1 - p0).cross(p2 - p0)) < 0.0;
+
+            closest = Some((distance, normal, is_front));
+            closest_distance = distance;
+        }
+
+        closest
+    }
+
+    ///
+    /// Returns the (distance, normal, is_front) of the closest intersection
+    /// between the Ray and the cells of the given level that are within the
+    /// given range of columns and rows. The cells are walked in the order the
+    /// Ray passes through them with a 2D DDA. Cells whose (min, max) heights
+    /// the Ray passes entirely above or below are skipped, and the rest are
+    /// searched again one level down.
+    ///
+    /// # Arguments
+    /// * `&self` - The Heightfield.
+    /// * `ray` - The intersecting Ray.
+    /// * `level` - The level of detail to walk.
+    /// * `columns` - The (first, last) columns of the level to walk.
+    /// * `rows` - The (first, last) rows of the level to walk.
+    /// * `min_dist` - The distance along the Ray to start walking at.
+    /// * `max_dist` - The distance along the Ray to stop walking at.
+    ///
+    fn march(&self, ray: Ray, level: usize, columns: (usize, usize), rows: (usize, usize), min_dist: f64, max_dist: f64) -> Option<(f64, Vector3, bool)> {
+
+        let min_max = &self.levels[level];
+        let scale = (1_usize << level) as f64;
+        let width = self.cell_width * scale;
+        let depth = self.cell_depth * scale;
+
+        let start = ray.get_point(min_dist);
+        let mut x = (((start.x - self.origin.x) / width).floor().max(0.0) as usize).clamp(columns.0, columns.1);
+        let mut z = (((start.z - self.origin.z) / depth).floor().max(0.0) as usize).clamp(rows.0, rows.1);
+
+        // The distance along the Ray to the next cell boundary on each axis, and
+        // the distance along the Ray between boundaries.
+        let next_boundary = |cell: usize, origin: f64, direction: f64, size: f64, ray_origin: f64| {
+            if direction == 0.0 { return f64::INFINITY; }
+            let boundary = if 0.0 < direction { cell + 1 } else { cell } as f64;
+            (origin + boundary * size - ray_origin) / direction
+        };
+        let mut x_boundary = next_boundary(x, self.origin.x, ray.direction.x, width, ray.origin.x);
+        let mut z_boundary = next_boundary(z, self.origin.z, ray.direction.z, depth, ray.origin.z);
+        let x_delta = if ray.direction.x == 0.0 { f64::INFINITY } else { width / ray.direction.x.abs() };
+        let z_delta = if ray.direction.z == 0.0 { f64::INFINITY } else { depth / ray.direction.z.abs() };
+
+        let mut cell_entry = min_dist;
+
+        loop {
+
+            let cell_exit = x_boundary.min(z_boundary).min(max_dist);
+
+            let y_entry = ray.origin.y + cell_entry * ray.direction.y;
+            let y_exit = ray.origin.y + cell_exit * ray.direction.y;
+            let (cell_min, cell_max) = min_max.bounds[z * min_max.columns + x];
+
+            if y_entry.min(y_exit) <= cell_max && cell_min <= y_entry.max(y_exit) {
+
+                let hit = if level == 0 {
+                    self.get_cell_intersect(ray, x, z, min_dist, max_dist)
+                } else {
+                    let below = &self.levels[level - 1];
+                    let child_columns = (2 * x, (2 * x + 1).min(below.columns - 1));
+                    let child_rows = (2 * z, (2 * z + 1).min(below.rows - 1));
+                    self.march(ray, level - 1, child_columns, child_rows, cell_entry, cell_exit)
+                };
+
+                if hit.is_some() { return hit; }
+            }
+
+            if max_dist <= cell_exit { return None; }
+
+            if x_boundary < z_boundary {
+                if 0.0 < ray.direction.x {
+                    if x == columns.1 { return None; }
+                    x += 1;
+                } else {
+                    if x == columns.0 { return None; }
+                    x -= 1;
+                }
+                x_boundary += x_delta;
+            } else {
+                if 0.0 < ray.direction.z {
+                    if z == rows.1 { return None; }
+                    z += 1;
+                } else {
+                    if z == rows.0 { return None; }
+                    z -= 1;
+                }
+                z_boundary += z_delta;
+            }
+
+            cell_entry = cell_exit;
+        }
+    }
+
+    ///
+    /// Returns the uv coordinates of a point, which are laid across the
+    /// Heightfield from above.
+    ///
+    /// # Arguments
+    /// * `&self` - The Heightfield.
+    /// * `point` - The point.
+    ///
+    fn get_uv(&self, point: Point3) -> (f64, f64) {
+        ((point.x - self.origin.x) / self.size.x, (point.z - self.origin.z) / self.size.z)
+    }
+
+    ///
+    /// Returns the point sampled SurfacePoint of an intersection from march,
+    /// in the same space as the Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Heightfield.
+    /// * `ray` - The intersecting Ray.
+    /// * `intersect` - The (distance, normal, is_front) of the intersection.
+    ///
+    fn get_surface(&self, ray: Ray, (distance, smooth_normal, _): (f64, Vector3, bool)) -> SurfacePoint {
+        let point = ray.get_point(distance);
+        let (u, v) = self.get_uv(point);
+        SurfacePoint::new((u.clamp(0.0, 1.0), v.clamp(0.0, 1.0)), point, point - self.origin, smooth_normal)
+    }
+
+    ///
+    /// Returns the (distance, normal, is_front) of the closest intersection
+    /// between the Heightfield and the given Ray that isn't cut out by the
+    /// Heightfield's opacity mask.
+    ///
+    /// # Arguments
+    /// * `&self` - The Heightfield.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_opaque_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<(f64, Vector3, bool)> {
+        let top = self.levels.len() - 1;
+        get_opaque_intersect(
+            &self.material,
+            ray,
+            min_dist,
+            |min_dist| {
+                let (entry, exit) = self.bounding_box.get_intersect(ray, min_dist, max_dist)?;
+                self.march(ray, top, (0, 0), (0, 0), entry, exit)
+            },
+            |intersect| self.get_surface(ray, intersect),
+            |(distance, _, _)| get_distance_past(distance),
+        )
+    }
+}
+
+///
+/// Returns the min/max mipmap of the given grid of heights. See the levels
+/// field of Heightfield.
+///
+/// # Arguments
+/// * `heights` - The heights of every sample, stored row by row.
+/// * `columns` - The number of samples along the x axis.
+/// * `rows` - The number of samples along the z axis.
+///
+fn construct_min_max_levels(heights: &[f64], columns: usize, rows: usize) -> Vec<MinMaxLevel> {
+
+    let mut bounds = Vec::with_capacity((columns - 1) * (rows - 1));
+    for z in 0..rows - 1 {
+        for x in 0..columns - 1 {
+            let corners = [
+                heights[z * columns + x],
+                heights[z * columns + x + 1],
+                heights[(z + 1) * columns + x],
+                heights[(z + 1) * columns + x + 1],
+            ];
+            let min = corners.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = corners.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            bounds.push((min, max));
+        }
+    }
+
+    let mut levels = vec![MinMaxLevel { columns: columns - 1, rows: rows - 1, bounds: bounds }];
+
+    while 1 < levels[levels.len() - 1].columns || 1 < levels[levels.len() - 1].rows {
+
+        let below = &levels[levels.len() - 1];
+        let level_columns = below.columns.div_ceil(2);
+        let level_rows = below.rows.div_ceil(2);
+        let mut bounds = Vec::with_capacity(level_columns * level_rows);
+
+        for z in 0..level_rows {
+            for x in 0..level_columns {
+                let mut min = f64::INFINITY;
+                let mut max = f64::NEG_INFINITY;
+                for child_z in (2 * z)..(2 * z + 2).min(below.rows) {
+                    for child_x in (2 * x)..(2 * x + 2).min(below.columns) {
+                        let (child_min, child_max) = below.bounds[child_z * below.columns + child_x];
+                        min = min.min(child_min);
+                        max = max.max(child_max);
+                    }
+                }
+                bounds.push((min, max));
+            }
+        }
+
+        levels.push(MinMaxLevel { columns: level_columns, rows: level_rows, bounds: bounds });
+    }
+
+    levels
+}
+
+///
+/// Returns the (heights, columns, rows) of a grayscale PGM image, with heights
+/// between [0, 1]. The rows are flipped so the image's bottom row comes first.
+///
+/// # Arguments
+/// * `bytes` - The contents of the image file.
+///
+fn read_pgm(bytes: &[u8]) -> io::Result<(Vec<f64>, usize, usize)> {
+
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    // The header is four whitespace separated tokens, with comments starting at '#'.
+    let mut tokens: Vec<String> = vec![];
+    let mut position = 0;
+    while tokens.len() < 4 {
+        while position < bytes.len() && bytes[position].is_ascii_whitespace() { position += 1; }
+        if position < bytes.len() && bytes[position] == b'#' {
+            while position < bytes.len() && bytes[position] != b'\n' { position += 1; }
+            continue;
+        }
+        let start = position;
+        while position < bytes.len() && !bytes[position].is_ascii_whitespace() { position += 1; }
+        if start == position { return Err(invalid("truncated PGM header")); }
+        tokens.push(String::from_utf8_lossy(&bytes[start..position]).to_string());
+    }
+    // Exactly one whitespace byte separates the header from binary data.
+    position += 1;
+
+    let parse = |token: &str| token.parse::<usize>().map_err(|_| invalid("invalid PGM header"));
+    let columns = parse(&tokens[1])?;
+    let rows = parse(&tokens[2])?;
+    let max_value = parse(&tokens[3])?;
+    if columns < 2 || rows < 2 || max_value == 0 { return Err(invalid("invalid PGM dimensions")); }
+
+    let values: Vec<usize> = match tokens[0].as_str() {
+        "P2" => String::from_utf8_lossy(bytes.get(position..).unwrap_or(&[]))
+            .split_ascii_whitespace()
+            .map(parse)
+            .collect::<io::Result<Vec<usize>>>()?,
+        "P5" if max_value < 256 => bytes.get(position..).unwrap_or(&[]).iter().map(|&b| b as usize).collect(),
+        "P5" => bytes.get(position..).unwrap_or(&[]).chunks_exact(2).map(|b| (b[0] as usize) << 8 | b[1] as usize).collect(),
+        _ => return Err(invalid("not a grayscale PGM image")),
+    };
+    if values.len() < columns * rows { return Err(invalid("truncated PGM data")); }
+
+    let mut heights = Vec::with_capacity(columns * rows);
+    for row in (0..rows).rev() {
+        for col in 0..columns {
+            heights.push(values[row * columns + col] as f64 / max_value as f64);
+        }
+    }
+
+    Ok((heights, columns, rows))
+}
+
+impl Hittable for Heightfield {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Heightfield and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Heightfield intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let intersect = self.get_opaque_intersect(ray, min_dist, max_dist)?;
+        let (distance, _, is_front) = intersect;
+        let surface = self.get_surface(ray, intersect).with_derivatives(ray, false, |point| self.get_uv(point));
+
+        let normal = self.material.get_normal(surface);
+        let normal = if is_front { normal } else { -normal };
+
+        Some(Hit::new(
+            ray,
+            distance,
+            normal,
+            is_front,
+            self.material.texture.get_color(surface),
+            self.material.get_reflectivity(surface),
+            self.material.get_refraction_index(surface),
+        ))
+    }
+
+    ///
+    /// Returns true if there is a Hit between the Heightfield and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Heightfield intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.get_opaque_intersect(ray, min_dist, max_dist).is_some()
+    }
+
+    ///
+    /// Returns an AABB that surrounds the Heightfield.
+    ///
+    /// # Arguments
+    /// * `&self` - The Heightfield.
+    ///
+    fn get_aabb(&self) -> AABB {
+        self.bounding_box
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn plain_pgm_rows_are_flipped() {
+        let (heights, columns, rows) = read_pgm(b"P2\n# a comment\n3 2\n4\n0 1 2\n3 4 4\n").unwrap();
+        assert_eq!((columns, rows), (3, 2));
+        assert_eq!(heights, vec![0.75, 1.0, 1.0, 0.0, 0.25, 0.5]);
+    }
+
+    #[test]
+    fn binary_pgm_reads_one_or_two_bytes_per_sample() {
+        let (heights, _, _) = read_pgm(b"P5 2 2 255\n\x00\xff\x33\x66").unwrap();
+        assert_eq!(heights, vec![0.2, 0.4, 0.0, 1.0]);
+
+        let (heights, _, _) = read_pgm(b"P5 2 2 1000\n\x00\x00\x03\xe8\x01\xf4\x00\xfa").unwrap();
+        assert_eq!(heights, vec![0.5, 0.25, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn invalid_pgm_files_are_rejected() {
+        for bytes in [&b"P6 2 2 255\n\x00\x00\x00\x00"[..], b"P2 2 2 255\n0 1 2", b"P2 1 2 255\n0 1", b"P2 2 2"] {
+            assert_eq!(read_pgm(bytes).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
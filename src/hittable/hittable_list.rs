@@ -0,0 +1,128 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::aabb::surrounding_box;
+use crate::hittable::Hittable;
+use crate::Ray;
+
+use std::cell::Cell;
+use std::mem::size_of;
+use std::rc::Rc;
+
+/// Represents a group of Hittables that are all checked one after another.
+/// Used for the leaves of a BVH, where checking a handful of Hittables is
+/// cheaper than splitting them any further.
+pub struct HittableList {
+    /// The Hittables in the list.
+    pub hittables: Vec<Rc<dyn Hittable>>,
+    /// An AABB that surrounds every Hittable in the list. In a Cell so that it can be refit.
+    bounding_box: Cell<AABB>,
+}
+
+impl HittableList {
+
+    ///
+    /// Returns a HittableList constructed from the given Hittables.
+    ///
+    /// # Arguments
+    /// * `hittables` - The HittableList's hittables field. Must not be empty.
+    ///
+    pub fn new(hittables: Vec<Rc<dyn Hittable>>) -> Self {
+        let bounding_box = hittables
+            .iter()
+            .map(|h| h.get_aabb())
+            .reduce(surrounding_box)
+            .expect("a HittableList needs at least one Hittable");
+        Self {
+            hittables: hittables,
+            bounding_box: Cell::new(bounding_box),
+        }
+    }
+}
+
+impl Hittable for HittableList {
+
+    ///
+    /// Returns Some(Hit) for the closest Hit between the Ray and any of the
+    /// HittableList's Hittables.
+    ///
+    /// # Arguments
+    /// * `&self` - The HittableList intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let mut closest_distance = max_dist;
+        let mut hit: Option<Hit> = None;
+
+        for hittable in &self.hittables {
+            if let Some(h) = hittable.get_hit(ray, min_dist, closest_distance) {
+                closest_distance = h.distance;
+                hit = Some(h);
+            }
+        }
+
+        hit
+    }
+
+    ///
+    /// Returns true if the Ray hits any of the HittableList's Hittables. The
+    /// search stops at the first Hittable hit.
+    ///
+    /// # Arguments
+    /// * `&self` - The HittableList intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.hittables.iter().any(|hittable| hittable.is_hit(ray, min_dist, max_dist))
+    }
+
+    ///
+    /// Returns an AABB that surrounds the HittableList.
+    ///
+    /// # Arguments
+    /// * `&self` - The HittableList.
+    ///
+    fn get_aabb(&self) -> AABB {
+        self.bounding_box.get()
+    }
+
+    ///
+    /// Refits every Hittable in the list, then sets the list's AABB to
+    /// surround them.
+    ///
+    /// # Arguments
+    /// * `&self` - The HittableList.
+    ///
+    fn refit(&self) -> AABB {
+        let bounding_box = self.hittables.iter().map(|h| h.refit()).reduce(surrounding_box).unwrap();
+        self.bounding_box.set(bounding_box);
+        bounding_box
+    }
+
+    ///
+    /// Returns the SAH cost of the HittableList, which is the cost of checking
+    /// every Hittable in it.
+    ///
+    /// # Arguments
+    /// * `&self` - The HittableList.
+    ///
+    fn get_sah_cost(&self) -> f64 {
+        self.hittables.iter().map(|h| h.get_sah_cost()).sum()
+    }
+
+    ///
+    /// Returns how many bytes the HittableList and its list of Hittables take up.
+    ///
+    /// # Arguments
+    /// * `&self` - The HittableList.
+    ///
+    fn get_memory_size(&self) -> usize {
+        size_of::<Self>()
+            + self.hittables.capacity() * size_of::<Rc<dyn Hittable>>()
+            + self.hittables.iter().map(|h| h.get_memory_size()).sum::<usize>()
+    }
+}
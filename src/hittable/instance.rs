@@ -0,0 +1,232 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::Hittable;
+use crate::Ray;
+use crate::transform::Transform;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Represents a transformed copy of a Hittable. The Hittable itself is shared,
+/// so the same geometry can be placed in a scene any number of times without
+/// being duplicated. The transformation is kept in a Cell so that Instances
+/// can be moved after they have been put in a BVH. The BVH then needs to be
+/// refit.
+pub struct Instance {
+    /// The instanced Hittable, in its own object space.
+    pub hittable: Rc<dyn Hittable>,
+    /// The transformation from object space to world space.
+    object_to_world: Cell<Transform>,
+    /// The world space AABB of the transformed Hittable.
+    bounding_box: Cell<AABB>,
+}
+
+impl Instance {
+
+    ///
+    /// Returns an Instance constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `hittable` - The Instance's hittable field.
+    /// * `object_to_world` - The transformation that places the Hittable in the world.
+    ///
+    pub fn new(hittable: Rc<dyn Hittable>, object_to_world: Transform) -> Self {
+        let bounding_box = object_to_world.transform_aabb(hittable.get_aabb());
+        Self {
+            hittable: hittable,
+            object_to_world: Cell::new(object_to_world),
+            bounding_box: Cell::new(bounding_box),
+        }
+    }
+
+    ///
+    /// Returns the transformation that places the Hittable in the world.
+    ///
+    /// # Arguments
+    /// * `&self` - The Instance.
+    ///
+    pub fn get_transform(&self) -> Transform {
+        self.object_to_world.get()
+    }
+
+    ///
+    /// Moves the Instance by replacing its transformation. Any BVH the
+    /// Instance is in has to be refit or rebuilt afterwards.
+    ///
+    /// # Arguments
+    /// * `&self` - The Instance.
+    /// * `object_to_world` - The transformation that places the Hittable in the world.
+    ///
+    pub fn set_transform(&self, object_to_world: Transform) {
+        self.object_to_world.set(object_to_world);
+        self.bounding_box.set(object_to_world.transform_aabb(self.hittable.get_aabb()));
+    }
+
+    ///
+    /// Returns the given Ray moved into the Instance's object space. The Ray
+    /// keeps the way back to world space, through any Instances it is already
+    /// inside of, so that the instanced Hittable can shade in world space.
+    ///
+    /// # Arguments
+    /// * `&self` - The Instance.
+    /// * `ray` - The Ray, in the space the Instance is placed in.
+    ///
+    fn get_object_ray(&self, ray: Ray) -> Ray {
+        let object_to_world = self.object_to_world.get();
+        let parent_to_world = ray.object_to_world.unwrap_or(Transform::identity());
+        let mut object_ray = object_to_world.inverse().transform_ray(ray);
+        object_ray.object_to_world = Some(parent_to_world * object_to_world);
+        object_ray
+    }
+}
+
+impl Hittable for Instance {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Instance and a given Ray.
+    /// The Ray is moved into object space, and the Hittable shades the Hit in
+    /// world space. The Ray's direction is not normalized, so distances along
+    /// it are the same in both spaces.
+    ///
+    /// # Arguments
+    /// * `&self` - The Instance intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let mut hit = self.hittable.get_hit(self.get_object_ray(ray), min_dist, max_dist)?;
+        hit.ray = ray;
+        Some(hit)
+    }
+
+    ///
+    /// Returns true if there is a Hit between the Instance and a given Ray.
+    /// The Ray is moved into object space, as in get_hit.
+    ///
+    /// # Arguments
+    /// * `&self` - The Instance intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.hittable.is_hit(self.get_object_ray(ray), min_dist, max_dist)
+    }
+
+    ///
+    /// Returns an AABB that surrounds the Instance.
+    ///
+    /// # Arguments
+    /// * `&self` - The Instance.
+    ///
+    fn get_aabb(&self) -> AABB {
+        self.bounding_box.get()
+    }
+
+    ///
+    /// Refits the instanced Hittable, then transforms its new AABB.
+    ///
+    /// # Arguments
+    /// * `&self` - The Instance.
+    ///
+    fn refit(&self) -> AABB {
+        let bounding_box = self.object_to_world.get().transform_aabb(self.hittable.refit());
+        self.bounding_box.set(bounding_box);
+        bounding_box
+    }
+
+    ///
+    /// Returns the SAH cost of the instanced Hittable.
+    ///
+    /// # Arguments
+    /// * `&self` - The Instance.
+    ///
+    fn get_sah_cost(&self) -> f64 {
+        self.hittable.get_sah_cost()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::quad::Quad;
+    use crate::material::Material;
+    use crate::ray::RayDifferentials;
+    use crate::texture::SurfacePoint;
+    use crate::texture::Texture;
+    use crate::vector3::Point3;
+    use crate::vector3::Vector3;
+
+    /// Keeps the last SurfacePoint it was looked up at.
+    struct SurfaceRecorder {
+        surface: Cell<Option<SurfacePoint>>,
+    }
+
+    impl Texture for SurfaceRecorder {
+        fn get_color(&self, surface: SurfacePoint) -> Color {
+            self.surface.set(Some(surface));
+            Color::new(0.0, 0.0, 0.0)
+        }
+    }
+
+    fn assert_vector_eq(a: Vector3, b: Vector3) {
+        assert!((a - b).mag() < 1e-6, "({}, {}, {}) != ({}, {}, {})", a.x, a.y, a.z, b.x, b.y, b.z);
+    }
+
+    /// Hits a Quad with a Ray and returns the Hit and the SurfacePoint it was shaded at.
+    fn get_surface(quad: &dyn Hittable, recorder: &Rc<SurfaceRecorder>) -> (Hit, SurfacePoint) {
+        let ray = Ray::with_differentials(Point3::new(1.5, 2.5, 10.0), Vector3::new(0.0, 0.0, -1.0), RayDifferentials {
+            x_origin: Point3::new(1.5, 2.5, 10.0),
+            x_direction: Vector3::new(0.01, 0.0, -1.0),
+            y_origin: Point3::new(1.5, 2.5, 10.0),
+            y_direction: Vector3::new(0.0, 0.01, -1.0),
+        });
+        let hit = quad.get_hit(ray, 0.0, f64::INFINITY).expect("the Ray should hit the Quad");
+        (hit, recorder.surface.get().expect("the Quad should have been shaded"))
+    }
+
+    #[test]
+    fn instanced_hits_are_shaded_in_world_space() {
+
+        let recorder = Rc::new(SurfaceRecorder { surface: Cell::new(None) });
+        let get_material = || Material::new(Box::new(Rc::clone(&recorder)), None, None);
+
+        // Non-uniform scaling tilts the normal differently than the Quad's
+        // sides, so it catches normals moved like vectors.
+        let object_to_world = Transform::translation(Vector3::new(1.0, 2.0, 3.0))
+            * Transform::rotation(Vector3::new(1.0, 1.0, 0.0), 30.0)
+            * Transform::scaling(Vector3::new(3.0, 1.0, 2.0));
+        let (origin, u, v) = (Point3::new(-1.0, -1.0, 0.0), Vector3::new(2.0, 0.0, 0.0), Vector3::new(0.0, 2.0, 0.0));
+
+        let instance = Instance::new(Rc::new(Quad::new(origin, u, v, get_material())), object_to_world);
+        let (instance_hit, instance_surface) = get_surface(&instance, &recorder);
+
+        let quad = Quad::new(
+            object_to_world.transform_point(origin),
+            object_to_world.transform_vector(u),
+            object_to_world.transform_vector(v),
+            get_material(),
+        );
+        let (hit, surface) = get_surface(&quad, &recorder);
+
+        assert!((instance_hit.distance - hit.distance).abs() < 1e-9);
+        assert_vector_eq(instance_hit.normal, hit.normal);
+        assert!((instance_surface.uv.0 - surface.uv.0).abs() < 1e-9);
+        assert!((instance_surface.uv.1 - surface.uv.1).abs() < 1e-9);
+        assert_vector_eq(instance_surface.point, surface.point);
+        assert_vector_eq(instance_surface.normal, surface.normal);
+        assert_vector_eq(instance_surface.dpdu, surface.dpdu);
+        assert_vector_eq(instance_surface.dpdv, surface.dpdv);
+        assert_vector_eq(instance_surface.footprint.dpdx, surface.footprint.dpdx);
+        assert_vector_eq(instance_surface.footprint.dpdy, surface.footprint.dpdy);
+
+        // Steps across the surface land on the same spot of the object.
+        let step = Vector3::new(0.1, -0.2, 0.3);
+        let object_step = instance_surface.to_object * step;
+        assert_vector_eq(object_to_world.transform_vector(object_step), step);
+    }
+}
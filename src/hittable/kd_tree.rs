@@ -0,0 +1,453 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::aabb::surrounding_box;
+use crate::hittable::accelerator::Accelerator;
+use crate::hittable::bvh_node::INTERSECTION_COST;
+use crate::hittable::bvh_node::TRAVERSAL_COST;
+use crate::hittable::Hittable;
+use crate::Ray;
+use crate::vector3::Point3;
+
+use std::mem::size_of;
+use std::rc::Rc;
+
+/// The deepest a KdTree can be. The traversal stack has a fixed size.
+const MAX_DEPTH: usize = 64;
+
+/// How much cheaper a split that leaves one side empty is made to look. Cutting
+/// off empty space lets Rays skip it with a single plane test.
+const EMPTY_BONUS: f64 = 0.2;
+
+/// A node of a KdTree.
+#[derive(Clone, Copy)]
+enum KdNode {
+    /// A node split in two by a plane. The child below the plane is the node
+    /// right after this one.
+    Interior {
+        /// The axis the plane is perpendicular to, where 0 is x, 1 is y and 2 is z.
+        axis: u8,
+        /// Where the plane crosses the axis.
+        split: f64,
+        /// The index of the child above the plane.
+        above: u32,
+    },
+    /// A node holding Hittables.
+    Leaf {
+        /// The index of the leaf's first Hittable in hittable_indices.
+        first: u32,
+        /// The number of Hittables in the leaf.
+        count: u32,
+    },
+}
+
+/// One side of a Hittable's AABB along an axis, used to sweep a plane through
+/// a node when looking for the best split.
+#[derive(Clone, Copy)]
+struct Edge {
+    /// Where the side crosses the axis.
+    position: f64,
+    /// If this is the side where the AABB starts, rather than ends.
+    is_start: bool,
+}
+
+/// Represents a k-d tree: space is split in two by an axis aligned plane, then
+/// each half is split again, until every leaf holds only a few Hittables.
+/// Unlike a BVH, the children of a node never overlap, so a Ray can stop as
+/// soon as it finds a Hit inside the node it is in. A Hittable that crosses a
+/// plane is stored on both sides of it.
+pub struct KdTree {
+    /// The nodes of the tree, depth first. The first node is the root.
+    nodes: Vec<KdNode>,
+    /// The Hittables in each leaf, as indices into hittables.
+    hittable_indices: Vec<u32>,
+    /// The Hittables in the tree.
+    hittables: Vec<Rc<dyn Hittable>>,
+    /// The AABB of the whole tree.
+    bounding_box: AABB,
+}
+
+impl KdTree {
+
+    ///
+    /// Returns a KdTree built over the given Hittables. Each node is split
+    /// where the surface area heuristic says it is cheapest to, trying the
+    /// sides of every Hittable's AABB, until no split is cheaper than a leaf.
+    ///
+    /// # Arguments
+    /// * `hittables` - The Hittables in the tree. Must not be empty.
+    ///
+    pub fn new(hittables: Vec<Rc<dyn Hittable>>) -> Self {
+
+        assert!(!hittables.is_empty(), "a KdTree needs at least one Hittable");
+
+        let boxes: Vec<AABB> = hittables.iter().map(|h| h.get_aabb()).collect();
+        let bounding_box = boxes.iter().copied().reduce(surrounding_box).unwrap();
+        let max_depth = ((8.0 + 1.3 * (hittables.len() as f64).log2()).round() as usize).min(MAX_DEPTH);
+
+        let mut nodes = vec![];
+        let mut hittable_indices = vec![];
+        construct_kd_node((0..hittables.len() as u32).collect(), bounding_box, &boxes, max_depth, &mut nodes, &mut hittable_indices);
+
+        Self {
+            nodes: nodes,
+            hittable_indices: hittable_indices,
+            hittables: hittables,
+            bounding_box: bounding_box,
+        }
+    }
+}
+
+///
+/// Appends the nodes of a k-d tree over the given Hittables to nodes, depth
+/// first, and appends the Hittables in each leaf to hittable_indices.
+///
+/// # Arguments
+/// * `indices` - The indices of the Hittables that overlap the node.
+/// * `bounding_box` - The node's AABB.
+/// * `boxes` - The AABB of every Hittable in the tree.
+/// * `depth_left` - How many more times the node may be split.
+/// * `nodes` - The nodes of the tree.
+/// * `hittable_indices` - The Hittables in each leaf.
+///
+fn construct_kd_node(indices: Vec<u32>, bounding_box: AABB, boxes: &[AABB], depth_left: usize, nodes: &mut Vec<KdNode>, hittable_indices: &mut Vec<u32>) {
+
+    let split = if indices.len() <= 1 || depth_left == 0 { None } else { find_kd_split(&indices, bounding_box, boxes) };
+
+    let (axis, position) = match split {
+        Some(split) => split,
+        None => {
+            nodes.push(KdNode::Leaf { first: hittable_indices.len() as u32, count: indices.len() as u32 });
+            hittable_indices.extend(indices);
+            return;
+        },
+    };
+
+    // A Hittable that touches the plane goes on both sides, so that a Ray
+    // that runs along the plane finds it whichever side it is walked on.
+    let below: Vec<u32> = indices.iter().copied().filter(|&i| boxes[i as usize].min[axis] <= position).collect();
+    let above: Vec<u32> = indices.iter().copied().filter(|&i| position <= boxes[i as usize].max[axis]).collect();
+
+    let mut below_box = bounding_box;
+    let mut above_box = bounding_box;
+    set_axis(&mut below_box.max, axis, position);
+    set_axis(&mut above_box.min, axis, position);
+
+    let node_index = nodes.len();
+    nodes.push(KdNode::Interior { axis: axis as u8, split: position, above: 0 });
+    construct_kd_node(below, below_box, boxes, depth_left - 1, nodes, hittable_indices);
+    let above_index = nodes.len() as u32;
+    nodes[node_index] = KdNode::Interior { axis: axis as u8, split: position, above: above_index };
+    construct_kd_node(above, above_box, boxes, depth_left - 1, nodes, hittable_indices);
+}
+
+///
+/// Returns the (axis, position) of the cheapest plane to split a node with,
+/// according to the surface area heuristic, or None if no split is cheaper
+/// than making the node a leaf.
+///
+/// # Arguments
+/// * `indices` - The indices of the Hittables that overlap the node.
+/// * `bounding_box` - The node's AABB.
+/// * `boxes` - The AABB of every Hittable in the tree.
+///
+fn find_kd_split(indices: &[u32], bounding_box: AABB, boxes: &[AABB]) -> Option<(usize, f64)> {
+
+    let area = bounding_box.surface_area();
+    if area <= 0.0 { return None; }
+
+    let count = indices.len();
+    let mut best_cost = count as f64 * INTERSECTION_COST;
+    let mut best_split = None;
+
+    for axis in 0..3 {
+
+        let (node_min, node_max) = (bounding_box.min[axis], bounding_box.max[axis]);
+        if node_max <= node_min { continue; }
+
+        let mut edges: Vec<Edge> = Vec::with_capacity(2 * count);
+        for &i in indices {
+            let b = boxes[i as usize];
+            edges.push(Edge { position: b.min[axis].max(node_min), is_start: true });
+            edges.push(Edge { position: b.max[axis].min(node_max), is_start: false });
+        }
+        // Where edges meet, starts come first, so that a Hittable that ends
+        // exactly where another starts is counted on both sides.
+        edges.sort_unstable_by(|a, b| a.position.total_cmp(&b.position).then(b.is_start.cmp(&a.is_start)));
+
+        let mut below_count = 0;
+        let mut above_count = count;
+
+        for edge in &edges {
+
+            if !edge.is_start { above_count -= 1; }
+
+            if node_min < edge.position && edge.position < node_max {
+
+                let mut below_box = bounding_box;
+                let mut above_box = bounding_box;
+                set_axis(&mut below_box.max, axis, edge.position);
+                set_axis(&mut above_box.min, axis, edge.position);
+
+                let bonus = if below_count == 0 || above_count == 0 { EMPTY_BONUS } else { 0.0 };
+                let weighted_count = below_box.surface_area() / area * below_count as f64
+                    + above_box.surface_area() / area * above_count as f64;
+                let cost = TRAVERSAL_COST + (1.0 - bonus) * INTERSECTION_COST * weighted_count;
+
+                if cost < best_cost {
+                    best_cost = cost;
+                    best_split = Some((axis, edge.position));
+                }
+            }
+
+            if edge.is_start { below_count += 1; }
+        }
+    }
+
+    best_split
+}
+
+///
+/// Sets one part of a Point3 by axis, where 0 is x, 1 is y and 2 is z.
+///
+/// # Arguments
+/// * `point` - The Point3.
+/// * `axis` - The axis.
+/// * `value` - The new value.
+///
+fn set_axis(point: &mut Point3, axis: usize, value: f64) {
+    match axis {
+        0 => point.x = value,
+        1 => point.y = value,
+        _ => point.z = value,
+    }
+}
+
+impl Hittable for KdTree {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the KdTree and a given Ray.
+    /// Nodes are visited in the order the Ray passes through them, and the
+    /// search stops once the closest Hit so far is nearer than the next node.
+    ///
+    /// # Arguments
+    /// * `&self` - The KdTree intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let (mut entry, mut exit) = self.bounding_box.get_intersect(ray, min_dist, max_dist)?;
+
+        let mut closest_distance = max_dist;
+        let mut hit: Option<Hit> = None;
+
+        // Each entry is a node's (index, entry, exit), for the far sides of
+        // the planes crossed so far.
+        let mut stack = [(0_usize, 0.0, 0.0); MAX_DEPTH];
+        let mut stack_size = 0;
+        let mut node_index = 0;
+
+        loop {
+
+            // Nodes are reached in order along the Ray, so everything left is further away.
+            if closest_distance < entry { break; }
+
+            match self.nodes[node_index] {
+
+                KdNode::Interior { axis, split, above } => {
+
+                    let axis = axis as usize;
+                    let origin = ray.origin[axis];
+                    let direction = ray.direction[axis];
+                    let below = node_index + 1;
+                    let above = above as usize;
+
+                    if direction == 0.0 {
+                        node_index = if origin <= split { below } else { above };
+                        continue;
+                    }
+
+                    // The near child is the one the Ray is in before it crosses the plane.
+                    let (near, far) = if 0.0 < direction { (below, above) } else { (above, below) };
+                    let split_distance = (split - origin) / direction;
+
+                    if exit <= split_distance {
+                        node_index = near;
+                    } else if split_distance <= entry {
+                        node_index = far;
+                    } else {
+                        stack[stack_size] = (far, split_distance, exit);
+                        stack_size += 1;
+                        node_index = near;
+                        exit = split_distance;
+                    }
+                    continue;
+                },
+
+                KdNode::Leaf { first, count } => {
+                    let first = first as usize;
+                    for &i in &self.hittable_indices[first..first + count as usize] {
+                        if let Some(h) = self.hittables[i as usize].get_hit(ray, min_dist, closest_distance) {
+                            closest_distance = h.distance;
+                            hit = Some(h);
+                        }
+                    }
+                },
+            }
+
+            if stack_size == 0 { break; }
+            stack_size -= 1;
+            (node_index, entry, exit) = stack[stack_size];
+        }
+
+        hit
+    }
+
+    ///
+    /// Returns true if there is a Hit between the KdTree and a given Ray. The
+    /// search stops at the first Hittable hit.
+    ///
+    /// # Arguments
+    /// * `&self` - The KdTree intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+
+        let (mut entry, mut exit) = match self.bounding_box.get_intersect(ray, min_dist, max_dist) {
+            Some(range) => range,
+            None => return false,
+        };
+
+        // Each entry is a node's (index, entry, exit), for the far sides of
+        // the planes crossed so far.
+        let mut stack = [(0_usize, 0.0, 0.0); MAX_DEPTH];
+        let mut stack_size = 0;
+        let mut node_index = 0;
+
+        loop {
+
+            match self.nodes[node_index] {
+
+                KdNode::Interior { axis, split, above } => {
+
+                    let axis = axis as usize;
+                    let origin = ray.origin[axis];
+                    let direction = ray.direction[axis];
+                    let below = node_index + 1;
+                    let above = above as usize;
+
+                    if direction == 0.0 {
+                        node_index = if origin <= split { below } else { above };
+                        continue;
+                    }
+
+                    let (near, far) = if 0.0 < direction { (below, above) } else { (above, below) };
+                    let split_distance = (split - origin) / direction;
+
+                    if exit <= split_distance {
+                        node_index = near;
+                    } else if split_distance <= entry {
+                        node_index = far;
+                    } else {
+                        stack[stack_size] = (far, split_distance, exit);
+                        stack_size += 1;
+                        node_index = near;
+                        exit = split_distance;
+                    }
+                    continue;
+                },
+
+                KdNode::Leaf { first, count } => {
+                    let first = first as usize;
+                    for &i in &self.hittable_indices[first..first + count as usize] {
+                        if self.hittables[i as usize].is_hit(ray, min_dist, max_dist) { return true; }
+                    }
+                },
+            }
+
+            if stack_size == 0 { return false; }
+            stack_size -= 1;
+            (node_index, entry, exit) = stack[stack_size];
+        }
+    }
+
+    ///
+    /// Returns an AABB that surrounds the KdTree.
+    ///
+    /// # Arguments
+    /// * `&self` - The KdTree.
+    ///
+    fn get_aabb(&self) -> AABB {
+        self.bounding_box
+    }
+
+    ///
+    /// Returns how many bytes the KdTree's nodes and lists of Hittables take up.
+    ///
+    /// # Arguments
+    /// * `&self` - The KdTree.
+    ///
+    fn get_memory_size(&self) -> usize {
+        size_of::<Self>()
+            + self.nodes.capacity() * size_of::<KdNode>()
+            + self.hittable_indices.capacity() * size_of::<u32>()
+            + self.hittables.capacity() * size_of::<Rc<dyn Hittable>>()
+    }
+}
+
+impl Accelerator for KdTree {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::bvh_node::construct_sah_bvh_root;
+    use crate::hittable::quad::Quad;
+    use crate::hittable::sphere::Sphere;
+    use crate::material::Material;
+    use crate::texture::monochrome::Monochrome;
+
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    fn get_material() -> Material {
+        Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None)
+    }
+
+    fn get_point(rng: &mut StdRng, size: f64) -> Point3 {
+        Point3::new(rng.gen_range(-size..size), rng.gen_range(-size..size), rng.gen_range(-size..size))
+    }
+
+    #[test]
+    fn hits_match_a_bvh() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut hittables: Vec<Rc<dyn Hittable>> = (0..300).map(|i| -> Rc<dyn Hittable> {
+            let center = get_point(&mut rng, 10.0);
+            if i % 3 == 0 {
+                Rc::new(Quad::new(center, get_point(&mut rng, 2.0), get_point(&mut rng, 2.0), get_material()))
+            } else {
+                Rc::new(Sphere::new(center, rng.gen_range(0.1..2.0), get_material()))
+            }
+        }).collect();
+        let kd_tree = KdTree::new(hittables.clone());
+        let length = hittables.len();
+        let bvh = construct_sah_bvh_root(&mut hittables, 0, length, 1);
+
+        for i in 0..2000 {
+            // Half of the Rays start inside the bounds, among the hittables.
+            let origin = get_point(&mut rng, if i % 2 == 0 { 8.0 } else { 15.0 });
+            let mut direction = get_point(&mut rng, 1.0);
+            if i % 5 == 0 { direction.y = 0.0; }
+            let ray = Ray::new(origin, direction);
+
+            let expected = bvh.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance);
+            assert_eq!(kd_tree.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance), expected);
+            assert_eq!(kd_tree.is_hit(ray, 0.001, f64::INFINITY), expected.is_some());
+        }
+    }
+}
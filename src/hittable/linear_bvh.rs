@@ -0,0 +1,1061 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::aabb::surrounding_box;
+use crate::hittable::accelerator::Accelerator;
+use crate::hittable::bvh_node::INTERSECTION_COST;
+use crate::hittable::bvh_node::TRAVERSAL_COST;
+use crate::hittable::bvh_node::partition_sah;
+use crate::hittable::Hittable;
+use crate::hittable::mesh::MeshData;
+use crate::hittable::quad::Quad;
+use crate::hittable::sphere::Sphere;
+use crate::Ray;
+use crate::vector3::Point3;
+
+use std::cell::Cell;
+use std::fs;
+use std::io;
+use std::mem::size_of;
+use std::rc::Rc;
+
+/// The deepest a LinearBvh can be. Trees built with the SAH are nowhere near
+/// this deep, but the traversal stack has a fixed size.
+const MAX_DEPTH: usize = 64;
+
+/// The first bytes of every LinearBvh cache file.
+const CACHE_MAGIC: &[u8; 8] = b"RTBVH\0\0\0";
+
+/// The version of the LinearBvh cache file format. A cache file starts with
+/// CACHE_MAGIC, this version, the geometry hash, the number of primitives and
+/// the number of nodes. Then come the nodes, the primitive order, and a
+/// checksum of everything before it. All numbers are little endian. This must
+/// go up whenever the format or the builder changes, so that old cache files
+/// are rebuilt instead of loaded.
+const CACHE_VERSION: u32 = 2;
+
+/// The size in bytes of a cache file's header.
+const CACHE_HEADER_SIZE: usize = 8 + 4 + 8 + 8 + 8;
+
+/// The size in bytes of a node in a cache file: six f64s for the AABB, then
+/// offset, primitive_count, axis and three bytes of padding.
+const CACHE_NODE_SIZE: usize = 6 * 8 + 4 + 4 + 1 + 3;
+
+/// The starting value of a 64 bit FNV-1a hash.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// The multiplier of a 64 bit FNV-1a hash.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A Hittable handed to a LinearBvh. Spheres and Quads are stored by value
+/// and checked without dynamic dispatch. Mesh triangles are stored as an
+/// index into their Mesh's shared geometry. Anything else is kept behind a
+/// Rc<dyn Hittable>.
+pub enum Primitive {
+    /// A Sphere.
+    Sphere(Sphere),
+    /// A Quad.
+    Quad(Quad),
+    /// The triangle with the given index in a Mesh's geometry.
+    Triangle(Rc<MeshData>, u32),
+    /// Any other Hittable.
+    Hittable(Rc<dyn Hittable>),
+}
+
+impl Hittable for Primitive {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Primitive and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Primitive intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+        match self {
+            Primitive::Sphere(sphere) => sphere.get_hit(ray, min_dist, max_dist),
+            Primitive::Quad(quad) => quad.get_hit(ray, min_dist, max_dist),
+            Primitive::Triangle(mesh, i) => mesh.get_triangle_hit(*i as usize, ray, min_dist, max_dist),
+            Primitive::Hittable(hittable) => hittable.get_hit(ray, min_dist, max_dist),
+        }
+    }
+
+    ///
+    /// Returns true if there is a Hit between the Primitive and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Primitive intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        match self {
+            Primitive::Sphere(sphere) => sphere.is_hit(ray, min_dist, max_dist),
+            Primitive::Quad(quad) => quad.is_hit(ray, min_dist, max_dist),
+            Primitive::Triangle(mesh, i) => mesh.is_triangle_hit(*i as usize, ray, min_dist, max_dist),
+            Primitive::Hittable(hittable) => hittable.is_hit(ray, min_dist, max_dist),
+        }
+    }
+
+    ///
+    /// Returns an AABB that surrounds the Primitive.
+    ///
+    /// # Arguments
+    /// * `&self` - The Primitive.
+    ///
+    fn get_aabb(&self) -> AABB {
+        match self {
+            Primitive::Sphere(sphere) => sphere.get_aabb(),
+            Primitive::Quad(quad) => quad.get_aabb(),
+            Primitive::Triangle(mesh, i) => mesh.get_triangle_aabb(*i as usize),
+            Primitive::Hittable(hittable) => hittable.get_aabb(),
+        }
+    }
+
+    ///
+    /// Refits the Primitive. Only Hittables behind a Rc<dyn Hittable> can
+    /// have moved.
+    ///
+    /// # Arguments
+    /// * `&self` - The Primitive.
+    ///
+    fn refit(&self) -> AABB {
+        match self {
+            Primitive::Hittable(hittable) => hittable.refit(),
+            _ => self.get_aabb(),
+        }
+    }
+
+    ///
+    /// Returns the SAH cost of checking the Primitive.
+    ///
+    /// # Arguments
+    /// * `&self` - The Primitive.
+    ///
+    fn get_sah_cost(&self) -> f64 {
+        match self {
+            Primitive::Hittable(hittable) => hittable.get_sah_cost(),
+            _ => INTERSECTION_COST,
+        }
+    }
+}
+
+/// Where a LinearBvh's leaf finds one of its primitives: a type, and an index
+/// into the LinearBvh's list of primitives of that type.
+#[derive(Clone, Copy)]
+enum PrimitiveIndex {
+    /// An index into spheres.
+    Sphere(u32),
+    /// An index into quads.
+    Quad(u32),
+    /// An index into meshes, and the index of the triangle in that Mesh.
+    Triangle(u32, u32),
+    /// An index into hittables.
+    Hittable(u32),
+}
+
+/// A node of a LinearBvh.
+struct LinearNode {
+    /// This node's AABB. In a Cell so that it can be refit.
+    bounding_box: Cell<AABB>,
+    /// For a leaf, the index of its first primitive in primitive_indices. For
+    /// an interior node, the index of its second child. The first child of an
+    /// interior node is always the node right after it.
+    offset: u32,
+    /// The number of primitives in a leaf, or 0 for an interior node.
+    primitive_count: u32,
+    /// The axis an interior node was split along, where 0 is x, 1 is y and 2 is z.
+    axis: u8,
+}
+
+/// Represents a bounding volume hierarchy compiled into a flat list of nodes.
+/// Nodes are stored depth first, so a node's first child comes right after
+/// it, and the tree is walked with a stack instead of recursion. The
+/// primitives are grouped by type, and each type is stored in the order the
+/// leaves reference them.
+pub struct LinearBvh {
+    /// The nodes of the hierarchy. The first node is the root.
+    nodes: Vec<LinearNode>,
+    /// The primitives referenced by the leaves, in leaf order.
+    primitive_indices: Vec<PrimitiveIndex>,
+    /// The Spheres in the hierarchy.
+    spheres: Vec<Sphere>,
+    /// The Quads in the hierarchy.
+    quads: Vec<Quad>,
+    /// The geometry of every Mesh with triangles in the hierarchy.
+    meshes: Vec<Rc<MeshData>>,
+    /// Every other Hittable in the hierarchy.
+    hittables: Vec<Rc<dyn Hittable>>,
+}
+
+impl LinearBvh {
+
+    ///
+    /// Returns a LinearBvh built over the given primitives with the surface
+    /// area heuristic. See construct_sah_bvh_root.
+    ///
+    /// # Arguments
+    /// * `primitives` - The primitives that will make up the leaves of the hierarchy. Must not be empty.
+    /// * `max_leaf_size` - The most primitives a leaf may hold.
+    ///
+    pub fn new(primitives: Vec<Primitive>, max_leaf_size: usize) -> Self {
+        let (nodes, order) = construct_layout(&primitives, max_leaf_size);
+        Self::from_layout(primitives, nodes, order)
+    }
+
+    ///
+    /// Returns a LinearBvh over the given primitives, loading its layout from
+    /// the given cache file if the file was saved for the same primitives.
+    /// Otherwise, the LinearBvh is built as usual and saved to the cache file
+    /// for next time. See the cache file format at CACHE_VERSION.
+    ///
+    /// A cache file only matches if the primitives' AABBs are exactly the same
+    /// as when it was saved, since the AABBs are all the builder looks at.
+    /// Files that are stale, damaged or from a different version are ignored
+    /// and overwritten.
+    ///
+    /// # Arguments
+    /// * `primitives` - The primitives that will make up the leaves of the hierarchy. Must not be empty.
+    /// * `max_leaf_size` - The most primitives a leaf may hold.
+    /// * `cache_path` - The path of the cache file.
+    ///
+    pub fn with_cache(primitives: Vec<Primitive>, max_leaf_size: usize, cache_path: &str) -> Self {
+
+        let geometry_hash = get_geometry_hash(&primitives, max_leaf_size);
+
+        let (nodes, order) = match read_cache(cache_path, geometry_hash, primitives.len()) {
+            Ok(layout) => layout,
+            Err(error) => {
+                if error.kind() != io::ErrorKind::NotFound {
+                    eprintln!("Rebuilding BVH cache {}: {}", cache_path, error);
+                }
+                let (nodes, order) = construct_layout(&primitives, max_leaf_size);
+                if let Err(error) = write_cache(cache_path, geometry_hash, &nodes, &order) {
+                    eprintln!("Could not save BVH cache {}: {}", cache_path, error);
+                }
+                (nodes, order)
+            },
+        };
+
+        Self::from_layout(primitives, nodes, order)
+    }
+
+    ///
+    /// Returns a LinearBvh with the given nodes, moving the primitives into
+    /// place in the order the leaves reference them.
+    ///
+    /// # Arguments
+    /// * `primitives` - The primitives of the hierarchy.
+    /// * `nodes` - The nodes of the hierarchy.
+    /// * `order` - The index in primitives of every primitive, in leaf order.
+    ///
+    fn from_layout(primitives: Vec<Primitive>, nodes: Vec<LinearNode>, order: Vec<usize>) -> Self {
+
+        // Move every primitive into the list for its type, in leaf order.
+        let mut slots: Vec<Option<Primitive>> = primitives.into_iter().map(Some).collect();
+        let mut primitive_indices = Vec::with_capacity(order.len());
+        let mut spheres = vec![];
+        let mut quads = vec![];
+        let mut meshes: Vec<Rc<MeshData>> = vec![];
+        let mut hittables = vec![];
+
+        for i in order {
+            match slots[i].take().unwrap() {
+                Primitive::Sphere(sphere) => {
+                    primitive_indices.push(PrimitiveIndex::Sphere(spheres.len() as u32));
+                    spheres.push(sphere);
+                },
+                Primitive::Quad(quad) => {
+                    primitive_indices.push(PrimitiveIndex::Quad(quads.len() as u32));
+                    quads.push(quad);
+                },
+                Primitive::Triangle(mesh, triangle) => {
+                    // A LinearBvh rarely holds more than a few Meshes.
+                    let mesh_index = match meshes.iter().position(|m| Rc::ptr_eq(m, &mesh)) {
+                        Some(mesh_index) => mesh_index,
+                        None => {
+                            meshes.push(mesh);
+                            meshes.len() - 1
+                        },
+                    };
+                    primitive_indices.push(PrimitiveIndex::Triangle(mesh_index as u32, triangle));
+                },
+                Primitive::Hittable(hittable) => {
+                    primitive_indices.push(PrimitiveIndex::Hittable(hittables.len() as u32));
+                    hittables.push(hittable);
+                },
+            }
+        }
+
+        Self {
+            nodes: nodes,
+            primitive_indices: primitive_indices,
+            spheres: spheres,
+            quads: quads,
+            meshes: meshes,
+            hittables: hittables,
+        }
+    }
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the given Ray and one of the
+    /// LinearBvh's primitives.
+    ///
+    /// # Arguments
+    /// * `&self` - The LinearBvh.
+    /// * `index` - Where the primitive is stored.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_primitive_hit(&self, index: PrimitiveIndex, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+        match index {
+            PrimitiveIndex::Sphere(i) => self.spheres[i as usize].get_hit(ray, min_dist, max_dist),
+            PrimitiveIndex::Quad(i) => self.quads[i as usize].get_hit(ray, min_dist, max_dist),
+            PrimitiveIndex::Triangle(m, i) => self.meshes[m as usize].get_triangle_hit(i as usize, ray, min_dist, max_dist),
+            PrimitiveIndex::Hittable(i) => self.hittables[i as usize].get_hit(ray, min_dist, max_dist),
+        }
+    }
+
+    ///
+    /// Returns true if there is a Hit between the given Ray and one of the
+    /// LinearBvh's primitives.
+    ///
+    /// # Arguments
+    /// * `&self` - The LinearBvh.
+    /// * `index` - Where the primitive is stored.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_primitive_hit(&self, index: PrimitiveIndex, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        match index {
+            PrimitiveIndex::Sphere(i) => self.spheres[i as usize].is_hit(ray, min_dist, max_dist),
+            PrimitiveIndex::Quad(i) => self.quads[i as usize].is_hit(ray, min_dist, max_dist),
+            PrimitiveIndex::Triangle(m, i) => self.meshes[m as usize].is_triangle_hit(i as usize, ray, min_dist, max_dist),
+            PrimitiveIndex::Hittable(i) => self.hittables[i as usize].is_hit(ray, min_dist, max_dist),
+        }
+    }
+
+    ///
+    /// Refits one of the LinearBvh's primitives and returns its new AABB.
+    ///
+    /// # Arguments
+    /// * `&self` - The LinearBvh.
+    /// * `index` - Where the primitive is stored.
+    ///
+    fn refit_primitive(&self, index: PrimitiveIndex) -> AABB {
+        match index {
+            PrimitiveIndex::Sphere(i) => self.spheres[i as usize].get_aabb(),
+            PrimitiveIndex::Quad(i) => self.quads[i as usize].get_aabb(),
+            PrimitiveIndex::Triangle(m, i) => self.meshes[m as usize].get_triangle_aabb(i as usize),
+            PrimitiveIndex::Hittable(i) => self.hittables[i as usize].refit(),
+        }
+    }
+
+    ///
+    /// Returns the SAH cost of the subtree with the given root. See
+    /// BvhNode::get_sah_cost.
+    ///
+    /// # Arguments
+    /// * `&self` - The LinearBvh.
+    /// * `index` - The index of the subtree's root.
+    ///
+    fn get_node_sah_cost(&self, index: usize) -> f64 {
+
+        let node = &self.nodes[index];
+
+        if 0 < node.primitive_count {
+            let first = node.offset as usize;
+            return self.primitive_indices[first..first + node.primitive_count as usize].iter().map(|&p| match p {
+                PrimitiveIndex::Hittable(i) => self.hittables[i as usize].get_sah_cost(),
+                _ => INTERSECTION_COST,
+            }).sum();
+        }
+
+        let area = node.bounding_box.get().surface_area();
+        let weight = |child: usize| {
+            if area <= 0.0 { 1.0 } else { self.nodes[child].bounding_box.get().surface_area() / area }
+        };
+
+        TRAVERSAL_COST
+            + weight(index + 1) * self.get_node_sah_cost(index + 1)
+            + weight(node.offset as usize) * self.get_node_sah_cost(node.offset as usize)
+    }
+}
+
+///
+/// Returns the (nodes, order) of a LinearBvh built over the given primitives,
+/// where order is the index of every primitive in leaf order.
+///
+/// # Arguments
+/// * `primitives` - The primitives that will make up the leaves of the hierarchy. Must not be empty.
+/// * `max_leaf_size` - The most primitives a leaf may hold.
+///
+fn construct_layout(primitives: &[Primitive], max_leaf_size: usize) -> (Vec<LinearNode>, Vec<usize>) {
+
+    assert!(!primitives.is_empty(), "a LinearBvh needs at least one primitive");
+
+    let mut items: Vec<(AABB, usize)> = primitives.iter().map(|p| p.get_aabb()).zip(0..).collect();
+    let mut order: Vec<usize> = Vec::with_capacity(items.len());
+    let mut nodes: Vec<LinearNode> = Vec::with_capacity(2 * items.len());
+    flatten(&mut items, max_leaf_size.max(1), 0, &mut nodes, &mut order);
+
+    (nodes, order)
+}
+
+///
+/// Appends the nodes of a BVH over the given items to nodes, depth first, and
+/// appends the items' indices to order in the same order the leaves reference
+/// them. Splits are found with partition_sah.
+///
+/// # Arguments
+/// * `items` - The (AABB, index) of every primitive in this part of the hierarchy.
+/// * `max_leaf_size` - The most primitives a leaf may hold.
+/// * `depth` - How deep in the hierarchy the new node is.
+/// * `nodes` - The flattened nodes.
+/// * `order` - The primitive indices in leaf order.
+///
+fn flatten(items: &mut [(AABB, usize)], max_leaf_size: usize, depth: usize, nodes: &mut Vec<LinearNode>, order: &mut Vec<usize>) {
+
+    let bounding_box = items.iter().map(|item| item.0).reduce(surrounding_box).unwrap();
+    let node_index = nodes.len();
+
+    let split = if items.len() == 1 {
+        None
+    } else {
+        let (middle, split_cost) = partition_sah(items, &|item: &(AABB, usize)| item.0);
+        let leaf_cost = items.len() as f64 * INTERSECTION_COST;
+        let is_leaf = items.len() <= max_leaf_size && leaf_cost <= split_cost;
+        // Past the maximum depth, whatever is left becomes one big leaf.
+        if is_leaf || MAX_DEPTH - 1 <= depth { None } else { Some(middle) }
+    };
+
+    match split {
+        None => {
+            nodes.push(LinearNode {
+                bounding_box: Cell::new(bounding_box),
+                offset: order.len() as u32,
+                primitive_count: u32::try_from(items.len()).expect("too many primitives in a LinearBvh leaf"),
+                axis: 0,
+            });
+            order.extend(items.iter().map(|item| item.1));
+        },
+        Some(middle) => {
+            // The split axis is the one the two halves' centroids are furthest apart on.
+            let left_centroid = items[..middle].iter().map(|item| item.0).reduce(surrounding_box).unwrap().centroid();
+            let right_centroid = items[middle..].iter().map(|item| item.0).reduce(surrounding_box).unwrap().centroid();
+            let gap = right_centroid - left_centroid;
+            let axis = (0..3).max_by(|&a, &b| gap[a].abs().total_cmp(&gap[b].abs())).unwrap();
+
+            nodes.push(LinearNode {
+                bounding_box: Cell::new(bounding_box),
+                offset: 0,
+                primitive_count: 0,
+                axis: axis as u8,
+            });
+            flatten(&mut items[..middle], max_leaf_size, depth + 1, nodes, order);
+            nodes[node_index].offset = nodes.len() as u32;
+            flatten(&mut items[middle..], max_leaf_size, depth + 1, nodes, order);
+        },
+    }
+}
+
+///
+/// Returns the 64 bit FNV-1a hash of the given bytes, continuing on from the
+/// given hash. Unlike std's hashers, it is the same on every run and every
+/// platform, so it can be saved to a file.
+///
+/// # Arguments
+/// * `hash` - The hash so far, or FNV_OFFSET_BASIS to start a new one.
+/// * `bytes` - The bytes to hash.
+///
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |h, &b| (h ^ b as u64).wrapping_mul(FNV_PRIME))
+}
+
+///
+/// Returns a hash of everything construct_layout looks at: the leaf size and
+/// the AABB of every primitive, in order.
+///
+/// # Arguments
+/// * `primitives` - The primitives of the hierarchy.
+/// * `max_leaf_size` - The most primitives a leaf may hold.
+///
+fn get_geometry_hash(primitives: &[Primitive], max_leaf_size: usize) -> u64 {
+
+    let mut hash = fnv1a(FNV_OFFSET_BASIS, &(max_leaf_size as u64).to_le_bytes());
+    hash = fnv1a(hash, &(primitives.len() as u64).to_le_bytes());
+
+    for primitive in primitives {
+        let b = primitive.get_aabb();
+        for v in [b.min.x, b.min.y, b.min.z, b.max.x, b.max.y, b.max.z] {
+            hash = fnv1a(hash, &v.to_bits().to_le_bytes());
+        }
+    }
+
+    hash
+}
+
+///
+/// Saves the layout of a LinearBvh to a cache file. See CACHE_VERSION.
+///
+/// # Arguments
+/// * `path` - The path of the cache file.
+/// * `geometry_hash` - The get_geometry_hash of the primitives the layout was built for.
+/// * `nodes` - The nodes of the hierarchy.
+/// * `order` - The index of every primitive in leaf order.
+///
+fn write_cache(path: &str, geometry_hash: u64, nodes: &[LinearNode], order: &[usize]) -> io::Result<()> {
+
+    let mut bytes = Vec::with_capacity(CACHE_HEADER_SIZE + nodes.len() * CACHE_NODE_SIZE + order.len() * 4 + 8);
+    bytes.extend_from_slice(CACHE_MAGIC);
+    bytes.extend_from_slice(&CACHE_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&geometry_hash.to_le_bytes());
+    bytes.extend_from_slice(&(order.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&(nodes.len() as u64).to_le_bytes());
+
+    for node in nodes {
+        let b = node.bounding_box.get();
+        for v in [b.min.x, b.min.y, b.min.z, b.max.x, b.max.y, b.max.z] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes.extend_from_slice(&node.offset.to_le_bytes());
+        bytes.extend_from_slice(&node.primitive_count.to_le_bytes());
+        bytes.push(node.axis);
+        bytes.extend_from_slice(&[0; 3]);
+    }
+
+    for &i in order {
+        bytes.extend_from_slice(&(i as u32).to_le_bytes());
+    }
+
+    let checksum = fnv1a(FNV_OFFSET_BASIS, &bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+
+    // Write to another file and rename it, so that a run that is stopped part
+    // way through never leaves half a cache file behind.
+    let temporary_path = format!("{}.tmp", path);
+    fs::write(&temporary_path, &bytes)?;
+    fs::rename(&temporary_path, path)
+}
+
+///
+/// Returns the (nodes, order) saved in a cache file by write_cache. Returns an
+/// error if the file can't be read, is from another version, was saved for
+/// different primitives, or is damaged in any way.
+///
+/// # Arguments
+/// * `path` - The path of the cache file.
+/// * `geometry_hash` - The get_geometry_hash of the primitives the layout is for.
+/// * `primitive_count` - The number of primitives the layout is for.
+///
+fn read_cache(path: &str, geometry_hash: u64, primitive_count: usize) -> io::Result<(Vec<LinearNode>, Vec<usize>)> {
+
+    let bytes = fs::read(path)?;
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    if bytes.len() < CACHE_HEADER_SIZE + 8 || &bytes[..8] != CACHE_MAGIC {
+        return Err(invalid("not a BVH cache file"));
+    }
+
+    let (body, checksum) = bytes.split_at(bytes.len() - 8);
+    if fnv1a(FNV_OFFSET_BASIS, body) != u64::from_le_bytes(checksum.try_into().unwrap()) {
+        return Err(invalid("checksum does not match"));
+    }
+
+    let mut position = 8;
+    let mut take = |size: usize| {
+        position += size;
+        &body[position - size..position]
+    };
+
+    if u32::from_le_bytes(take(4).try_into().unwrap()) != CACHE_VERSION {
+        return Err(invalid("saved by a different version"));
+    }
+    if u64::from_le_bytes(take(8).try_into().unwrap()) != geometry_hash {
+        return Err(invalid("saved for different geometry"));
+    }
+    if u64::from_le_bytes(take(8).try_into().unwrap()) != primitive_count as u64 {
+        return Err(invalid("saved for a different number of primitives"));
+    }
+    let node_count = u64::from_le_bytes(take(8).try_into().unwrap()) as usize;
+
+    let expected_size = node_count.checked_mul(CACHE_NODE_SIZE)
+        .and_then(|size| size.checked_add(CACHE_HEADER_SIZE + primitive_count * 4));
+    if expected_size != Some(body.len()) {
+        return Err(invalid("wrong file size"));
+    }
+
+    let nodes: Vec<LinearNode> = (0..node_count).map(|_| {
+        let mut v = [0.0; 6];
+        for x in &mut v {
+            *x = f64::from_le_bytes(take(8).try_into().unwrap());
+        }
+        let offset = u32::from_le_bytes(take(4).try_into().unwrap());
+        let primitive_count = u32::from_le_bytes(take(4).try_into().unwrap());
+        let axis = take(4)[0];
+        LinearNode {
+            bounding_box: Cell::new(AABB::new(Point3::new(v[0], v[1], v[2]), Point3::new(v[3], v[4], v[5]))),
+            offset: offset,
+            primitive_count: primitive_count,
+            axis: axis,
+        }
+    }).collect();
+
+    let order: Vec<usize> = (0..primitive_count)
+        .map(|_| u32::from_le_bytes(take(4).try_into().unwrap()) as usize)
+        .collect();
+
+    if !is_valid_layout(&nodes, &order) {
+        return Err(invalid("nodes do not make a valid hierarchy"));
+    }
+
+    Ok((nodes, order))
+}
+
+///
+/// Returns true if the given nodes are laid out the way flatten lays them
+/// out, so that walking them can never go out of bounds or overflow the
+/// traversal stack, and their leaves reference every primitive exactly once.
+///
+/// # Arguments
+/// * `nodes` - The nodes of the hierarchy.
+/// * `order` - The index of every primitive in leaf order.
+///
+fn is_valid_layout(nodes: &[LinearNode], order: &[usize]) -> bool {
+
+    let mut is_seen = vec![false; order.len()];
+    for &i in order {
+        if order.len() <= i || is_seen[i] { return false; }
+        is_seen[i] = true;
+    }
+
+    let mut next_leaf_offset = 0;
+    !nodes.is_empty()
+        && get_subtree_end(nodes, 0, 0, &mut next_leaf_offset) == Some(nodes.len())
+        && next_leaf_offset == order.len()
+}
+
+///
+/// Returns the index of the node right after the subtree with the given root,
+/// or None if the subtree is not laid out depth first with its leaves in
+/// order, or is too deep.
+///
+/// # Arguments
+/// * `nodes` - The nodes of the hierarchy.
+/// * `index` - The index of the subtree's root.
+/// * `depth` - How deep in the hierarchy the subtree's root is.
+/// * `next_leaf_offset` - Where in the primitive order the next leaf must start.
+///
+fn get_subtree_end(nodes: &[LinearNode], index: usize, depth: usize, next_leaf_offset: &mut usize) -> Option<usize> {
+
+    let node = nodes.get(index)?;
+    if MAX_DEPTH <= depth { return None; }
+
+    if 0 < node.primitive_count {
+        if node.offset as usize != *next_leaf_offset { return None; }
+        *next_leaf_offset += node.primitive_count as usize;
+        return Some(index + 1);
+    }
+
+    if 3 <= node.axis { return None; }
+    let left_end = get_subtree_end(nodes, index + 1, depth + 1, next_leaf_offset)?;
+    if node.offset as usize != left_end { return None; }
+    get_subtree_end(nodes, left_end, depth + 1, next_leaf_offset)
+}
+
+impl Hittable for LinearBvh {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the LinearBvh and a given Ray.
+    /// At each interior node, the child on the side the Ray comes from is
+    /// visited first, so close Hits are found early and used to skip any node
+    /// that is further away.
+    ///
+    /// # Arguments
+    /// * `&self` - The LinearBvh intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let is_negative = [ray.direction.x < 0.0, ray.direction.y < 0.0, ray.direction.z < 0.0];
+        let mut closest_distance = max_dist;
+        let mut hit: Option<Hit> = None;
+
+        let mut stack = [0_u32; MAX_DEPTH];
+        let mut stack_size = 0;
+        let mut node_index = 0;
+
+        loop {
+
+            let node = &self.nodes[node_index];
+
+            if node.bounding_box.get().get_intersect(ray, min_dist, closest_distance).is_some() {
+
+                if 0 < node.primitive_count {
+
+                    let first = node.offset as usize;
+                    for &index in &self.primitive_indices[first..first + node.primitive_count as usize] {
+                        if let Some(h) = self.get_primitive_hit(index, ray, min_dist, closest_distance) {
+                            closest_distance = h.distance;
+                            hit = Some(h);
+                        }
+                    }
+
+                } else {
+
+                    // Visit the near child now and the far child later.
+                    let (near, far) = if is_negative[node.axis as usize] {
+                        (node.offset as usize, node_index + 1)
+                    } else {
+                        (node_index + 1, node.offset as usize)
+                    };
+                    stack[stack_size] = far as u32;
+                    stack_size += 1;
+                    node_index = near;
+                    continue;
+                }
+            }
+
+            if stack_size == 0 { break; }
+            stack_size -= 1;
+            node_index = stack[stack_size] as usize;
+        }
+
+        hit
+    }
+
+    ///
+    /// Returns true if there is a Hit between the LinearBvh and a given Ray.
+    /// Any Hit will do, so the children are visited in storage order and the
+    /// traversal stops at the first primitive hit.
+    ///
+    /// # Arguments
+    /// * `&self` - The LinearBvh intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+
+        let mut stack = [0_u32; MAX_DEPTH];
+        let mut stack_size = 0;
+        let mut node_index = 0;
+
+        loop {
+
+            let node = &self.nodes[node_index];
+
+            if node.bounding_box.get().is_hit(ray, min_dist, max_dist) {
+
+                if 0 < node.primitive_count {
+
+                    let first = node.offset as usize;
+                    for &index in &self.primitive_indices[first..first + node.primitive_count as usize] {
+                        if self.is_primitive_hit(index, ray, min_dist, max_dist) { return true; }
+                    }
+
+                } else {
+
+                    stack[stack_size] = node.offset;
+                    stack_size += 1;
+                    node_index += 1;
+                    continue;
+                }
+            }
+
+            if stack_size == 0 { return false; }
+            stack_size -= 1;
+            node_index = stack[stack_size] as usize;
+        }
+    }
+
+    ///
+    /// Returns an AABB that surrounds the LinearBvh.
+    ///
+    /// # Arguments
+    /// * `&self` - The LinearBvh.
+    ///
+    fn get_aabb(&self) -> AABB {
+        self.nodes[0].bounding_box.get()
+    }
+
+    ///
+    /// Refits every node from the bottom up. A node's children are always
+    /// stored after it, so going through the nodes backwards reaches both
+    /// children before their parent.
+    ///
+    /// # Arguments
+    /// * `&self` - The LinearBvh.
+    ///
+    fn refit(&self) -> AABB {
+        for (index, node) in self.nodes.iter().enumerate().rev() {
+            let bounding_box = if 0 < node.primitive_count {
+                let first = node.offset as usize;
+                self.primitive_indices[first..first + node.primitive_count as usize].iter()
+                    .map(|&p| self.refit_primitive(p))
+                    .reduce(surrounding_box)
+                    .unwrap()
+            } else {
+                surrounding_box(self.nodes[index + 1].bounding_box.get(), self.nodes[node.offset as usize].bounding_box.get())
+            };
+            node.bounding_box.set(bounding_box);
+        }
+        self.get_aabb()
+    }
+
+    ///
+    /// Returns the SAH cost of the LinearBvh.
+    ///
+    /// # Arguments
+    /// * `&self` - The LinearBvh.
+    ///
+    fn get_sah_cost(&self) -> f64 {
+        self.get_node_sah_cost(0)
+    }
+
+    ///
+    /// Returns how many bytes the LinearBvh's nodes and primitive lists take
+    /// up. Spheres and Quads are stored in the lists themselves, so unlike
+    /// other Hittables, they are counted too.
+    ///
+    /// # Arguments
+    /// * `&self` - The LinearBvh.
+    ///
+    fn get_memory_size(&self) -> usize {
+        size_of::<Self>()
+            + self.nodes.capacity() * size_of::<LinearNode>()
+            + self.primitive_indices.capacity() * size_of::<PrimitiveIndex>()
+            + self.spheres.capacity() * size_of::<Sphere>()
+            + self.quads.capacity() * size_of::<Quad>()
+            + self.meshes.capacity() * size_of::<Rc<MeshData>>()
+            + self.hittables.capacity() * size_of::<Rc<dyn Hittable>>()
+    }
+}
+
+impl Accelerator for LinearBvh {
+
+    ///
+    /// Returns true, since a LinearBvh can be refit.
+    ///
+    /// # Arguments
+    /// * `&self` - The LinearBvh.
+    ///
+    fn can_refit(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::instance::Instance;
+    use crate::material::Material;
+    use crate::texture::monochrome::Monochrome;
+    use crate::transform::Transform;
+
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    fn get_material() -> Material {
+        Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None)
+    }
+
+    fn get_point(rng: &mut StdRng, size: f64) -> Point3 {
+        Point3::new(rng.gen_range(-size..size), rng.gen_range(-size..size), rng.gen_range(-size..size))
+    }
+
+    /// Returns a mix of Spheres, Quads and Instances.
+    fn get_hittables(rng: &mut StdRng) -> Vec<Rc<dyn Hittable>> {
+        let shared: Rc<dyn Hittable> = Rc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 0.5, get_material()));
+        (0..300).map(|i| -> Rc<dyn Hittable> {
+            let center = get_point(rng, 10.0);
+            match i % 3 {
+                0 => Rc::new(Sphere::new(center, rng.gen_range(0.1..0.8), get_material())),
+                1 => Rc::new(Quad::new(center, get_point(rng, 1.0), get_point(rng, 1.0), get_material())),
+                _ => Rc::new(Instance::new(Rc::clone(&shared), Transform::translation(center))),
+            }
+        }).collect()
+    }
+
+    fn get_primitives(hittables: &[Rc<dyn Hittable>]) -> Vec<Primitive> {
+        hittables.iter()
+            .map(|hittable| hittable.get_primitive().unwrap_or_else(|| Primitive::Hittable(Rc::clone(hittable))))
+            .collect()
+    }
+
+    fn get_closest_distance(hittables: &[Rc<dyn Hittable>], ray: Ray) -> Option<f64> {
+        hittables.iter()
+            .filter_map(|hittable| hittable.get_hit(ray, 0.001, f64::INFINITY))
+            .map(|hit| hit.distance)
+            .min_by(f64::total_cmp)
+    }
+
+    fn get_random_ray(rng: &mut StdRng) -> Ray {
+        Ray::new(get_point(rng, 15.0), get_point(rng, 1.0))
+    }
+
+    /// Returns a path in the temporary directory that no other test uses.
+    fn get_cache_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("linear_bvh_{}_{}.bvh", name, std::process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    /// Returns every number stored for the nodes, so layouts can be compared.
+    fn get_layout_numbers(nodes: &[LinearNode]) -> Vec<f64> {
+        nodes.iter().flat_map(|node| {
+            let b = node.bounding_box.get();
+            [b.min.x, b.min.y, b.min.z, b.max.x, b.max.y, b.max.z, node.offset as f64, node.primitive_count as f64, node.axis as f64]
+        }).collect()
+    }
+
+    /// Writes a cache file for some random primitives and returns them with
+    /// the file's bytes and the geometry hash it was saved for.
+    fn write_test_cache(path: &str) -> (Vec<Primitive>, Vec<u8>, u64) {
+        let mut rng = StdRng::seed_from_u64(5);
+        let primitives = get_primitives(&get_hittables(&mut rng));
+        let (nodes, order) = construct_layout(&primitives, 4);
+        let geometry_hash = get_geometry_hash(&primitives, 4);
+        write_cache(path, geometry_hash, &nodes, &order).unwrap();
+        (primitives, fs::read(path).unwrap(), geometry_hash)
+    }
+
+    #[test]
+    fn spheres_and_quads_are_stored_by_value() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let bvh = LinearBvh::new(get_primitives(&get_hittables(&mut rng)), 4);
+        assert_eq!((bvh.spheres.len(), bvh.quads.len(), bvh.hittables.len()), (100, 100, 100));
+    }
+
+    #[test]
+    fn hits_match_checking_every_hittable() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let hittables = get_hittables(&mut rng);
+        for max_leaf_size in [1, 4] {
+            let bvh = LinearBvh::new(get_primitives(&hittables), max_leaf_size);
+            for _ in 0..2000 {
+                let ray = get_random_ray(&mut rng);
+                let expected = get_closest_distance(&hittables, ray);
+                assert_eq!(bvh.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance), expected);
+                assert_eq!(bvh.is_hit(ray, 0.001, f64::INFINITY), expected.is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn mesh_triangles_share_their_geometry() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let meshes: Vec<Rc<MeshData>> = (0..2).map(|_| Rc::new(MeshData {
+            positions: (0..300).map(|_| get_point(&mut rng, 10.0)).collect(),
+            normals: vec![],
+            uvs: vec![],
+            triangles: (0..100).map(|i| [3 * i, 3 * i + 1, 3 * i + 2]).collect(),
+            material: get_material(),
+        })).collect();
+        let primitives: Vec<Primitive> = (0..200)
+            .map(|i| Primitive::Triangle(Rc::clone(&meshes[i % 2]), (i / 2) as u32))
+            .collect();
+        let bvh = LinearBvh::new(primitives, 4);
+        assert_eq!((bvh.meshes.len(), bvh.primitive_indices.len()), (2, 200));
+
+        for _ in 0..2000 {
+            let ray = get_random_ray(&mut rng);
+            let expected = meshes.iter()
+                .flat_map(|mesh| (0..100).filter_map(move |i| mesh.get_triangle_hit(i, ray, 0.001, f64::INFINITY)))
+                .map(|hit| hit.distance)
+                .min_by(f64::total_cmp);
+            assert_eq!(bvh.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance), expected);
+            assert_eq!(bvh.is_hit(ray, 0.001, f64::INFINITY), expected.is_some());
+        }
+    }
+
+    #[test]
+    fn valid_cache_files_load_the_saved_layout() {
+        let path = get_cache_path("valid");
+        let (primitives, _, geometry_hash) = write_test_cache(&path);
+        let (nodes, order) = construct_layout(&primitives, 4);
+
+        let (cached_nodes, cached_order) = read_cache(&path, geometry_hash, primitives.len()).unwrap();
+        assert_eq!(get_layout_numbers(&cached_nodes), get_layout_numbers(&nodes));
+        assert_eq!(cached_order, order);
+
+        let bvh = LinearBvh::with_cache(primitives, 4, &path);
+        assert_eq!(get_layout_numbers(&bvh.nodes), get_layout_numbers(&nodes));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn damaged_cache_files_are_rejected() {
+        let path = get_cache_path("damaged");
+        let (primitives, mut bytes, geometry_hash) = write_test_cache(&path);
+        for i in [0, CACHE_HEADER_SIZE + 3, bytes.len() / 2, bytes.len() - 1] {
+            bytes[i] ^= 0x10;
+            fs::write(&path, &bytes).unwrap();
+            let error = read_cache(&path, geometry_hash, primitives.len()).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            bytes[i] ^= 0x10;
+        }
+
+        // A damaged file is rebuilt and saved again.
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0x10;
+        fs::write(&path, &bytes).unwrap();
+        let (nodes, _) = construct_layout(&primitives, 4);
+        let bvh = LinearBvh::with_cache(primitives, 4, &path);
+        assert_eq!(get_layout_numbers(&bvh.nodes), get_layout_numbers(&nodes));
+        bytes[middle] ^= 0x10;
+        assert_eq!(fs::read(&path).unwrap(), bytes);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn cache_files_for_other_versions_or_geometry_are_rejected() {
+        let path = get_cache_path("stale");
+        let (primitives, mut bytes, geometry_hash) = write_test_cache(&path);
+
+        assert!(read_cache(&path, geometry_hash + 1, primitives.len()).is_err());
+        assert!(read_cache(&path, geometry_hash, primitives.len() - 1).is_err());
+        assert!(read_cache(&path, get_geometry_hash(&primitives, 2), primitives.len()).is_err());
+
+        // Give the file another version with a checksum that still matches.
+        bytes.truncate(bytes.len() - 8);
+        bytes[8..12].copy_from_slice(&(CACHE_VERSION + 1).to_le_bytes());
+        let checksum = fnv1a(FNV_OFFSET_BASIS, &bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        fs::write(&path, &bytes).unwrap();
+        let error = read_cache(&path, geometry_hash, primitives.len()).err().unwrap();
+        assert_eq!(error.to_string(), "saved by a different version");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn refit_follows_moved_instances() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let sphere: Rc<dyn Hittable> = Rc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 0.5, get_material()));
+        let instances: Vec<Rc<Instance>> = (0..50)
+            .map(|_| Rc::new(Instance::new(Rc::clone(&sphere), Transform::translation(get_point(&mut rng, 10.0)))))
+            .collect();
+        let hittables: Vec<Rc<dyn Hittable>> = instances.iter().map(|i| Rc::clone(i) as Rc<dyn Hittable>).collect();
+        let bvh = LinearBvh::new(get_primitives(&hittables), 2);
+
+        for instance in &instances {
+            instance.set_transform(Transform::translation(get_point(&mut rng, 10.0)));
+        }
+        bvh.refit();
+
+        for _ in 0..2000 {
+            let ray = get_random_ray(&mut rng);
+            let expected = get_closest_distance(&hittables, ray);
+            assert_eq!(bvh.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance), expected);
+        }
+        assert!(bvh.can_refit());
+    }
+}
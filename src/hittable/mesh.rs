@@ -0,0 +1,629 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::bvh_node::DEFAULT_MAX_LEAF_SIZE;
+use crate::hittable::get_distance_past;
+use crate::hittable::get_opaque_intersect;
+use crate::hittable::Hittable;
+use crate::hittable::linear_bvh::LinearBvh;
+use crate::hittable::linear_bvh::Primitive;
+use crate::hittable::triangle::get_triangle_aabb;
+use crate::hittable::triangle::get_barycentric;
+use crate::hittable::triangle::get_triangle_intersect;
+use crate::material::Material;
+use crate::Ray;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::rc::Rc;
+
+/// The (position, uv, normal) indices of a face's corner in an OBJ file.
+type FaceCorner = (usize, Option<usize>, Option<usize>);
+
+/// The vertices and triangles of a Mesh, as read from a Wavefront OBJ file
+/// or displaced, ready to be made into one.
+pub struct ObjData {
+    /// The position of every vertex.
+    pub positions: Vec<Point3>,
+    /// The indices of every triangle's vertices.
+    pub triangles: Vec<[usize; 3]>,
+    /// The unit normal of every vertex, or empty if the file has none.
+    pub normals: Vec<Vector3>,
+    /// The texture coordinates of every vertex, or empty if the file has none.
+    pub uvs: Vec<(f64, f64)>,
+}
+
+/// The geometry of a Mesh. Shared by every triangle of the Mesh, so vertices
+/// are only stored once no matter how many triangles use them.
+pub struct MeshData {
+    /// The position of every vertex.
+    pub positions: Vec<Point3>,
+    /// The unit normal of every vertex, or empty to shade each triangle flat.
+    pub normals: Vec<Vector3>,
+    /// The (u, v) texture coordinates of every vertex, or empty to use each
+    /// triangle's barycentric coordinates.
+    pub uvs: Vec<(f64, f64)>,
+    /// The indices of every triangle's three vertices, in counter-clockwise
+    /// order when seen from the front.
+    pub triangles: Vec<[usize; 3]>,
+    /// The mesh's material.
+    pub material: Material,
+}
+
+impl MeshData {
+
+    ///
+    /// Returns the corners of the triangle with the given index.
+    ///
+    /// # Arguments
+    /// * `&self` - The MeshData.
+    /// * `triangle` - The index of the triangle.
+    ///
+    pub fn get_vertices(&self, triangle: usize) -> [Point3; 3] {
+        let [a, b, c] = self.triangles[triangle];
+        [self.positions[a], self.positions[b], self.positions[c]]
+    }
+
+    ///
+    /// Returns the uv coordinates at the given barycentric coordinates of one
+    /// of the triangles, interpolated from its vertices' if the Mesh has them.
+    ///
+    /// # Arguments
+    /// * `&self` - The MeshData.
+    /// * `triangle` - The index of the triangle.
+    /// * `b1` - The weight of the triangle's second vertex.
+    /// * `b2` - The weight of the triangle's third vertex.
+    ///
+    fn get_triangle_uv(&self, triangle: usize, b1: f64, b2: f64) -> (f64, f64) {
+        if self.uvs.is_empty() {
+            return (b1, b2);
+        }
+        let [i0, i1, i2] = self.triangles[triangle];
+        let b0 = 1.0 - b1 - b2;
+        let t = &self.uvs;
+        (b0 * t[i0].0 + b1 * t[i1].0 + b2 * t[i2].0, b0 * t[i0].1 + b1 * t[i1].1 + b2 * t[i2].1)
+    }
+
+    ///
+    /// Returns the point sampled SurfacePoint of an intersection with one of
+    /// the triangles, in the same space as the Ray. The Mesh's vertices are
+    /// already in its own space, so the point is also the object_point.
+    ///
+    /// # Arguments
+    /// * `&self` - The MeshData.
+    /// * `triangle` - The index of the triangle.
+    /// * `ray` - The intersecting Ray.
+    /// * `intersect` - The (distance, b1, b2) of the intersection.
+    ///
+    fn get_triangle_surface(&self, triangle: usize, ray: Ray, (distance, b1, b2): (f64, f64, f64)) -> SurfacePoint {
+        let normal = if self.normals.is_empty() {
+            let [p0, p1, p2] = self.get_vertices(triangle);
+            (p1 - p0).cross(p2 - p0).unit()
+        } else {
+            let [i0, i1, i2] = self.triangles[triangle];
+            let n = &self.normals;
+            ((1.0 - b1 - b2) * n[i0] + b1 * n[i1] + b2 * n[i2]).unit()
+        };
+        let point = ray.get_point(distance);
+        SurfacePoint::new(self.get_triangle_uv(triangle, b1, b2), point, point, normal)
+    }
+
+    ///
+    /// Returns the (distance, b1, b2) of the intersection between one of the
+    /// triangles and a given Ray, if there is one that isn't cut out by the
+    /// Mesh's opacity mask.
+    ///
+    /// # Arguments
+    /// * `&self` - The MeshData.
+    /// * `triangle` - The index of the triangle.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_opaque_triangle_intersect(&self, triangle: usize, ray: Ray, min_dist: f64, max_dist: f64) -> Option<(f64, f64, f64)> {
+        let vertices = self.get_vertices(triangle);
+        get_opaque_intersect(
+            &self.material,
+            ray,
+            min_dist,
+            |min_dist| get_triangle_intersect(ray, vertices, min_dist, max_dist),
+            |intersect| self.get_triangle_surface(triangle, ray, intersect),
+            |(distance, _, _)| get_distance_past(distance),
+        )
+    }
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between one of the triangles and a
+    /// given Ray. Vertex normals and texture coordinates are interpolated
+    /// across the triangle if the Mesh has them.
+    ///
+    /// # Arguments
+    /// * `&self` - The MeshData.
+    /// * `triangle` - The index of the triangle intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    pub fn get_triangle_hit(&self, triangle: usize, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let intersect = self.get_opaque_triangle_intersect(triangle, ray, min_dist, max_dist)?;
+        let vertices = self.get_vertices(triangle);
+        let geometric_normal = (vertices[1] - vertices[0]).cross(vertices[2] - vertices[0]);
+        let is_front = ray.direction.dot(geometric_normal) < 0.0;
+
+        let surface = self.get_triangle_surface(triangle, ray, intersect).with_derivatives(ray, false, |point| {
+            let (b1, b2) = get_barycentric(point, vertices);
+            self.get_triangle_uv(triangle, b1, b2)
+        });
+
+        let material = &self.material;
+        let normal = material.get_normal(surface);
+        Some(Hit::new(
+            ray,
+            intersect.0,
+            if is_front { normal } else { -normal },
+            is_front,
+            material.texture.get_color(surface),
+            material.get_reflectivity(surface),
+            material.get_refraction_index(surface),
+        ))
+    }
+
+    ///
+    /// Returns true if there is a Hit between one of the triangles and a given
+    /// Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The MeshData.
+    /// * `triangle` - The index of the triangle intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    pub fn is_triangle_hit(&self, triangle: usize, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.get_opaque_triangle_intersect(triangle, ray, min_dist, max_dist).is_some()
+    }
+
+    ///
+    /// Returns an AABB that surrounds one of the triangles.
+    ///
+    /// # Arguments
+    /// * `&self` - The MeshData.
+    /// * `triangle` - The index of the triangle.
+    ///
+    pub fn get_triangle_aabb(&self, triangle: usize) -> AABB {
+        get_triangle_aabb(self.get_vertices(triangle))
+    }
+}
+
+/// Represents a surface made of triangles that share vertices. The triangles
+/// are kept in their own BVH, so a Mesh can be placed in a scene any number
+/// of times with Instances without its triangles ever being sorted again.
+pub struct Mesh {
+    /// The BVH over the Mesh's triangles.
+    bvh: LinearBvh,
+}
+
+impl Mesh {
+
+    ///
+    /// Returns a Mesh constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `positions` - The position of every vertex.
+    /// * `triangles` - The indices of every triangle's vertices. Must not be empty.
+    /// * `normals` - The unit normal of every vertex, or empty to shade each triangle flat.
+    /// * `uvs` - The texture coordinates of every vertex, or empty.
+    /// * `material` - The Mesh's material.
+    ///
+    pub fn new(positions: Vec<Point3>, triangles: Vec<[usize; 3]>, normals: Vec<Vector3>, uvs: Vec<(f64, f64)>, material: Material) -> Self {
+        Self::construct(positions, triangles, normals, uvs, material, None)
+    }
+
+    ///
+    /// Returns a Mesh constructed from the given arguments, with its BVH
+    /// loaded from the given cache file if it was saved for the same triangles,
+    /// or built and saved there otherwise. See LinearBvh::with_cache.
+    ///
+    /// # Arguments
+    /// * `positions` - The position of every vertex.
+    /// * `triangles` - The indices of every triangle's vertices. Must not be empty.
+    /// * `normals` - The unit normal of every vertex, or empty to shade each triangle flat.
+    /// * `uvs` - The texture coordinates of every vertex, or empty.
+    /// * `material` - The Mesh's material.
+    /// * `cache_path` - The path of the BVH cache file.
+    ///
+    pub fn with_cache(positions: Vec<Point3>, triangles: Vec<[usize; 3]>, normals: Vec<Vector3>, uvs: Vec<(f64, f64)>, material: Material, cache_path: &str) -> Self {
+        Self::construct(positions, triangles, normals, uvs, material, Some(cache_path))
+    }
+
+    ///
+    /// Returns a Mesh constructed from the given arguments. Its BVH is cached
+    /// if a cache path is given.
+    ///
+    /// # Arguments
+    /// * `positions` - The position of every vertex.
+    /// * `triangles` - The indices of every triangle's vertices. Must not be empty.
+    /// * `normals` - The unit normal of every vertex, or empty to shade each triangle flat.
+    /// * `uvs` - The texture coordinates of every vertex, or empty.
+    /// * `material` - The Mesh's material.
+    /// * `cache_path` - The path of the BVH cache file, if any.
+    ///
+    fn construct(positions: Vec<Point3>, triangles: Vec<[usize; 3]>, normals: Vec<Vector3>, uvs: Vec<(f64, f64)>, material: Material, cache_path: Option<&str>) -> Self {
+
+        assert!(!triangles.is_empty(), "a Mesh needs at least one triangle");
+        assert!(normals.is_empty() || normals.len() == positions.len());
+        assert!(uvs.is_empty() || uvs.len() == positions.len());
+
+        let data = Rc::new(MeshData {
+            positions: positions,
+            normals: normals,
+            uvs: uvs,
+            triangles: triangles,
+            material: material,
+        });
+
+        let primitives: Vec<Primitive> = (0..data.triangles.len() as u32)
+            .map(|index| Primitive::Triangle(Rc::clone(&data), index))
+            .collect();
+
+        Self {
+            bvh: match cache_path {
+                Some(path) => LinearBvh::with_cache(primitives, DEFAULT_MAX_LEAF_SIZE, path),
+                None => LinearBvh::new(primitives, DEFAULT_MAX_LEAF_SIZE),
+            },
+        }
+    }
+
+    ///
+    /// Returns a Mesh read from a Wavefront OBJ file. Only vertex positions,
+    /// normals, texture coordinates and faces are read. Faces with more than
+    /// three corners are split into triangles fanning out from their first
+    /// corner. Since OBJ faces index positions, normals and texture coordinates
+    /// separately, every distinct combination becomes its own vertex.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the OBJ file.
+    /// * `material` - The Mesh's material.
+    ///
+    pub fn from_obj(path: &str, material: Material) -> io::Result<Self> {
+        let ObjData { positions, triangles, normals, uvs } = read_obj(path)?;
+        Ok(Self::new(positions, triangles, normals, uvs, material))
+    }
+
+    ///
+    /// Returns a Mesh read from a Wavefront OBJ file, like from_obj, with its
+    /// BVH cached in a file next to the OBJ file with .bvh added to its name.
+    /// Later runs load the BVH from there instead of building it again, as
+    /// long as the OBJ file's geometry hasn't changed.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the OBJ file.
+    /// * `material` - The Mesh's material.
+    ///
+    pub fn from_obj_cached(path: &str, material: Material) -> io::Result<Self> {
+        let ObjData { positions, triangles, normals, uvs } = read_obj(path)?;
+        Ok(Self::with_cache(positions, triangles, normals, uvs, material, &format!("{}.bvh", path)))
+    }
+
+    ///
+    /// Returns a Mesh read from a Wavefront OBJ file, like from_obj, with its
+    /// surface displaced by a texture. See displace.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the OBJ file.
+    /// * `material` - The Mesh's material.
+    /// * `displacement` - How far out to push the surface, read with Texture::get_value.
+    /// * `scale` - How far a displacement of 1 pushes the surface.
+    /// * `subdivisions` - How many times to split every triangle into four first.
+    ///
+    pub fn from_obj_displaced(path: &str, material: Material, displacement: &dyn Texture, scale: f64, subdivisions: u32) -> io::Result<Self> {
+        let ObjData { positions, triangles, normals, uvs } = read_obj(path)?;
+        let ObjData { positions, triangles, normals, uvs } = displace(&positions, &triangles, &normals, &uvs, displacement, scale, subdivisions);
+        Ok(Self::new(positions, triangles, normals, uvs, material))
+    }
+}
+
+///
+/// Returns the vertices and triangles of a Mesh with its surface truly
+/// displaced, rather than only shaded as if it were, like a bump map.
+/// Every triangle is first split into four by its edges' midpoints the given
+/// number of times, so that there are vertices to move, then every vertex is
+/// pushed along its normal by the displacement texture. The displaced Mesh is
+/// shaded smoothly with new normals.
+///
+/// Vertices are looked up with their position as both the point and the
+/// object point, like a Mesh's hits are. Meshes split at uv seams have two
+/// vertices in the same place there, which can crack apart if a uv texture
+/// pushes them apart differently.
+///
+/// # Arguments
+/// * `positions` - The position of every vertex.
+/// * `triangles` - The indices of every triangle's vertices.
+/// * `normals` - The unit normal of every vertex, or empty to use smooth normals.
+/// * `uvs` - The texture coordinates of every vertex, or empty.
+/// * `displacement` - How far out to push the surface, read with Texture::get_value.
+/// * `scale` - How far a displacement of 1 pushes the surface.
+/// * `subdivisions` - How many times to split every triangle into four first.
+///
+pub fn displace(
+    positions: &[Point3],
+    triangles: &[[usize; 3]],
+    normals: &[Vector3],
+    uvs: &[(f64, f64)],
+    displacement: &dyn Texture,
+    scale: f64,
+    subdivisions: u32,
+) -> ObjData {
+
+    let mut positions = positions.to_vec();
+    let mut triangles = triangles.to_vec();
+    let mut normals = if normals.is_empty() { get_smooth_normals(&positions, &triangles) } else { normals.to_vec() };
+    let mut uvs = uvs.to_vec();
+
+    for _ in 0..subdivisions {
+
+        // Triangles that share an edge share its midpoint, so the surface
+        // stays closed.
+        let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut get_midpoint = |a: usize, b: usize| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                positions.push(0.5 * (positions[a] + positions[b]));
+                let normal = normals[a] + normals[b];
+                normals.push(if normal.near_zero() { normals[a] } else { normal.unit() });
+                if !uvs.is_empty() {
+                    uvs.push((0.5 * (uvs[a].0 + uvs[b].0), 0.5 * (uvs[a].1 + uvs[b].1)));
+                }
+                positions.len() - 1
+            })
+        };
+
+        triangles = triangles.iter().flat_map(|&[a, b, c]| {
+            let (ab, bc, ca) = (get_midpoint(a, b), get_midpoint(b, c), get_midpoint(c, a));
+            [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+        }).collect();
+    }
+
+    for (i, position) in positions.iter_mut().enumerate() {
+        let uv = if uvs.is_empty() { (0.0, 0.0) } else { uvs[i] };
+        let surface = SurfacePoint::new(uv, *position, *position, normals[i]);
+        *position += scale * displacement.get_value(surface) * normals[i];
+    }
+
+    let normals = get_smooth_normals(&positions, &triangles);
+    ObjData {
+        positions: positions,
+        triangles: triangles,
+        normals: normals,
+        uvs: uvs,
+    }
+}
+
+///
+/// Returns the vertices and triangles of the Mesh in a Wavefront OBJ file. See
+/// Mesh::from_obj.
+///
+/// # Arguments
+/// * `path` - The path of the OBJ file.
+///
+fn read_obj(path: &str) -> io::Result<ObjData> {
+
+    let invalid = |line: usize| io::Error::new(io::ErrorKind::InvalidData, format!("invalid OBJ data on line {}", line + 1));
+
+    let mut obj_positions: Vec<Point3> = vec![];
+    let mut obj_normals: Vec<Vector3> = vec![];
+    let mut obj_uvs: Vec<(f64, f64)> = vec![];
+    let mut faces: Vec<Vec<FaceCorner>> = vec![];
+
+    for (line_number, line) in fs::read_to_string(path)?.lines().enumerate() {
+
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let numbers = |tokens: std::str::SplitWhitespace| -> io::Result<Vec<f64>> {
+            tokens.map(|t| t.parse::<f64>().map_err(|_| invalid(line_number))).collect()
+        };
+
+        match keyword {
+            "v" | "vn" => {
+                let n = numbers(tokens)?;
+                if n.len() < 3 { return Err(invalid(line_number)); }
+                let v = Vector3::new(n[0], n[1], n[2]);
+                if keyword == "v" { obj_positions.push(v) } else { obj_normals.push(v.unit()) }
+            },
+            "vt" => {
+                let n = numbers(tokens)?;
+                if n.len() < 2 { return Err(invalid(line_number)); }
+                obj_uvs.push((n[0], n[1]));
+            },
+            "f" => {
+                // Indices start at 1, and negative indices count back from the end.
+                let resolve = |token: Option<&str>, count: usize| -> io::Result<Option<usize>> {
+                    match token {
+                        None | Some("") => Ok(None),
+                        Some(t) => {
+                            let i = t.parse::<i64>().map_err(|_| invalid(line_number))?;
+                            let index = if i < 0 { count as i64 + i } else { i - 1 };
+                            if index < 0 || count as i64 <= index { return Err(invalid(line_number)); }
+                            Ok(Some(index as usize))
+                        },
+                    }
+                };
+                let mut face = vec![];
+                for corner in tokens {
+                    let mut parts = corner.split('/');
+                    let position = resolve(parts.next(), obj_positions.len())?.ok_or_else(|| invalid(line_number))?;
+                    let uv = resolve(parts.next(), obj_uvs.len())?;
+                    let normal = resolve(parts.next(), obj_normals.len())?;
+                    face.push((position, uv, normal));
+                }
+                if face.len() < 3 { return Err(invalid(line_number)); }
+                faces.push(face);
+            },
+            _ => (),
+        }
+    }
+
+    let has_normals = faces.iter().flatten().all(|corner| corner.2.is_some());
+    let has_uvs = faces.iter().flatten().all(|corner| corner.1.is_some());
+
+    let mut positions = vec![];
+    let mut normals = vec![];
+    let mut uvs = vec![];
+    let mut triangles = vec![];
+    let mut vertex_indices = std::collections::HashMap::new();
+
+    for face in faces {
+        let mut corners = vec![];
+        for (position, uv, normal) in face {
+            let key = (position, if has_uvs { uv } else { None }, if has_normals { normal } else { None });
+            let index = *vertex_indices.entry(key).or_insert_with(|| {
+                positions.push(obj_positions[position]);
+                if has_normals { normals.push(obj_normals[normal.unwrap()]); }
+                if has_uvs { uvs.push(obj_uvs[uv.unwrap()]); }
+                positions.len() - 1
+            });
+            corners.push(index);
+        }
+        for i in 1..corners.len() - 1 {
+            triangles.push([corners[0], corners[i], corners[i + 1]]);
+        }
+    }
+
+    if triangles.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "OBJ file has no faces"));
+    }
+
+    Ok(ObjData {
+        positions: positions,
+        triangles: triangles,
+        normals: normals,
+        uvs: uvs,
+    })
+}
+
+///
+/// Returns a unit normal for every vertex, found by adding up the normals of
+/// the triangles around it. Larger triangles count for more, so Meshes with
+/// uneven triangles still shade smoothly.
+///
+/// # Arguments
+/// * `positions` - The position of every vertex.
+/// * `triangles` - The indices of every triangle's vertices.
+///
+pub fn get_smooth_normals(positions: &[Point3], triangles: &[[usize; 3]]) -> Vec<Vector3> {
+
+    let mut normals = vec![Vector3::new(0.0, 0.0, 0.0); positions.len()];
+
+    for &[a, b, c] in triangles {
+        // The cross product's length is twice the triangle's area.
+        let normal = (positions[b] - positions[a]).cross(positions[c] - positions[a]);
+        normals[a] += normal;
+        normals[b] += normal;
+        normals[c] += normal;
+    }
+
+    normals.into_iter().map(|n| if n.near_zero() { Vector3::new(0.0, 1.0, 0.0) } else { n.unit() }).collect()
+}
+
+impl Hittable for Mesh {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Mesh and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Mesh intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+        self.bvh.get_hit(ray, min_dist, max_dist)
+    }
+
+    ///
+    /// Returns true if there is a Hit between the Mesh and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Mesh intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.bvh.is_hit(ray, min_dist, max_dist)
+    }
+
+    ///
+    /// Returns an AABB that surrounds the Mesh.
+    ///
+    /// # Arguments
+    /// * `&self` - The Mesh.
+    ///
+    fn get_aabb(&self) -> AABB {
+        self.bvh.get_aabb()
+    }
+
+    ///
+    /// Returns the SAH cost of the BVH over the Mesh's triangles.
+    ///
+    /// # Arguments
+    /// * `&self` - The Mesh.
+    ///
+    fn get_sah_cost(&self) -> f64 {
+        self.bvh.get_sah_cost()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::texture::monochrome::Monochrome;
+
+    fn get_material() -> Material {
+        Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None)
+    }
+
+    /// Writes a unit square split into a quad face and returns the OBJ's path.
+    fn write_test_obj(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("mesh_{}_{}.obj", name, std::process::id()));
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nf 1/1 2/2 3/3 -1/-1\n";
+        fs::write(&path, obj).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn faces_are_split_into_triangles() {
+        let path = write_test_obj("faces");
+        let ObjData { positions, triangles, normals, uvs } = read_obj(&path).unwrap();
+        assert_eq!(triangles, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!((positions.len(), normals.len(), uvs.len()), (4, 0, 4));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn cached_meshes_save_their_bvh_next_to_the_obj_file() {
+        let path = write_test_obj("cached");
+        let cache_path = format!("{}.bvh", path);
+        let ray = Ray::new(Point3::new(0.25, 0.5, 1.0), Vector3::new(0.0, 0.0, -1.0));
+
+        let built = Mesh::from_obj_cached(&path, get_material()).unwrap();
+        let saved = fs::read(&cache_path).unwrap();
+        let loaded = Mesh::from_obj_cached(&path, get_material()).unwrap();
+
+        assert_eq!(fs::read(&cache_path).unwrap(), saved);
+        for mesh in [built, loaded] {
+            assert_eq!(mesh.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance), Some(1.0));
+        }
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&cache_path).unwrap();
+    }
+}
@@ -1,10 +1,80 @@
 pub mod aabb;
+pub mod accelerator;
+pub mod axis_aligned_box;
 pub mod bvh_node;
+pub mod capsule;
+pub mod cone;
+pub mod csg;
+pub mod cylinder;
+pub mod disk;
+pub mod grid;
+pub mod heightfield;
+pub mod hittable_list;
+pub mod instance;
+pub mod kd_tree;
+pub mod linear_bvh;
+pub mod mesh;
+pub mod quad;
+pub mod sdf_shape;
 pub mod sphere;
+pub mod top_level_bvh;
+pub mod torus;
+pub mod triangle;
+pub mod wide_bvh;
 
 use crate::Hit;
 use crate::hittable::aabb::AABB;
+use crate::hittable::bvh_node::INTERSECTION_COST;
+use crate::hittable::linear_bvh::Primitive;
+use crate::material::Material;
 use crate::Ray;
+use crate::texture::SurfacePoint;
+
+///
+/// Returns the min_dist to search from again after a Hit at the given distance
+/// was cut out by its Material's opacity mask. It is just far enough along the
+/// Ray that the same Hit isn't found again.
+///
+/// # Arguments
+/// * `distance` - The distance along the Ray of the Hit that was cut out.
+///
+pub fn get_distance_past(distance: f64) -> f64 {
+    distance + 1e-9 * distance.abs().max(1.0)
+}
+
+///
+/// Returns the closest intersection along a Ray that the Material's opacity
+/// mask doesn't cut out. Cut out intersections are stepped past until an
+/// opaque one is found. They are only worked out as far as a point sampled
+/// SurfacePoint to look the mask up at, so that a Hit is only shaded once,
+/// and occlusion queries are never shaded.
+///
+/// # Arguments
+/// * `material` - The Hittable's Material.
+/// * `ray` - The intersecting Ray.
+/// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+/// * `get_intersect` - Returns the closest intersection past the given min_dist.
+/// * `get_surface` - Returns the SurfacePoint of an intersection, in the same
+///   space as the Ray. Its uv coordinates, point and normal are all it needs.
+/// * `get_distance_past` - Returns the min_dist to search from again after
+///   the given intersection is cut out.
+///
+pub fn get_opaque_intersect<T: Copy>(
+    material: &Material,
+    ray: Ray,
+    mut min_dist: f64,
+    get_intersect: impl Fn(f64) -> Option<T>,
+    get_surface: impl Fn(T) -> SurfacePoint,
+    get_distance_past: impl Fn(T) -> f64,
+) -> Option<T> {
+    loop {
+        let intersect = get_intersect(min_dist)?;
+        if material.opacity_mask.is_none() || material.is_opaque(get_surface(intersect).in_world_space(ray)) {
+            return Some(intersect);
+        }
+        min_dist = get_distance_past(intersect);
+    }
+}
 
 /// Represents any object in 3D space that can be Hit by a Ray.
 pub trait Hittable {
@@ -25,6 +95,12 @@ pub trait Hittable {
 
     ///
     /// Returns true if there is a Hit between the Hittable and a given Ray.
+    /// This is an occlusion query: any Hit in the range will do, not just the
+    /// closest one, and no Hit is built, so no texture is looked up. Hittables
+    /// made of other Hittables stop at the first one hit.
+    ///
+    /// By default the Hit is found with get_hit and thrown away, so Hittables
+    /// that can tell they are hit more cheaply than that should override this.
     ///
     /// # Arguments
     /// * `&self` - The Hittable intersected by the Ray.
@@ -39,6 +115,36 @@ pub trait Hittable {
         }
     }
 
+    ///
+    /// Returns every interval along the given Ray where the Ray is inside the
+    /// Hittable, as (entry, exit) pairs of Hits sorted by distance. The whole
+    /// line the Ray lies on is searched, including behind the Ray's origin.
+    /// This only makes sense for closed Hittables.
+    ///
+    /// By default the intervals are found by repeatedly calling get_hit, each
+    /// time starting just past the previous Hit.
+    ///
+    /// # Arguments
+    /// * `&self` - The Hittable intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    ///
+    fn get_intervals(&self, ray: Ray) -> Vec<(Hit, Hit)> {
+
+        let step = 1e-7 / ray.direction.mag();
+        let mut min_dist = f64::NEG_INFINITY;
+        let mut hits: Vec<Hit> = vec![];
+
+        while let Some(hit) = self.get_hit(ray, min_dist, f64::INFINITY) {
+            let next_dist = hit.distance + step;
+            hits.push(hit);
+            // Stop if the step is too small to move past the Hit.
+            if next_dist <= min_dist { break; }
+            min_dist = next_dist;
+        }
+
+        hits.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect()
+    }
+
     ///
     /// Returns an AABB that surrounds the Hittable.
     ///
@@ -46,4 +152,114 @@ pub trait Hittable {
     /// * `&self` - The Hittable.
     ///
     fn get_aabb(&self) -> AABB;
-}
\ No newline at end of file
+
+    ///
+    /// Updates every AABB the Hittable keeps for the Hittables inside it, from
+    /// the bottom up, and returns the Hittable's new AABB. This keeps a BVH
+    /// correct after the Hittables in it have moved, without rebuilding it.
+    /// Hittables that do not keep any AABBs simply return get_aabb.
+    ///
+    /// # Arguments
+    /// * `&self` - The Hittable.
+    ///
+    fn refit(&self) -> AABB {
+        self.get_aabb()
+    }
+
+    ///
+    /// Returns the expected cost of checking a Ray against the Hittable, given
+    /// that the Ray hits its AABB, according to the surface area heuristic.
+    /// Refitting a BVH makes this grow as its nodes' AABBs grow and overlap,
+    /// which is how to tell when it is time to rebuild it instead.
+    ///
+    /// # Arguments
+    /// * `&self` - The Hittable.
+    ///
+    fn get_sah_cost(&self) -> f64 {
+        INTERSECTION_COST
+    }
+
+    ///
+    /// Returns how many bytes the Hittable's acceleration structure takes up,
+    /// such as the nodes of a BVH, not counting the Hittables inside it.
+    /// Hittables without an acceleration structure take up none.
+    ///
+    /// # Arguments
+    /// * `&self` - The Hittable.
+    ///
+    fn get_memory_size(&self) -> usize {
+        0
+    }
+
+    ///
+    /// Returns a copy of the Hittable as a Primitive that a LinearBvh stores
+    /// by value and checks without dynamic dispatch, or None if the Hittable
+    /// has to be kept behind a Rc<dyn Hittable>.
+    ///
+    /// # Arguments
+    /// * `&self` - The Hittable.
+    ///
+    fn get_primitive(&self) -> Option<Primitive> {
+        None
+    }
+}
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::instance::Instance;
+    use crate::hittable::sphere::Sphere;
+    use crate::material::OpacityMask;
+    use crate::texture::monochrome::Monochrome;
+    use crate::texture::Texture;
+    use crate::transform::Transform;
+    use crate::vector3::Point3;
+    use crate::vector3::Vector3;
+
+    use std::rc::Rc;
+
+    /// Opaque where the world space point is behind the z = 0 plane.
+    struct BehindPlane;
+
+    impl Texture for BehindPlane {
+        fn get_color(&self, surface: SurfacePoint) -> Color {
+            let value = if surface.point.z < 0.0 { 1.0 } else { 0.0 };
+            Color::new(value, value, value)
+        }
+    }
+
+    fn get_sphere(center: Point3, opacity: Box<dyn Texture>) -> Sphere {
+        let material = Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None)
+            .with_opacity_mask(OpacityMask::Threshold { opacity: opacity, threshold: 0.5 });
+        Sphere::new(center, 1.0, material)
+    }
+
+    #[test]
+    fn cut_out_intersections_are_stepped_past() {
+
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+
+        // The front of the Sphere is cut out, so the Ray hits the back.
+        let sphere = get_sphere(Point3::new(0.0, 0.0, 0.0), Box::new(BehindPlane));
+        assert!((sphere.get_hit(ray, 0.0, f64::INFINITY).unwrap().distance - 6.0).abs() < 1e-9);
+        assert!(sphere.is_hit(ray, 0.0, f64::INFINITY));
+        assert!(!sphere.is_hit(ray, 0.0, 5.5));
+
+        // A Sphere cut out everywhere is never hit.
+        let sphere = get_sphere(Point3::new(0.0, 0.0, 0.0), Box::new(Monochrome::new(Color::new(0.0, 0.0, 0.0))));
+        assert!(sphere.get_hit(ray, 0.0, f64::INFINITY).is_none());
+        assert!(!sphere.is_hit(ray, 0.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn instanced_cutouts_are_looked_up_in_world_space() {
+
+        // Moved behind the plane, the whole Sphere is opaque.
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let sphere = Rc::new(get_sphere(Point3::new(0.0, 0.0, 0.0), Box::new(BehindPlane)));
+        let instance = Instance::new(sphere, Transform::translation(Vector3::new(0.0, 0.0, -3.0)));
+        assert!((instance.get_hit(ray, 0.0, f64::INFINITY).unwrap().distance - 7.0).abs() < 1e-9);
+        assert!(instance.is_hit(ray, 0.0, 7.5));
+    }
+}
@@ -0,0 +1,208 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::aabb::surrounding_box;
+use crate::hittable::get_distance_past;
+use crate::hittable::get_opaque_intersect;
+use crate::hittable::Hittable;
+use crate::hittable::linear_bvh::Primitive;
+use crate::material::Material;
+use crate::Ray;
+use crate::texture::SurfacePoint;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use std::rc::Rc;
+
+/// Represents a parallelogram in 3D space.
+#[derive(Clone)]
+pub struct Quad {
+    /// One corner of the quad.
+    pub origin: Point3,
+    /// The edge going from the origin to one of the adjacent corners.
+    pub u: Vector3,
+    /// The edge going from the origin to the other adjacent corner.
+    pub v: Vector3,
+    /// The quad's material. Shared so that several quads can make up one object.
+    pub material: Rc<Material>,
+    /// The unit normal of the plane containing the quad.
+    normal: Vector3,
+    /// The plane's constant term, such that normal.dot(point) == d for every point on the plane.
+    d: f64,
+    /// The vector used to find the planar (alpha, beta) coordinates of a point.
+    w: Vector3,
+}
+
+impl Quad {
+
+    ///
+    /// Returns a Quad constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `origin` - The Quad's origin field.
+    /// * `u` - The Quad's u field.
+    /// * `v` - The Quad's v field.
+    /// * `material` - The Quad's material field.
+    ///
+    pub fn new(origin: Point3, u: Vector3, v: Vector3, material: Material) -> Self {
+        Self::with_shared_material(origin, u, v, Rc::new(material))
+    }
+
+    ///
+    /// Returns a Quad whose material is shared with other Quads, such as the
+    /// sides of an AxisAlignedBox.
+    ///
+    /// # Arguments
+    /// * `origin` - The Quad's origin field.
+    /// * `u` - The Quad's u field.
+    /// * `v` - The Quad's v field.
+    /// * `material` - The Quad's material field.
+    ///
+    pub fn with_shared_material(origin: Point3, u: Vector3, v: Vector3, material: Rc<Material>) -> Self {
+        let n = u.cross(v);
+        let normal = n.unit();
+        Self {
+            origin: origin,
+            u: u,
+            v: v,
+            material: material,
+            normal: normal,
+            d: normal.dot(origin),
+            w: n / n.mag_squared(),
+        }
+    }
+
+    ///
+    /// Returns the (distance, (u, v)) of the intersection between the Quad and
+    /// the given Ray, if there is one. u and v are the planar coordinates of the
+    /// intersection along the Quad's edges and are both between [0, 1].
+    ///
+    /// # Arguments
+    /// * `&self` - The Quad.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    pub fn get_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<(f64, (f64, f64))> {
+
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < 1e-8 { return None; }
+
+        let distance = (self.d - self.normal.dot(ray.origin)) / denominator;
+        if distance < min_dist || max_dist < distance { return None; }
+
+        let (alpha, beta) = self.get_uv(ray.get_point(distance));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) { return None; }
+
+        Some((distance, (alpha, beta)))
+    }
+
+    ///
+    /// Returns the planar coordinates of a point along the Quad's edges. Points
+    /// off the Quad's plane are projected onto it, and points outside of the
+    /// Quad give coordinates outside of [0, 1].
+    ///
+    /// # Arguments
+    /// * `&self` - The Quad.
+    /// * `point` - The point.
+    ///
+    pub fn get_uv(&self, point: Point3) -> (f64, f64) {
+        let planar_point = point - self.origin;
+        let alpha = self.w.dot(planar_point.cross(self.v));
+        let beta = self.w.dot(self.u.cross(planar_point));
+        (alpha, beta)
+    }
+
+    ///
+    /// Returns the (distance, (u, v)) of the intersection between the Quad and
+    /// the given Ray, if there is one that isn't cut out by the Quad's opacity
+    /// mask.
+    ///
+    /// # Arguments
+    /// * `&self` - The Quad.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_opaque_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<(f64, (f64, f64))> {
+        get_opaque_intersect(
+            &self.material,
+            ray,
+            min_dist,
+            |min_dist| self.get_intersect(ray, min_dist, max_dist),
+            |(distance, uv)| {
+                let point = ray.get_point(distance);
+                SurfacePoint::new(uv, point, point - self.origin, self.normal)
+            },
+            |(distance, _)| get_distance_past(distance),
+        )
+    }
+}
+
+impl Hittable for Quad {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Quad and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Quad intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let (distance, uv) = self.get_opaque_intersect(ray, min_dist, max_dist)?;
+        let point = ray.get_point(distance);
+
+        let surface = SurfacePoint::new(uv, point, point - self.origin, self.normal)
+            .with_derivatives(ray, false, |point| self.get_uv(point));
+        let is_front = ray.direction.dot(self.normal) < 0.0;
+        let mut normal = self.material.get_normal(surface);
+        if !is_front { normal = -normal };
+
+        Some(Hit::new(
+            ray,
+            distance,
+            normal,
+            is_front,
+            self.material.texture.get_color(surface),
+            self.material.get_reflectivity(surface),
+            self.material.get_refraction_index(surface),
+        ))
+    }
+
+    ///
+    /// Returns true if there is a Hit between the Quad and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Quad intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.get_opaque_intersect(ray, min_dist, max_dist).is_some()
+    }
+
+    ///
+    /// Returns an AABB that surrounds the Quad.
+    ///
+    /// # Arguments
+    /// * `&self` - The Quad.
+    ///
+    fn get_aabb(&self) -> AABB {
+        let diagonal1 = AABB::from_points(self.origin, self.origin + self.u + self.v);
+        let diagonal2 = AABB::from_points(self.origin + self.u, self.origin + self.v);
+        surrounding_box(diagonal1, diagonal2).pad()
+    }
+
+    ///
+    /// Returns a copy of the Quad, so that a LinearBvh can store it by value.
+    ///
+    /// # Arguments
+    /// * `&self` - The Quad.
+    ///
+    fn get_primitive(&self) -> Option<Primitive> {
+        Some(Primitive::Quad(self.clone()))
+    }
+}
@@ -0,0 +1,310 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::get_opaque_intersect;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::Ray;
+use crate::texture::SurfacePoint;
+use crate::sdf::DistanceFunction;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use std::f64::consts::PI;
+
+/// The most steps sphere tracing will take before giving up on a Ray.
+const MAX_STEPS: u32 = 512;
+/// How close to the surface a point has to be to count as a Hit.
+const HIT_DISTANCE: f64 = 1e-5;
+
+/// Represents an implicit surface given by a signed distance function. Hits are
+/// found by sphere tracing: stepping along the Ray by the distance to the
+/// closest surface until that distance is close enough to zero.
+pub struct SdfShape {
+    /// The shape's signed distance function.
+    pub distance_function: DistanceFunction,
+    /// A box that the whole surface fits inside. Rays are only traced inside it.
+    pub bounding_box: AABB,
+    /// The shape's material.
+    pub material: Material,
+    /// The fraction of the distance to step at a time. Should be below 1.0 for
+    /// distance functions that overestimate the distance, like twisted shapes.
+    pub step_scale: f64,
+}
+
+impl SdfShape {
+
+    ///
+    /// Returns an SdfShape constructed from the given arguments, with a step
+    /// scale of 1.0.
+    ///
+    /// # Arguments
+    /// * `distance_function` - The SdfShape's distance_function field.
+    /// * `bounding_box` - The SdfShape's bounding_box field.
+    /// * `material` - The SdfShape's material field.
+    ///
+    pub fn new(distance_function: DistanceFunction, bounding_box: AABB, material: Material) -> Self {
+        Self {
+            distance_function: distance_function,
+            bounding_box: bounding_box,
+            material: material,
+            step_scale: 1.0,
+        }
+    }
+
+    ///
+    /// Returns the unit normal of the surface at the given point, found from the
+    /// gradient of the distance function. Uses four samples arranged in a
+    /// tetrahedron rather than six central differences.
+    ///
+    /// # Arguments
+    /// * `&self` - The SdfShape.
+    /// * `point` - A point on the surface.
+    ///
+    fn get_normal(&self, point: Point3) -> Vector3 {
+        let h = 1e-5;
+        let f = &self.distance_function;
+        let k1 = Vector3::new(1.0, -1.0, -1.0);
+        let k2 = Vector3::new(-1.0, -1.0, 1.0);
+        let k3 = Vector3::new(-1.0, 1.0, -1.0);
+        let k4 = Vector3::new(1.0, 1.0, 1.0);
+        let gradient = k1 * f(point + h * k1) + k2 * f(point + h * k2) + k3 * f(point + h * k3) + k4 * f(point + h * k4);
+        gradient.unit()
+    }
+
+    ///
+    /// Returns a (f64, f64) tuple of the SdfShape's (u, v) texture coordinates
+    /// at the given normal. Implicit surfaces have no natural parameterization,
+    /// so the normal is mapped the same way as a Sphere's.
+    ///
+    /// # Arguments
+    /// * `&self` - The SdfShape.
+    /// * `normal` - The outward unit normal at the point on the surface.
+    ///
+    fn get_uv(&self, normal: Vector3) -> (f64, f64) {
+        let theta = (-normal.y).clamp(-1.0, 1.0).acos();
+        let phi = (-normal.z).atan2(normal.x) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    ///
+    /// Returns the distance along the given Ray to where sphere tracing first
+    /// reaches the SdfShape's surface, if it does.
+    ///
+    /// # Arguments
+    /// * `&self` - The SdfShape.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<f64> {
+
+        let (entry, exit) = self.bounding_box.get_intersect(ray, min_dist, max_dist)?;
+
+        let direction_length = ray.direction.mag();
+        let mut distance = entry;
+        let mut hit_distance: Option<f64> = None;
+
+        for _ in 0..MAX_STEPS {
+            let surface_distance = (self.distance_function)(ray.get_point(distance)).abs();
+            if surface_distance < HIT_DISTANCE {
+                hit_distance = Some(distance);
+                break;
+            }
+            distance += self.step_scale * surface_distance / direction_length;
+            if exit < distance { break; }
+        }
+
+        hit_distance
+    }
+
+    ///
+    /// Returns the point sampled SurfacePoint at the given distance along the
+    /// Ray, in the same space as the Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The SdfShape.
+    /// * `ray` - The intersecting Ray.
+    /// * `distance` - The distance along the Ray to the intersection.
+    ///
+    fn get_surface(&self, ray: Ray, distance: f64) -> SurfacePoint {
+        let point = ray.get_point(distance);
+        let normal = self.get_normal(point);
+        SurfacePoint::new(self.get_uv(normal), point, point, normal)
+    }
+
+    ///
+    /// Returns the distance along the given Ray to its nearest intersection
+    /// with the SdfShape that isn't cut out by the SdfShape's opacity mask.
+    /// Past a cut out intersection, the Ray is moved on far enough that the
+    /// march doesn't stop on it again.
+    ///
+    /// # Arguments
+    /// * `&self` - The SdfShape.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_opaque_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<f64> {
+        get_opaque_intersect(
+            &self.material,
+            ray,
+            min_dist,
+            |min_dist| self.get_intersect(ray, min_dist, max_dist),
+            |distance| self.get_surface(ray, distance),
+            |distance| distance + 2.0 * HIT_DISTANCE / ray.direction.mag(),
+        )
+    }
+
+    ///
+    /// Returns the shaded Hit at the given distance along the Ray, which must
+    /// be on the SdfShape's surface.
+    ///
+    /// # Arguments
+    /// * `&self` - The SdfShape.
+    /// * `ray` - The intersecting Ray.
+    /// * `distance` - The distance along the Ray to the intersection.
+    ///
+    fn get_hit_at(&self, ray: Ray, distance: f64) -> Hit {
+
+        let surface = self.get_surface(ray, distance);
+        let is_front = ray.direction.dot(surface.normal) < 0.0;
+        let surface = surface.with_derivatives(ray, true, |point| self.get_uv(self.get_normal(point)));
+        let mut normal = self.material.get_normal(surface);
+        if !is_front { normal = -normal };
+
+        Hit::new(
+            ray,
+            distance,
+            normal,
+            is_front,
+            self.material.texture.get_color(surface),
+            self.material.get_reflectivity(surface),
+            self.material.get_refraction_index(surface),
+        )
+    }
+}
+
+impl Hittable for SdfShape {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the SdfShape and a given Ray.
+    /// Steps are taken using the absolute distance, so Rays that start inside
+    /// the surface (such as refracted Rays) still find their way out.
+    ///
+    /// # Arguments
+    /// * `&self` - The SdfShape intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+        let distance = self.get_opaque_intersect(ray, min_dist, max_dist)?;
+        Some(self.get_hit_at(ray, distance))
+    }
+
+    ///
+    /// Returns every interval along the given Ray where the Ray is inside the
+    /// SdfShape. After each boundary, the Ray is nudged forward until it is
+    /// clear of the surface so the next march does not stop on it again. Only
+    /// the boundaries that are found are shaded.
+    ///
+    /// # Arguments
+    /// * `&self` - The SdfShape intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    ///
+    fn get_intervals(&self, ray: Ray) -> Vec<(Hit, Hit)> {
+
+        let step = HIT_DISTANCE / ray.direction.mag();
+        let mut min_dist = f64::NEG_INFINITY;
+        let mut distances: Vec<f64> = vec![];
+
+        while let Some(distance) = self.get_opaque_intersect(ray, min_dist, f64::INFINITY) {
+            let mut next_dist = distance + step;
+            for _ in 0..MAX_STEPS {
+                if 2.0 * HIT_DISTANCE < (self.distance_function)(ray.get_point(next_dist)).abs() { break; }
+                next_dist += step;
+            }
+            distances.push(distance);
+            min_dist = next_dist;
+        }
+
+        distances.chunks_exact(2).map(|pair| (self.get_hit_at(ray, pair[0]), self.get_hit_at(ray, pair[1]))).collect()
+    }
+
+    ///
+    /// Returns true if there is a Hit between the SdfShape and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The SdfShape intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.get_opaque_intersect(ray, min_dist, max_dist).is_some()
+    }
+
+    ///
+    /// Returns an AABB that surrounds the SdfShape.
+    ///
+    /// # Arguments
+    /// * `&self` - The SdfShape.
+    ///
+    fn get_aabb(&self) -> AABB {
+        self.bounding_box
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::sphere::Sphere;
+    use crate::sdf;
+    use crate::texture::monochrome::Monochrome;
+
+    fn get_material() -> Material {
+        Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None)
+    }
+
+    #[test]
+    fn sdf_sphere_matches_analytic_sphere() {
+
+        let center = Point3::new(0.5, -0.25, 1.0);
+        let bounds = AABB::new(center - Vector3::new(1.1, 1.1, 1.1), center + Vector3::new(1.1, 1.1, 1.1));
+        let shape = SdfShape::new(sdf::sphere(center, 1.0), bounds, get_material());
+        let sphere = Sphere::new(center, 1.0, get_material());
+
+        let rays = [
+            Ray::new(Point3::new(0.0, 0.0, 6.0), Vector3::new(0.1, -0.05, -1.0)),
+            Ray::new(Point3::new(-4.0, 1.0, 0.5), Vector3::new(2.0, -0.6, 0.3)),
+            // From inside, like a refracted Ray.
+            Ray::new(center, Vector3::new(0.3, 0.8, -0.2)),
+            // Grazing past, and missing.
+            Ray::new(Point3::new(0.5, 0.74, -5.0), Vector3::new(0.0, 0.0, 1.0)),
+            Ray::new(Point3::new(0.5, 0.8, -5.0), Vector3::new(0.0, 0.0, 1.0)),
+        ];
+
+        for ray in rays {
+
+            let expected = sphere.get_hit(ray, 0.0, f64::INFINITY);
+            let hit = shape.get_hit(ray, 0.0, f64::INFINITY);
+            assert_eq!(hit.is_some(), expected.is_some());
+            assert_eq!(shape.is_hit(ray, 0.0, f64::INFINITY), sphere.is_hit(ray, 0.0, f64::INFINITY));
+            if let (Some(hit), Some(expected)) = (hit, expected) {
+                assert!((hit.distance - expected.distance).abs() < 1e-4);
+                assert!((hit.normal - expected.normal).mag() < 1e-3);
+                assert_eq!(hit.is_front, expected.is_front);
+            }
+
+            let intervals = shape.get_intervals(ray);
+            let expected = sphere.get_intervals(ray);
+            assert_eq!(intervals.len(), expected.len());
+            for ((entry, exit), (expected_entry, expected_exit)) in intervals.iter().zip(&expected) {
+                assert!((entry.distance - expected_entry.distance).abs() < 1e-4 && entry.is_front);
+                assert!((exit.distance - expected_exit.distance).abs() < 1e-4 && !exit.is_front);
+            }
+        }
+    }
+}
@@ -1,21 +1,27 @@
 use crate::Hit;
 use crate::hittable::aabb::AABB;
+use crate::hittable::get_distance_past;
+use crate::hittable::get_opaque_intersect;
 use crate::hittable::Hittable;
+use crate::hittable::linear_bvh::Primitive;
 use crate::material::Material;
 use crate::Ray;
+use crate::texture::SurfacePoint;
 use crate::vector3::Point3;
 use crate::vector3::Vector3;
 
 use std::f64::consts::PI;
+use std::rc::Rc;
 
 /// Represents a sphere in 3D space.
+#[derive(Clone)]
 pub struct Sphere {
     /// The sphere's center.
     pub center: Point3,
     /// The sphere's radius.
     pub radius: f64,
-    /// The sphere's material.
-    pub material: Material,
+    /// The sphere's material. Shared so that copies of the Sphere are cheap.
+    pub material: Rc<Material>,
 }
 
 impl Sphere {
@@ -32,7 +38,7 @@ impl Sphere {
         Self {
             center: center,
             radius: radius,
-            material: material,
+            material: Rc::new(material),
         }
     }
 
@@ -54,20 +60,18 @@ impl Sphere {
         let v = theta / PI;
         (u, v)
     }
-}
-
-impl Hittable for Sphere {
 
     ///
-    /// Returns Some(Hit) if there is a Hit between the Sphere and a given Ray.
+    /// Returns the distance along the given Ray to its nearest intersection
+    /// with the Sphere, if there is one.
     ///
     /// # Arguments
-    /// * `&self` - The Sphere intersected by the Ray.
+    /// * `&self` - The Sphere.
     /// * `ray` - The intersecting Ray.
-    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
-    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
     ///
-    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+    fn get_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<f64> {
         let oc = ray.origin - self.center;
         let a = ray.direction.mag_squared();
         let half_b = oc.dot(ray.direction);
@@ -86,10 +90,65 @@ impl Hittable for Sphere {
             }
         }
 
-        let distance = root;
+        Some(root)
+    }
+
+    ///
+    /// Returns the point sampled SurfacePoint at the given distance along the
+    /// Ray, in the same space as the Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Sphere.
+    /// * `ray` - The intersecting Ray.
+    /// * `distance` - The distance along the Ray to the intersection.
+    ///
+    fn get_surface(&self, ray: Ray, distance: f64) -> SurfacePoint {
         let point = ray.get_point(distance);
-        let mut normal = (point - self.center) / self.radius;
-        let is_front = ray.direction.dot(normal) < 0.0;
+        let normal = (point - self.center) / self.radius;
+        SurfacePoint::new(self.get_uv(normal), point, point - self.center, normal)
+    }
+
+    ///
+    /// Returns the distance along the given Ray to its nearest intersection
+    /// with the Sphere that isn't cut out by the Sphere's opacity mask.
+    ///
+    /// # Arguments
+    /// * `&self` - The Sphere.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_opaque_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<f64> {
+        get_opaque_intersect(
+            &self.material,
+            ray,
+            min_dist,
+            |min_dist| self.get_intersect(ray, min_dist, max_dist),
+            |distance| self.get_surface(ray, distance),
+            get_distance_past,
+        )
+    }
+}
+
+impl Hittable for Sphere {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Sphere and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Sphere intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let distance = self.get_opaque_intersect(ray, min_dist, max_dist)?;
+        let surface = self.get_surface(ray, distance);
+        let is_front = ray.direction.dot(surface.normal) < 0.0;
+        let surface = surface
+            .with_derivatives(ray, true, |point| self.get_uv((point - self.center).unit() * self.radius.signum()));
+        let mut normal = self.material.get_normal(surface);
         if !is_front { normal = -normal };
 
         Some(Hit::new(
@@ -97,12 +156,25 @@ impl Hittable for Sphere {
             distance,
             normal,
             is_front,
-            self.material.texture.get_color(self.get_uv(normal)),
-            self.material.reflectivity,
-            self.material.refraction_index,
+            self.material.texture.get_color(surface),
+            self.material.get_reflectivity(surface),
+            self.material.get_refraction_index(surface),
         ))
     }
 
+    ///
+    /// Returns true if there is a Hit between the Sphere and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Sphere intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.get_opaque_intersect(ray, min_dist, max_dist).is_some()
+    }
+
     ///
     /// Returns an AABB that surrounds the Sphere.
     ///
@@ -113,4 +185,14 @@ impl Hittable for Sphere {
         let radius_vector = Vector3::new(self.radius, self.radius, self.radius);
         AABB::new(self.center - radius_vector, self.center + radius_vector)
     }
+
+    ///
+    /// Returns a copy of the Sphere, so that a LinearBvh can store it by value.
+    ///
+    /// # Arguments
+    /// * `&self` - The Sphere.
+    ///
+    fn get_primitive(&self) -> Option<Primitive> {
+        Some(Primitive::Sphere(self.clone()))
+    }
 }
\ No newline at end of file
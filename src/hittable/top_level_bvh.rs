@@ -0,0 +1,172 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::bvh_node::BvhNode;
+use crate::hittable::bvh_node::MAX_REFIT_COST_GROWTH;
+use crate::hittable::bvh_node::construct_sah_bvh_root;
+use crate::hittable::Hittable;
+use crate::hittable::instance::Instance;
+use crate::Ray;
+use crate::transform::Transform;
+use crate::vector3::Point3;
+
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The top level of a two level acceleration structure. Each unique piece of
+/// geometry, such as a Mesh, keeps its own bottom level BVH in object space.
+/// The top level is a BVH over transformed Instances of that geometry, so
+/// moving an Instance only means updating the top level, which is small and
+/// cheap. Moved Instances are handled by refitting the top level, and it is
+/// only rebuilt once refitting has made it too slow.
+pub struct TopLevelBvh {
+    /// Every Instance, indexed by the number push returned for it.
+    instances: Vec<Rc<Instance>>,
+    /// The root node of the BVH over the Instances. None until it is first
+    /// built, and set back to None whenever an Instance is added. It is behind
+    /// a RefCell so that it can be rebuilt lazily, the first time the
+    /// TopLevelBvh is intersected after a change.
+    root: RefCell<Option<BvhNode>>,
+    /// If an Instance has moved since the BVH was last refit.
+    is_moved: Cell<bool>,
+    /// The BVH's SAH cost when it was last built.
+    built_cost: Cell<f64>,
+}
+
+impl TopLevelBvh {
+
+    ///
+    /// Returns an empty TopLevelBvh.
+    ///
+    pub fn new() -> Self {
+        Self {
+            instances: vec![],
+            root: RefCell::new(None),
+            is_moved: Cell::new(false),
+            built_cost: Cell::new(0.0),
+        }
+    }
+
+    ///
+    /// Adds an Instance of the given Hittable and returns its index. The
+    /// Hittable is shared, not copied, so its own acceleration structure is
+    /// only ever built once.
+    ///
+    /// # Arguments
+    /// * `&mut self` - The TopLevelBvh.
+    /// * `hittable` - The instanced Hittable, in its own object space.
+    /// * `object_to_world` - The transformation that places the Instance in the world.
+    ///
+    pub fn push(&mut self, hittable: Rc<dyn Hittable>, object_to_world: Transform) -> usize {
+        self.instances.push(Rc::new(Instance::new(hittable, object_to_world)));
+        *self.root.get_mut() = None;
+        self.instances.len() - 1
+    }
+
+    ///
+    /// Moves the Instance with the given index. Only the top level is refit,
+    /// the next time the TopLevelBvh is intersected. Like Instance::set_transform,
+    /// this works through a shared reference, so the TopLevelBvh can be
+    /// animated after it has been put in a Scene.
+    ///
+    /// # Arguments
+    /// * `&self` - The TopLevelBvh.
+    /// * `index` - The index push returned for the Instance.
+    /// * `object_to_world` - The transformation that places the Instance in the world.
+    ///
+    pub fn set_transform(&self, index: usize, object_to_world: Transform) {
+        self.instances[index].set_transform(object_to_world);
+        self.is_moved.set(true);
+    }
+
+    ///
+    /// Returns the number of Instances.
+    ///
+    /// # Arguments
+    /// * `&self` - The TopLevelBvh.
+    ///
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    ///
+    /// Brings the top level BVH up to date. It is built if there is none, and
+    /// refit if any Instance has moved. If refitting grows its SAH cost past
+    /// MAX_REFIT_COST_GROWTH times what it was when it was built, it is rebuilt.
+    ///
+    /// # Arguments
+    /// * `&self` - The TopLevelBvh.
+    ///
+    fn update(&self) {
+
+        if self.instances.is_empty() { return; }
+
+        if self.root.borrow().is_some() {
+            if !self.is_moved.replace(false) { return; }
+            let root = self.root.borrow();
+            let root = root.as_ref().unwrap();
+            root.refit();
+            if root.get_sah_cost() <= self.built_cost.get() * MAX_REFIT_COST_GROWTH { return; }
+        }
+
+        let mut hittables: Vec<Rc<dyn Hittable>> = self.instances.iter().map(|i| Rc::clone(i) as Rc<dyn Hittable>).collect();
+        let length = hittables.len();
+        // Instances are expensive to check, so each one gets its own leaf.
+        let root = construct_sah_bvh_root(&mut hittables, 0, length, 1);
+        self.built_cost.set(root.get_sah_cost());
+        self.is_moved.set(false);
+        *self.root.borrow_mut() = Some(root);
+    }
+}
+
+impl Hittable for TopLevelBvh {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between any Instance and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The TopLevelBvh intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+        self.update();
+        self.root.borrow().as_ref()?.get_hit(ray, min_dist, max_dist)
+    }
+
+    ///
+    /// Returns true if there is a Hit between any Instance and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The TopLevelBvh intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.update();
+        match self.root.borrow().as_ref() {
+            Some(root) => root.is_hit(ray, min_dist, max_dist),
+            None => false,
+        }
+    }
+
+    ///
+    /// Returns an AABB that surrounds every Instance.
+    ///
+    /// # Arguments
+    /// * `&self` - The TopLevelBvh.
+    ///
+    fn get_aabb(&self) -> AABB {
+        self.update();
+        match self.root.borrow().as_ref() {
+            Some(root) => root.get_aabb(),
+            // An inside out box, which no Ray can hit.
+            None => AABB::new(
+                Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+                Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::get_distance_past;
+use crate::hittable::get_opaque_intersect;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::polynomial::solve_quartic;
+use crate::Ray;
+use crate::texture::SurfacePoint;
+use crate::vector3::orthonormal_basis;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use std::f64::consts::PI;
+
+/// Represents a torus (a donut shape) in 3D space.
+pub struct Torus {
+    /// The torus's center.
+    pub center: Point3,
+    /// The unit vector the torus is rotated around.
+    pub axis: Vector3,
+    /// The distance from the center to the middle of the tube.
+    pub major_radius: f64,
+    /// The radius of the tube.
+    pub minor_radius: f64,
+    /// The torus's material.
+    pub material: Material,
+    /// A unit vector orthogonal to the axis. The local x axis of the torus.
+    tangent: Vector3,
+    /// A unit vector orthogonal to both the axis and the tangent. The local z axis of the torus.
+    bitangent: Vector3,
+}
+
+impl Torus {
+
+    ///
+    /// Returns a Torus constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `center` - The Torus's center field.
+    /// * `axis` - The Torus's axis field. Does not need to be a unit vector.
+    /// * `major_radius` - The Torus's major_radius field.
+    /// * `minor_radius` - The Torus's minor_radius field.
+    /// * `material` - The Torus's material field.
+    ///
+    pub fn new(center: Point3, axis: Vector3, major_radius: f64, minor_radius: f64, material: Material) -> Self {
+        let (tangent, bitangent) = orthonormal_basis(axis);
+        Self {
+            center: center,
+            axis: axis.unit(),
+            major_radius: major_radius,
+            minor_radius: minor_radius,
+            material: material,
+            tangent: tangent,
+            bitangent: bitangent,
+        }
+    }
+
+    ///
+    /// Returns the given world space vector in the torus's local space, where
+    /// the torus lies in the xz plane and is rotated around the y axis.
+    ///
+    /// # Arguments
+    /// * `&self` - The Torus.
+    /// * `v` - The world space vector.
+    ///
+    fn to_local(&self, v: Vector3) -> Vector3 {
+        Vector3::new(v.dot(self.tangent), v.dot(self.axis), v.dot(self.bitangent))
+    }
+
+    ///
+    /// Returns the given local space vector in world space.
+    ///
+    /// # Arguments
+    /// * `&self` - The Torus.
+    /// * `v` - The local space vector.
+    ///
+    fn to_world(&self, v: Vector3) -> Vector3 {
+        v.x * self.tangent + v.y * self.axis + v.z * self.bitangent
+    }
+
+    ///
+    /// Returns a (f64, f64) tuple of the Torus's (u, v) texture coordinates at
+    /// the given point.
+    ///
+    /// u is an f64 between [0, 1] that is the angle around the torus's axis
+    /// v is an f64 between [0, 1] that is the angle around the torus's tube
+    ///
+    /// # Arguments
+    /// * `&self` - The Torus.
+    /// * `point` - The point on the torus's surface in local space.
+    ///
+    fn get_uv(&self, point: Point3) -> (f64, f64) {
+        let ring_distance = (point.x * point.x + point.z * point.z).sqrt();
+        let phi = point.z.atan2(point.x) + PI;
+        let theta = point.y.atan2(ring_distance - self.major_radius) + PI;
+        (phi / (2.0 * PI), theta / (2.0 * PI))
+    }
+
+    ///
+    /// Returns the distance along the given Ray to its nearest intersection
+    /// with the Torus, if there is one.
+    ///
+    /// # Arguments
+    /// * `&self` - The Torus.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<f64> {
+
+        let big_r = self.major_radius;
+        let small_r = self.minor_radius;
+        let bounding_radius = big_r + small_r;
+
+        // Work with a unit direction in local space so the quartic is well scaled.
+        let direction_length = ray.direction.mag();
+        let direction = self.to_local(ray.direction) / direction_length;
+        let mut origin = self.to_local(ray.origin - self.center);
+
+        // Reject rays that miss the bounding sphere entirely.
+        let closest_approach = -origin.dot(direction);
+        let offset_squared = (origin + closest_approach * direction).mag_squared();
+        if bounding_radius * bounding_radius < offset_squared { return None; }
+
+        // Move the origin up to the bounding sphere. Far away origins make the
+        // quartic's coefficients huge and destroy the precision of its roots.
+        let half_chord = (bounding_radius * bounding_radius - offset_squared).sqrt();
+        let shift = (closest_approach - half_chord).max(0.0);
+        origin += shift * direction;
+
+        let o_dot_d = origin.dot(direction);
+        let k = origin.mag_squared() + big_r * big_r - small_r * small_r;
+        let four_r_squared = 4.0 * big_r * big_r;
+
+        let a = 4.0 * o_dot_d;
+        let b = 2.0 * k + 4.0 * o_dot_d * o_dot_d - four_r_squared * (direction.x * direction.x + direction.z * direction.z);
+        let c = 4.0 * o_dot_d * k - 2.0 * four_r_squared * (origin.x * direction.x + origin.z * direction.z);
+        let d = k * k - four_r_squared * (origin.x * origin.x + origin.z * origin.z);
+
+        solve_quartic(a, b, c, d)
+            .into_iter()
+            .map(|root| (root + shift) / direction_length)
+            .find(|&root| min_dist <= root && root <= max_dist)
+    }
+
+    ///
+    /// Returns the point sampled SurfacePoint at the given distance along the
+    /// Ray, in the same space as the Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Torus.
+    /// * `ray` - The intersecting Ray.
+    /// * `distance` - The distance along the Ray to the intersection.
+    ///
+    fn get_surface(&self, ray: Ray, distance: f64) -> SurfacePoint {
+        let world_point = ray.get_point(distance);
+        let point = self.to_local(world_point - self.center);
+        let ring_distance = (point.x * point.x + point.z * point.z).sqrt();
+        let ring_point = Vector3::new(point.x, 0.0, point.z) * (self.major_radius / ring_distance);
+        let normal = self.to_world(point - ring_point).unit();
+        SurfacePoint::new(self.get_uv(point), world_point, world_point - self.center, normal)
+    }
+
+    ///
+    /// Returns the distance along the given Ray to its nearest intersection
+    /// with the Torus that isn't cut out by the Torus's opacity mask.
+    ///
+    /// # Arguments
+    /// * `&self` - The Torus.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+    /// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+    ///
+    fn get_opaque_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<f64> {
+        get_opaque_intersect(
+            &self.material,
+            ray,
+            min_dist,
+            |min_dist| self.get_intersect(ray, min_dist, max_dist),
+            |distance| self.get_surface(ray, distance),
+            get_distance_past,
+        )
+    }
+}
+
+impl Hittable for Torus {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the Torus and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Torus intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let distance = self.get_opaque_intersect(ray, min_dist, max_dist)?;
+        let surface = self.get_surface(ray, distance);
+        let is_front = ray.direction.dot(surface.normal) < 0.0;
+        let surface = surface.with_derivatives(ray, true, |point| self.get_uv(self.to_local(point - self.center)));
+        let mut normal = self.material.get_normal(surface);
+        if !is_front { normal = -normal };
+
+        Some(Hit::new(
+            ray,
+            distance,
+            normal,
+            is_front,
+            self.material.texture.get_color(surface),
+            self.material.get_reflectivity(surface),
+            self.material.get_refraction_index(surface),
+        ))
+    }
+
+    ///
+    /// Returns true if there is a Hit between the Torus and a given Ray.
+    ///
+    /// # Arguments
+    /// * `&self` - The Torus intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+        self.get_opaque_intersect(ray, min_dist, max_dist).is_some()
+    }
+
+    ///
+    /// Returns an AABB that surrounds the Torus.
+    ///
+    /// # Arguments
+    /// * `&self` - The Torus.
+    ///
+    fn get_aabb(&self) -> AABB {
+        let n = self.axis;
+        let extent = Vector3::new(
+            self.major_radius * (1.0 - n.x * n.x).max(0.0).sqrt() + self.minor_radius,
+            self.major_radius * (1.0 - n.y * n.y).max(0.0).sqrt() + self.minor_radius,
+            self.major_radius * (1.0 - n.z * n.z).max(0.0).sqrt() + self.minor_radius,
+        );
+        AABB::new(self.center - extent, self.center + extent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::texture::monochrome::Monochrome;
+
+    fn assert_hit(hit: Option<Hit>, distance: f64, normal: Vector3) {
+        let hit = hit.unwrap();
+        assert!((hit.distance - distance).abs() < 1e-9, "{} != {}", hit.distance, distance);
+        assert!((hit.normal - normal).mag() < 1e-9);
+        assert!(hit.is_front);
+    }
+
+    fn get_material() -> Material {
+        Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None)
+    }
+
+    #[test]
+    fn rays_hit_the_outside_and_top_of_the_tube() {
+        let torus = Torus::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0), 2.0, 0.5, get_material());
+
+        let side_ray = Ray::new(Point3::new(5.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+        assert_hit(torus.get_hit(side_ray, 0.0, f64::INFINITY), 2.5, Vector3::new(1.0, 0.0, 0.0));
+        // Leaving the tube into the hole.
+        let exit = torus.get_hit(side_ray, 3.0, f64::INFINITY).unwrap();
+        assert!((exit.distance - 3.5).abs() < 1e-9);
+        assert!(!exit.is_front);
+
+        let top_ray = Ray::new(Point3::new(2.0, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        assert_hit(torus.get_hit(top_ray, 0.0, f64::INFINITY), 4.5, Vector3::new(0.0, 1.0, 0.0));
+
+        // Straight down through the hole.
+        assert!(!torus.is_hit(Ray::new(Point3::new(0.0, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0)), 0.0, f64::INFINITY));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::hittable::aabb::AABB;
+use crate::Ray;
+use crate::vector3::Point3;
+
+///
+/// Returns the (distance, b1, b2) of the intersection between a Ray and a
+/// triangle, if there is one, using the Moller-Trumbore algorithm. b1 and b2
+/// are the barycentric weights of the second and third corners, so the point
+/// hit is (1 - b1 - b2) * p0 + b1 * p1 + b2 * p2.
+///
+/// # Arguments
+/// * `ray` - The intersecting Ray.
+/// * `vertices` - The triangle's corners.
+/// * `min_dist` - The minimum distance along the Ray to check for an intersection.
+/// * `max_dist` - The maximum distance along the Ray to check for an intersection.
+///
+pub fn get_triangle_intersect(ray: Ray, vertices: [Point3; 3], min_dist: f64, max_dist: f64) -> Option<(f64, f64, f64)> {
+
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+    let p = ray.direction.cross(edge2);
+    let determinant = edge1.dot(p);
+    if determinant.abs() < 1e-12 { return None; }
+
+    let inverse_determinant = 1.0 / determinant;
+    let s = ray.origin - vertices[0];
+    let b1 = s.dot(p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&b1) { return None; }
+    let q = s.cross(edge1);
+    let b2 = ray.direction.dot(q) * inverse_determinant;
+    if b2 < 0.0 || 1.0 < b1 + b2 { return None; }
+
+    let distance = edge2.dot(q) * inverse_determinant;
+    if distance < min_dist || max_dist < distance { return None; }
+
+    Some((distance, b1, b2))
+}
+
+///
+/// Returns the barycentric weights (b1, b2) of a point with respect to a
+/// triangle, in the same form as get_triangle_intersect. Points off the
+/// triangle's plane are projected onto it.
+///
+/// # Arguments
+/// * `point` - The point.
+/// * `vertices` - The triangle's corners.
+///
+pub fn get_barycentric(point: Point3, vertices: [Point3; 3]) -> (f64, f64) {
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+    let offset = point - vertices[0];
+    let (d11, d12, d22) = (edge1.dot(edge1), edge1.dot(edge2), edge2.dot(edge2));
+    let (d1p, d2p) = (edge1.dot(offset), edge2.dot(offset));
+    let denominator = d11 * d22 - d12 * d12;
+    ((d22 * d1p - d12 * d2p) / denominator, (d11 * d2p - d12 * d1p) / denominator)
+}
+
+///
+/// Returns an AABB that surrounds a triangle.
+///
+/// # Arguments
+/// * `vertices` - The triangle's corners.
+///
+pub fn get_triangle_aabb(vertices: [Point3; 3]) -> AABB {
+    let [a, b, c] = vertices;
+    AABB::new(
+        Point3::new(a.x.min(b.x).min(c.x), a.y.min(b.y).min(c.y), a.z.min(b.z).min(c.z)),
+        Point3::new(a.x.max(b.x).max(c.x), a.y.max(b.y).max(c.y), a.z.max(b.z).max(c.z)),
+    ).pad()
+}
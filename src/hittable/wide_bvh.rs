@@ -0,0 +1,603 @@
+//! A bounding volume hierarchy with BVH_WIDTH children per node.
+//!
+//! There is no SIMD library or intrinsic here: the lanes are plain
+//! `[f64; 4]` arrays and the lane test, get_lane_entries, is scalar code
+//! that relies on the compiler to auto-vectorize it. It does. In a release
+//! build for the default x86_64 target it compiles to packed SSE2
+//! instructions (subpd, mulpd, minpd, maxpd), two lanes each, with no loop
+//! over the lanes, and with `-C target-cpu=x86-64-v3` to the AVX ymm forms,
+//! all four lanes each. To check, look for them in the output of
+//! `cargo rustc --release -- --emit asm`.
+
+use crate::Hit;
+use crate::hittable::aabb::AABB;
+use crate::hittable::aabb::surrounding_box;
+use crate::hittable::accelerator::Accelerator;
+use crate::hittable::bvh_node::INTERSECTION_COST;
+use crate::hittable::bvh_node::partition_sah;
+use crate::hittable::Hittable;
+use crate::hittable::linear_bvh::Primitive;
+use crate::Ray;
+use crate::ray::PACKET_SIZE;
+use crate::ray::RayPacket;
+
+use std::mem::size_of;
+
+/// The most children a node of a WideBvh can have.
+pub const BVH_WIDTH: usize = 4;
+
+/// The deepest a WideBvh can be. Every node visited can leave up to
+/// BVH_WIDTH - 1 siblings on the traversal stack, which has a fixed size.
+const MAX_DEPTH: usize = 64;
+
+/// The most entries the traversal stack can hold.
+const STACK_SIZE: usize = MAX_DEPTH * (BVH_WIDTH - 1) + 1;
+
+/// One f64 for each child of a WideNode.
+type Lanes = [f64; BVH_WIDTH];
+
+/// A node of a WideBvh. The AABBs of its children are stored as lanes, one
+/// array per side, so that all of them can be checked against a Ray with the
+/// same few instructions, which the compiler vectorizes.
+#[derive(Clone, Copy)]
+struct WideNode {
+    /// The minimum x, y and z of each child's AABB. Unused lanes hold an
+    /// inside out box that no Ray can hit.
+    min: [Lanes; 3],
+    /// The maximum x, y and z of each child's AABB.
+    max: [Lanes; 3],
+    /// For a leaf child, the index of its first primitive. For an interior
+    /// child, the index of its node.
+    offsets: [u32; BVH_WIDTH],
+    /// The number of primitives in a leaf child, or 0 for an interior child.
+    primitive_counts: [u16; BVH_WIDTH],
+}
+
+/// A Ray with everything the lane test needs worked out ahead of time.
+#[derive(Clone, Copy)]
+struct LaneRay {
+    /// The Ray.
+    ray: Ray,
+    /// The Ray's origin as an array.
+    origin: [f64; 3],
+    /// One over each part of the Ray's direction.
+    inverse_direction: [f64; 3],
+}
+
+impl LaneRay {
+
+    ///
+    /// Returns a LaneRay for the given Ray.
+    ///
+    /// # Arguments
+    /// * `ray` - The Ray.
+    ///
+    fn new(ray: Ray) -> Self {
+        let direction = [ray.direction.x, ray.direction.y, ray.direction.z];
+        Self {
+            ray: ray,
+            origin: [ray.origin.x, ray.origin.y, ray.origin.z],
+            inverse_direction: direction.map(|d| 1.0 / d),
+        }
+    }
+}
+
+/// A child of a WideNode waiting on the packet traversal stack.
+#[derive(Clone, Copy)]
+struct StackEntry {
+    /// The child's offset. See WideNode::offsets.
+    offset: u32,
+    /// The child's primitive count. See WideNode::primitive_counts.
+    primitive_count: u16,
+    /// How far along each Ray it enters the child's AABB, or infinity for the
+    /// Rays that miss it.
+    entries: [f64; PACKET_SIZE],
+}
+
+/// Represents a bounding volume hierarchy whose nodes have up to BVH_WIDTH
+/// children. The children's AABBs are checked against a Ray all at once, so
+/// a Ray visits about half as many nodes as in a binary hierarchy, and each
+/// visit costs about the same. Like a LinearBvh, the nodes are stored in a
+/// flat list and walked with a stack. RayPackets can be traced through it
+/// together with get_packet_hits.
+pub struct WideBvh {
+    /// The nodes of the hierarchy. The first node is the root.
+    nodes: Vec<WideNode>,
+    /// The primitives referenced by the leaves, in leaf order.
+    primitives: Vec<Primitive>,
+    /// The AABB of the whole hierarchy.
+    bounding_box: AABB,
+}
+
+impl WideBvh {
+
+    ///
+    /// Returns a WideBvh built over the given primitives with the surface area
+    /// heuristic. Each node is filled by splitting its largest child that the
+    /// SAH says is worth splitting, until it has BVH_WIDTH children.
+    ///
+    /// # Arguments
+    /// * `primitives` - The primitives that will make up the leaves of the hierarchy. Must not be empty.
+    /// * `max_leaf_size` - The most primitives a leaf may hold.
+    ///
+    pub fn new(primitives: Vec<Primitive>, max_leaf_size: usize) -> Self {
+
+        assert!(!primitives.is_empty(), "a WideBvh needs at least one primitive");
+
+        let mut items: Vec<(AABB, usize)> = primitives.iter().map(|p| p.get_aabb()).zip(0..).collect();
+        let bounding_box = items.iter().map(|item| item.0).reduce(surrounding_box).unwrap();
+        let mut order: Vec<usize> = Vec::with_capacity(items.len());
+        let mut nodes: Vec<WideNode> = vec![];
+        flatten_wide(&mut items, max_leaf_size.clamp(1, u16::MAX as usize), 0, &mut nodes, &mut order);
+
+        // Put the primitives in leaf order.
+        let mut slots: Vec<Option<Primitive>> = primitives.into_iter().map(Some).collect();
+        let primitives = order.into_iter().map(|i| slots[i].take().unwrap()).collect();
+
+        Self {
+            nodes: nodes,
+            primitives: primitives,
+            bounding_box: bounding_box,
+        }
+    }
+
+    ///
+    /// Returns Some(Hit) for each Ray of a RayPacket that hits the WideBvh.
+    /// The Rays walk the hierarchy together: a child is visited if any of
+    /// them can hit it, and only the Rays that can are checked against what
+    /// is inside. Each Ray gets exactly the Hit that get_hit would give it.
+    ///
+    /// # Arguments
+    /// * `&self` - The WideBvh intersected by the Rays.
+    /// * `packet` - The intersecting Rays.
+    /// * `min_dist` - The minimum distance along the Rays to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Rays to check for a Hit.
+    ///
+    pub fn get_packet_hits(&self, packet: &RayPacket, min_dist: f64, max_dist: f64) -> [Option<Hit>; PACKET_SIZE] {
+
+        let rays = packet.map(LaneRay::new);
+        let mut closest_distances = [max_dist; PACKET_SIZE];
+        let mut hits: [Option<Hit>; PACKET_SIZE] = [None; PACKET_SIZE];
+
+        let mut entries = [f64::INFINITY; PACKET_SIZE];
+        for (entry, ray) in entries.iter_mut().zip(&rays) {
+            if let Some((distance, _)) = self.bounding_box.get_intersect(ray.ray, min_dist, max_dist) {
+                *entry = distance;
+            }
+        }
+
+        let mut stack = [StackEntry { offset: 0, primitive_count: 0, entries: entries }; STACK_SIZE];
+        let mut stack_size = 1;
+
+        while 0 < stack_size {
+
+            stack_size -= 1;
+            let child = stack[stack_size];
+            let is_active: [bool; PACKET_SIZE] = std::array::from_fn(|r| child.entries[r] <= closest_distances[r]);
+            if !is_active.contains(&true) { continue; }
+
+            if 0 < child.primitive_count {
+                let first = child.offset as usize;
+                for primitive in &self.primitives[first..first + child.primitive_count as usize] {
+                    for r in (0..PACKET_SIZE).filter(|&r| is_active[r]) {
+                        if let Some(h) = primitive.get_hit(rays[r].ray, min_dist, closest_distances[r]) {
+                            closest_distances[r] = h.distance;
+                            hits[r] = Some(h);
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let node = &self.nodes[child.offset as usize];
+            let mut lane_entries = [[f64::INFINITY; PACKET_SIZE]; BVH_WIDTH];
+            for r in (0..PACKET_SIZE).filter(|&r| is_active[r]) {
+                let entries = get_lane_entries(node, &rays[r], min_dist, closest_distances[r]);
+                for (lane, entry) in entries.into_iter().enumerate() {
+                    lane_entries[lane][r] = entry;
+                }
+            }
+
+            // The packet visits the children in the order the nearest Ray enters them.
+            let nearest = lane_entries.map(|e| e.into_iter().fold(f64::INFINITY, f64::min));
+            for lane in get_far_to_near(&nearest) {
+                if nearest[lane] == f64::INFINITY { continue; }
+                stack[stack_size] = StackEntry {
+                    offset: node.offsets[lane],
+                    primitive_count: node.primitive_counts[lane],
+                    entries: lane_entries[lane],
+                };
+                stack_size += 1;
+            }
+        }
+
+        hits
+    }
+}
+
+///
+/// Returns the result of a function applied to each pair of lanes. Every lane
+/// gets the same work with no branches, so once this is inlined the compiler
+/// can do them together with SIMD instructions.
+///
+/// # Arguments
+/// * `a` - The first lanes.
+/// * `b` - The second lanes.
+/// * `f` - The function.
+///
+#[inline(always)]
+fn zip_lanes(a: Lanes, b: Lanes, f: impl Fn(f64, f64) -> f64) -> Lanes {
+    std::array::from_fn(|lane| f(a[lane], b[lane]))
+}
+
+///
+/// Returns how far along a Ray it enters each child of a WideNode, or
+/// infinity for the children it misses within the range. Does the same
+/// arithmetic as AABB::get_intersect, for every lane at once.
+///
+/// # Arguments
+/// * `node` - The WideNode.
+/// * `ray` - The intersecting Ray.
+/// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+/// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+///
+fn get_lane_entries(node: &WideNode, ray: &LaneRay, min_dist: f64, max_dist: f64) -> Lanes {
+
+    let mut entry: Lanes = [min_dist; BVH_WIDTH];
+    let mut exit: Lanes = [max_dist; BVH_WIDTH];
+
+    for axis in 0..3 {
+
+        let (origin, inverse_direction) = (ray.origin[axis], ray.inverse_direction[axis]);
+        let (near_sides, far_sides) = if inverse_direction < 0.0 {
+            (node.max[axis], node.min[axis])
+        } else {
+            (node.min[axis], node.max[axis])
+        };
+
+        // A Ray parallel to the axis has an infinite inverse direction, so a
+        // child is either infinitely near or infinitely far along it. A side
+        // right on the origin gives NaN, which max and min skip over.
+        let near = near_sides.map(|side| (side - origin) * inverse_direction);
+        let far = far_sides.map(|side| (side - origin) * inverse_direction);
+        entry = zip_lanes(entry, near, f64::max);
+        exit = zip_lanes(exit, far, f64::min);
+    }
+
+    zip_lanes(entry, exit, |entry, exit| if exit < entry { f64::INFINITY } else { entry })
+}
+
+///
+/// Returns the lanes of a WideNode ordered from the furthest child to the
+/// nearest, so that pushing them onto the traversal stack in that order
+/// leaves the nearest on top.
+///
+/// # Arguments
+/// * `distances` - How far away each child is.
+///
+fn get_far_to_near(distances: &[f64; BVH_WIDTH]) -> [usize; BVH_WIDTH] {
+
+    // An insertion sort, which is as fast as it gets for so few lanes.
+    let mut lanes: [usize; BVH_WIDTH] = std::array::from_fn(|lane| lane);
+    for i in 1..BVH_WIDTH {
+        let mut j = i;
+        while 0 < j && distances[lanes[j - 1]] < distances[lanes[j]] {
+            lanes.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+
+    lanes
+}
+
+///
+/// Returns Some(middle) if the given items should be split into
+/// items[..middle] and items[middle..], or None if they should be a leaf.
+/// The items are reordered by partition_sah.
+///
+/// # Arguments
+/// * `items` - The (AABB, index) of every primitive in this part of the hierarchy.
+/// * `max_leaf_size` - The most primitives a leaf may hold.
+///
+fn get_split(items: &mut [(AABB, usize)], max_leaf_size: usize) -> Option<usize> {
+
+    if items.len() == 1 { return None; }
+
+    let (middle, split_cost) = partition_sah(items, &|item: &(AABB, usize)| item.0);
+    let leaf_cost = items.len() as f64 * INTERSECTION_COST;
+    if items.len() <= max_leaf_size && leaf_cost <= split_cost { None } else { Some(middle) }
+}
+
+///
+/// Appends the nodes of a WideBvh over the given items to nodes, depth first,
+/// and appends the items' indices to order in the same order the leaves
+/// reference them. Returns the index of the new node.
+///
+/// # Arguments
+/// * `items` - The (AABB, index) of every primitive in this part of the hierarchy.
+/// * `max_leaf_size` - The most primitives a leaf may hold.
+/// * `depth` - How deep in the hierarchy the new node is.
+/// * `nodes` - The flattened nodes.
+/// * `order` - The primitive indices in leaf order.
+///
+fn flatten_wide(items: &mut [(AABB, usize)], max_leaf_size: usize, depth: usize, nodes: &mut Vec<WideNode>, order: &mut Vec<usize>) -> usize {
+
+    // Each child is a (start, end, split) range of items, where split is
+    // where the SAH would split it, or None if it should be a leaf.
+    let mut children = vec![(0, items.len(), get_split(items, max_leaf_size))];
+
+    while children.len() < BVH_WIDTH {
+
+        let area = |&(start, end, _): &(usize, usize, Option<usize>)| {
+            items[start..end].iter().map(|item| item.0).reduce(surrounding_box).unwrap().surface_area()
+        };
+        let largest = (0..children.len())
+            .filter(|&i| children[i].2.is_some())
+            .max_by(|&a, &b| area(&children[a]).total_cmp(&area(&children[b])));
+        let i = match largest {
+            Some(i) => i,
+            None => break,
+        };
+
+        let (start, end, split) = children[i];
+        let middle = start + split.unwrap();
+        let left_split = get_split(&mut items[start..middle], max_leaf_size);
+        let right_split = get_split(&mut items[middle..end], max_leaf_size);
+        children[i] = (start, middle, left_split);
+        children.insert(i + 1, (middle, end, right_split));
+    }
+
+    let node_index = nodes.len();
+    nodes.push(WideNode {
+        min: [[f64::INFINITY; BVH_WIDTH]; 3],
+        max: [[f64::NEG_INFINITY; BVH_WIDTH]; 3],
+        offsets: [0; BVH_WIDTH],
+        primitive_counts: [0; BVH_WIDTH],
+    });
+
+    for (lane, (start, end, split)) in children.into_iter().enumerate() {
+
+        let bounding_box = items[start..end].iter().map(|item| item.0).reduce(surrounding_box).unwrap();
+        for axis in 0..3 {
+            nodes[node_index].min[axis][lane] = bounding_box.min[axis];
+            nodes[node_index].max[axis][lane] = bounding_box.max[axis];
+        }
+
+        // Past the maximum depth, whatever is left becomes one big leaf.
+        if split.is_none() || MAX_DEPTH - 1 <= depth {
+            nodes[node_index].offsets[lane] = order.len() as u32;
+            nodes[node_index].primitive_counts[lane] = (end - start) as u16;
+            order.extend(items[start..end].iter().map(|item| item.1));
+        } else {
+            let child_index = flatten_wide(&mut items[start..end], max_leaf_size, depth + 1, nodes, order);
+            nodes[node_index].offsets[lane] = child_index as u32;
+        }
+    }
+
+    node_index
+}
+
+impl Hittable for WideBvh {
+
+    ///
+    /// Returns Some(Hit) if there is a Hit between the WideBvh and a given Ray.
+    /// The children of each node are visited nearest first, so close Hits are
+    /// found early and used to skip any child that is further away.
+    ///
+    /// # Arguments
+    /// * `&self` - The WideBvh intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn get_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
+
+        let (root_entry, _) = self.bounding_box.get_intersect(ray, min_dist, max_dist)?;
+
+        let lane_ray = LaneRay::new(ray);
+        let mut closest_distance = max_dist;
+        let mut hit: Option<Hit> = None;
+
+        // Each entry is a child's (offset, primitive_count, entry distance).
+        let mut stack = [(0_u32, 0_u16, root_entry); STACK_SIZE];
+        let mut stack_size = 1;
+
+        while 0 < stack_size {
+
+            stack_size -= 1;
+            let (offset, primitive_count, entry) = stack[stack_size];
+            if closest_distance < entry { continue; }
+
+            if 0 < primitive_count {
+                let first = offset as usize;
+                for primitive in &self.primitives[first..first + primitive_count as usize] {
+                    if let Some(h) = primitive.get_hit(ray, min_dist, closest_distance) {
+                        closest_distance = h.distance;
+                        hit = Some(h);
+                    }
+                }
+                continue;
+            }
+
+            let node = &self.nodes[offset as usize];
+            let entries = get_lane_entries(node, &lane_ray, min_dist, closest_distance);
+            for lane in get_far_to_near(&entries) {
+                if entries[lane] == f64::INFINITY { continue; }
+                stack[stack_size] = (node.offsets[lane], node.primitive_counts[lane], entries[lane]);
+                stack_size += 1;
+            }
+        }
+
+        hit
+    }
+
+    ///
+    /// Returns true if there is a Hit between the WideBvh and a given Ray.
+    /// Any Hit will do, so the children of each node are not sorted and the
+    /// traversal stops at the first primitive hit.
+    ///
+    /// # Arguments
+    /// * `&self` - The WideBvh intersected by the Ray.
+    /// * `ray` - The intersecting Ray.
+    /// * `min_dist` - The minimum distance along the Ray to check for a Hit.
+    /// * `max_dist` - The maximum distance along the Ray to check for a Hit.
+    ///
+    fn is_hit(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+
+        if !self.bounding_box.is_hit(ray, min_dist, max_dist) { return false; }
+
+        let lane_ray = LaneRay::new(ray);
+
+        // Each entry is a child's (offset, primitive_count).
+        let mut stack = [(0_u32, 0_u16); STACK_SIZE];
+        let mut stack_size = 1;
+
+        while 0 < stack_size {
+
+            stack_size -= 1;
+            let (offset, primitive_count) = stack[stack_size];
+
+            if 0 < primitive_count {
+                let first = offset as usize;
+                let primitives = &self.primitives[first..first + primitive_count as usize];
+                if primitives.iter().any(|primitive| primitive.is_hit(ray, min_dist, max_dist)) { return true; }
+                continue;
+            }
+
+            let node = &self.nodes[offset as usize];
+            let entries = get_lane_entries(node, &lane_ray, min_dist, max_dist);
+            for (lane, entry) in entries.into_iter().enumerate() {
+                if entry == f64::INFINITY { continue; }
+                stack[stack_size] = (node.offsets[lane], node.primitive_counts[lane]);
+                stack_size += 1;
+            }
+        }
+
+        false
+    }
+
+    ///
+    /// Returns an AABB that surrounds the WideBvh.
+    ///
+    /// # Arguments
+    /// * `&self` - The WideBvh.
+    ///
+    fn get_aabb(&self) -> AABB {
+        self.bounding_box
+    }
+
+    ///
+    /// Returns how many bytes the WideBvh's nodes and primitives take up. See
+    /// LinearBvh::get_memory_size.
+    ///
+    /// # Arguments
+    /// * `&self` - The WideBvh.
+    ///
+    fn get_memory_size(&self) -> usize {
+        size_of::<Self>()
+            + self.nodes.capacity() * size_of::<WideNode>()
+            + self.primitives.capacity() * size_of::<Primitive>()
+    }
+}
+
+impl Accelerator for WideBvh {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::bvh_node::construct_sah_bvh_root;
+    use crate::hittable::quad::Quad;
+    use crate::hittable::sphere::Sphere;
+    use crate::material::Material;
+    use crate::texture::monochrome::Monochrome;
+    use crate::vector3::Point3;
+    use crate::vector3::Vector3;
+
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use std::rc::Rc;
+
+    fn get_material() -> Material {
+        Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.5, 0.5))), None, None)
+    }
+
+    fn get_point(rng: &mut StdRng, size: f64) -> Point3 {
+        Point3::new(rng.gen_range(-size..size), rng.gen_range(-size..size), rng.gen_range(-size..size))
+    }
+
+    /// Returns a random Ray. Some are parallel to an axis or two, which gives
+    /// the lane test infinite inverse directions to deal with.
+    fn get_random_ray(rng: &mut StdRng) -> Ray {
+        let mut direction = get_point(rng, 1.0);
+        match rng.gen_range(0..4) {
+            0 => direction.x = 0.0,
+            1 => (direction.y, direction.z) = (0.0, -0.0),
+            _ => (),
+        }
+        Ray::new(get_point(rng, 15.0), direction)
+    }
+
+    #[test]
+    fn hits_match_a_binary_bvh() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut hittables: Vec<Rc<dyn Hittable>> = (0..500).map(|i| -> Rc<dyn Hittable> {
+            let center = get_point(&mut rng, 10.0);
+            if i % 2 == 0 {
+                Rc::new(Sphere::new(center, rng.gen_range(0.1..0.8), get_material()))
+            } else {
+                Rc::new(Quad::new(center, get_point(&mut rng, 1.0), get_point(&mut rng, 1.0), get_material()))
+            }
+        }).collect();
+        let length = hittables.len();
+        let expected_bvh = construct_sah_bvh_root(&mut hittables, 0, length, 1);
+
+        for max_leaf_size in [1, 4] {
+            let bvh = WideBvh::new(hittables.iter().map(|h| h.get_primitive().unwrap()).collect(), max_leaf_size);
+
+            for _ in 0..500 {
+                let packet: RayPacket = std::array::from_fn(|_| get_random_ray(&mut rng));
+                let packet_hits = bvh.get_packet_hits(&packet, 0.001, f64::INFINITY);
+                for (ray, packet_hit) in packet.into_iter().zip(packet_hits) {
+                    let expected = expected_bvh.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance);
+                    assert_eq!(bvh.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance), expected);
+                    assert_eq!(packet_hit.map(|hit| hit.distance), expected);
+                    assert_eq!(bvh.is_hit(ray, 0.001, f64::INFINITY), expected.is_some());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn packet_hits_match_get_hit_on_axis_parallel_rays() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut get_grid_point = |step: f64, size: i32| {
+            Point3::new(rng.gen_range(-size..size) as f64, rng.gen_range(-size..size) as f64, rng.gen_range(-size..size) as f64) * step
+        };
+        let primitives: Vec<Primitive> = (0..200)
+            .map(|_| Primitive::Sphere(Sphere::new(get_grid_point(1.0, 5), 0.5, get_material())))
+            .collect();
+        let bvh = WideBvh::new(primitives, 2);
+
+        // The origins are on a half unit grid, so many of them lie right on
+        // the sides of the spheres' AABBs.
+        let directions = [
+            Vector3::new(1.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 0.0, -1.0),
+        ];
+        let mut hit_count = 0;
+        for i in 0..500 {
+            let packet: RayPacket = std::array::from_fn(|j| Ray::new(get_grid_point(0.5, 12), directions[(i + j) % directions.len()]));
+            let packet_hits = bvh.get_packet_hits(&packet, 0.001, f64::INFINITY);
+            for (ray, packet_hit) in packet.into_iter().zip(packet_hits) {
+                let expected = bvh.get_hit(ray, 0.001, f64::INFINITY).map(|hit| hit.distance);
+                assert_eq!(packet_hit.map(|hit| hit.distance), expected);
+                hit_count += expected.is_some() as usize;
+            }
+        }
+        assert!(0 < hit_count);
+    }
+}
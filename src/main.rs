@@ -1,30 +1,59 @@
+mod benchmark;
 mod camera;
 mod color;
 mod hit;
 mod hittable;
 mod material;
+mod matrix3;
+mod matrix4;
+mod polynomial;
+mod quaternion;
 mod ray;
 mod scene;
+mod sdf;
 mod texture;
+mod transform;
 mod vector3;
 
+use crate::benchmark::run_accelerator_benchmark;
+use crate::benchmark::run_animation_benchmark;
+use crate::benchmark::run_bvh_benchmark;
 use crate::camera::Camera;
 use crate::color::*;
 use crate::hit::Hit;
+use crate::hittable::accelerator::ACCELERATOR_KINDS;
+use crate::hittable::accelerator::AcceleratorKind;
+use crate::hittable::bvh_node::DEFAULT_MAX_LEAF_SIZE;
+use crate::hittable::Hittable;
+use crate::hittable::heightfield::Heightfield;
+use crate::hittable::instance::Instance;
+use crate::hittable::mesh::Mesh;
 use crate::hittable::sphere::Sphere;
 use crate::material::Material;
+use crate::material::OpacityMask;
 use crate::ray::Ray;
+use crate::scene::NAMED_SCENES;
 use crate::scene::Scene;
+use crate::scene::SceneBuilder;
 use crate::scene::construct_book1_final;
+use crate::scene::construct_named_scene;
 use crate::texture::checkered::Checkered;
+use crate::texture::image_texture::FILTERS;
+use crate::texture::image_texture::Filter;
+use crate::texture::image_texture::ImageTexture;
+use crate::texture::image_texture::WRAP_MODES;
+use crate::texture::image_texture::WrapMode;
 use crate::texture::monochrome::Monochrome;
 use crate::texture::noisy::Noisy;
+use crate::texture::Texture;
+use crate::transform::Transform;
 use crate::vector3::Point3;
 use crate::vector3::Vector3;
 
 use noise::Perlin;
 use rand::Rng;
 use rand::rngs::ThreadRng;
+use std::env;
 use std::rc::Rc;
 
 ///
@@ -64,6 +93,54 @@ fn get_ray_color(ray: Ray, scene: &Scene, depth: i32, rng: &ThreadRng) -> Color
 
 fn main() {
 
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    // `cargo run --release -- bench` compares BVH builders and traversals
+    // instead of rendering, and `cargo run --release -- bench-accelerators
+    // <scene>` compares every kind of Accelerator on one of the named scenes.
+    // `cargo run --release -- bench-animation` times keeping a Scene up to
+    // date while things in it move, appear and disappear.
+    match args.first().map(String::as_str) {
+        Some("bench") => {
+            run_bvh_benchmark();
+            return;
+        },
+        Some("bench-accelerators") => {
+            run_accelerator_benchmark(args.get(1).map_or("book1", String::as_str));
+            return;
+        },
+        Some("bench-animation") => {
+            run_animation_benchmark();
+            return;
+        },
+        _ => (),
+    }
+
+    // `--accelerator <name>` picks the kind of Accelerator the scene is built around.
+    let accelerator_kind = match args.iter().position(|arg| arg == "--accelerator") {
+        Some(i) => match args.get(i + 1).and_then(|name| AcceleratorKind::from_name(name)) {
+            Some(kind) => kind,
+            None => {
+                let names: Vec<&str> = ACCELERATOR_KINDS.iter().map(|kind| kind.get_name()).collect();
+                eprintln!("--accelerator must be one of: {}", names.join(", "));
+                return;
+            },
+        },
+        None => AcceleratorKind::Bvh,
+    };
+
+    // `--max-leaf-size <n>` sets the most hittables a leaf of the BVH may hold.
+    let max_leaf_size = match args.iter().position(|arg| arg == "--max-leaf-size") {
+        Some(i) => match args.get(i + 1).and_then(|size| size.parse::<usize>().ok()) {
+            Some(size) if 0 < size => size,
+            _ => {
+                eprintln!("--max-leaf-size needs a whole number above 0");
+                return;
+            },
+        },
+        None => DEFAULT_MAX_LEAF_SIZE,
+    };
+
     let aspect_ratio = 16.0 / 9.0;
     let image_height = 1080;
     let image_width = (image_height as f64 * aspect_ratio) as i32;
@@ -81,9 +158,119 @@ fn main() {
     let material1 = Material::new(Box::new(texture1), Some(0.0), None);
     let sphere1 = Sphere::new(Point3::new(-2.1, 1.0, 0.0), 1.0, material1);
 
-    let texture2 = Noisy::new(Perlin::new(2), 50.0, 10, false, _LIGHT_RED);
-    let material2 = Material::new(Box::new(texture2), None, None);
-    let sphere2 = Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, material2);
+    // `--texture <path>` wraps an image around the middle sphere instead.
+    let texture_path = match args.iter().position(|arg| arg == "--texture") {
+        Some(i) => match args.get(i + 1) {
+            Some(path) => Some(path),
+            None => {
+                eprintln!("--texture needs the path to an image");
+                return;
+            },
+        },
+        None => None,
+    };
+
+    // `--texture-filter <name>` and `--texture-wrap <name>` pick how the image is sampled.
+    let texture_filter = match args.iter().position(|arg| arg == "--texture-filter") {
+        Some(i) => match args.get(i + 1).and_then(|name| Filter::from_name(name)) {
+            Some(filter) => filter,
+            None => {
+                let names: Vec<&str> = FILTERS.iter().map(|filter| filter.get_name()).collect();
+                eprintln!("--texture-filter must be one of: {}", names.join(", "));
+                return;
+            },
+        },
+        None => Filter::Ewa,
+    };
+    let texture_wrap = match args.iter().position(|arg| arg == "--texture-wrap") {
+        Some(i) => match args.get(i + 1).and_then(|name| WrapMode::from_name(name)) {
+            Some(wrap_mode) => wrap_mode,
+            None => {
+                let names: Vec<&str> = WRAP_MODES.iter().map(|wrap_mode| wrap_mode.get_name()).collect();
+                eprintln!("--texture-wrap must be one of: {}", names.join(", "));
+                return;
+            },
+        },
+        None => WrapMode::Repeat,
+    };
+
+    let texture2: Box<dyn Texture> = match texture_path.map(|path| ImageTexture::from_file(path, true, texture_filter, texture_wrap)) {
+        Some(Ok(texture)) => Box::new(texture),
+        Some(Err(e)) => {
+            eprintln!("could not load texture: {}", e);
+            return;
+        },
+        None => Box::new(Noisy::new(Perlin::new(2), 50.0, 10, false, _LIGHT_RED)),
+    };
+    let mut material2 = Material::new(texture2, None, None);
+
+    // `--cutout` cuts holes in it wherever the image is transparent.
+    if args.iter().any(|arg| arg == "--cutout") {
+        match texture_path.map(|path| ImageTexture::alpha_from_file(path, texture_filter, texture_wrap)) {
+            Some(Ok(alpha)) => {
+                material2 = material2.with_opacity_mask(OpacityMask::Threshold { opacity: Box::new(alpha), threshold: 0.5 });
+            },
+            Some(Err(e)) => {
+                eprintln!("could not load texture: {}", e);
+                return;
+            },
+            None => {
+                eprintln!("--cutout needs a --texture to cut out");
+                return;
+            },
+        }
+    }
+
+    // `--mesh <path>` puts the Mesh in an OBJ file in the middle instead,
+    // scaled to fit where the sphere was. Its BVH is cached next to the file
+    // unless `--no-bvh-cache` is given. `--displace <height>` roughens its
+    // surface with noise as it is loaded.
+    let object2: Rc<dyn Hittable> = match args.iter().position(|arg| arg == "--mesh") {
+        Some(i) => {
+            let path = match args.get(i + 1) {
+                Some(path) => path,
+                None => {
+                    eprintln!("--mesh needs the path to an OBJ file");
+                    return;
+                },
+            };
+            let displacement = match args.iter().position(|arg| arg == "--displace") {
+                Some(j) => match args.get(j + 1).and_then(|height| height.parse::<f64>().ok()) {
+                    Some(height) => Some(height),
+                    None => {
+                        eprintln!("--displace needs a height");
+                        return;
+                    },
+                },
+                None => None,
+            };
+            let mesh = match displacement {
+                Some(height) => {
+                    let noise = Noisy::new(Perlin::new(4), 20.0, 4, false, _WHITE);
+                    Mesh::from_obj_displaced(path, material2, &noise, height, 2)
+                },
+                None if args.iter().any(|arg| arg == "--no-bvh-cache") => Mesh::from_obj(path, material2),
+                None => Mesh::from_obj_cached(path, material2),
+            };
+            match mesh {
+                Ok(mesh) => {
+                    let bounding_box = mesh.get_aabb();
+                    let size = bounding_box.max - bounding_box.min;
+                    let scale = 2.0 / size.x.max(size.y).max(size.z);
+                    let center = 0.5 * (bounding_box.min + bounding_box.max);
+                    let placement = Transform::translation(Vector3::new(0.0, 1.0, 0.0))
+                        * Transform::scaling(Vector3::new(scale, scale, scale))
+                        * Transform::translation(-center);
+                    Rc::new(Instance::new(Rc::new(mesh), placement))
+                },
+                Err(e) => {
+                    eprintln!("could not load mesh: {}", e);
+                    return;
+                },
+            }
+        },
+        None => Rc::new(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, material2)),
+    };
 
     let texture3 = Noisy::new(Perlin::new(3), 10.0, 30, true, _LIGHT_GREEN);
     let material3 = Material::new(Box::new(texture3), None, Some(1.5));
@@ -91,12 +278,55 @@ fn main() {
 
     let texture4 = Monochrome::new(_GREY);
     let material4 = Material::new(Box::new(texture4), None, None);
-    let sphere4 = Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, material4);
 
-    let mut scene = Scene::new(Rc::new(sphere1));
-    scene.push(Rc::new(sphere2));
-    scene.push(Rc::new(sphere3));
-    scene.push(Rc::new(sphere4));
+    // `--heightmap <path>` replaces the ground with terrain read from a PGM
+    // image, with white level with where the ground was.
+    let ground: Rc<dyn Hittable> = match args.iter().position(|arg| arg == "--heightmap") {
+        Some(i) => {
+            let origin = Point3::new(-10.0, -1.0, -10.0);
+            let size = Vector3::new(20.0, 1.0, 20.0);
+            match args.get(i + 1).map(|path| Heightfield::from_pgm(path, origin, size, material4)) {
+                Some(Ok(terrain)) => Rc::new(terrain),
+                Some(Err(e)) => {
+                    eprintln!("could not load heightmap: {}", e);
+                    return;
+                },
+                None => {
+                    eprintln!("--heightmap needs the path to a PGM image");
+                    return;
+                },
+            }
+        },
+        None => Rc::new(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, material4)),
+    };
+
+    let mut builder = SceneBuilder::new();
+    builder.set_accelerator_kind(accelerator_kind);
+    builder.set_max_leaf_size(max_leaf_size);
+
+    // `--scene <name>` renders one of the named scenes instead, from the same
+    // camera.
+    match args.iter().position(|arg| arg == "--scene") {
+        Some(i) => match args.get(i + 1).and_then(|name| construct_named_scene(name)) {
+            Some(named_scene) => {
+                for hittable in named_scene.get_hittables() {
+                    builder.push(hittable);
+                }
+            },
+            None => {
+                let names: Vec<&str> = NAMED_SCENES.iter().map(|(name, _)| *name).collect();
+                eprintln!("--scene must be one of: {}", names.join(", "));
+                return;
+            },
+        },
+        None => {
+            builder.push(Rc::new(sphere1));
+            builder.push(object2);
+            builder.push(Rc::new(sphere3));
+            builder.push(ground);
+        },
+    }
+    let scene = builder.build();
 
     let samples_per_pixel = 200;
     let max_bounce_depth = 100;
@@ -107,6 +337,11 @@ fn main() {
 
     let mut rng = rand::thread_rng();
 
+    // Each sample covers less than a whole pixel, so textures are filtered
+    // over a footprint that shrinks as more samples are taken.
+    let footprint_scale = (1.0 / (samples_per_pixel as f64).sqrt()).max(0.125);
+    let pixel_size = (footprint_scale / image_width as f64, footprint_scale / image_height as f64);
+
     for row in (0..image_height).rev() {
         eprint!("\r{} scanlines remaining...      ", row);
         for col in 0..image_width {
@@ -119,7 +354,7 @@ fn main() {
                 let w = (col as f64 + rand_w) / image_width as f64;
                 let h = (row as f64 + rand_h) / image_height as f64;
 
-                let ray = cam.get_ray(w, h);
+                let ray = cam.get_ray(w, h, pixel_size);
                 pixel_color += get_ray_color(ray, &scene, max_bounce_depth, &rng);
             }
 
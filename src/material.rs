@@ -1,17 +1,61 @@
+use crate::color::Color;
+use crate::texture::monochrome::Monochrome;
+use crate::texture::SurfacePoint;
 use crate::texture::Texture;
+use crate::vector3::Vector3;
+
+/// How a Material bends the normals of the surfaces it is on, to give them
+/// detail that catches the light without adding any geometry. The normal is
+/// bent in the surface's tangent frame, see SurfacePoint::get_tangent_frame.
+pub enum NormalMap {
+    /// A tangent space normal map, in the usual blue-ish encoding: red, green
+    /// and blue hold the normal's tangent, bitangent and normal components,
+    /// mapped from [-1, 1] to [0, 1]. Image normal maps are not sRGB encoded.
+    Tangent(Box<dyn Texture>),
+    /// A height map, read with Texture::get_value. The normal is bent as if
+    /// the surface were pushed out by the height times the strength.
+    Bump {
+        /// The height of the surface.
+        height: Box<dyn Texture>,
+        /// How far a height of 1 pushes the surface out.
+        strength: f64,
+    },
+}
+
+/// How a Material cuts holes in the surfaces it is on, for things like leaves,
+/// fences and decals on simple geometry. Rays go straight through the holes,
+/// so they are cut out of both Hits and occlusion queries.
+pub enum OpacityMask {
+    /// The surface is there wherever the opacity, read with
+    /// Texture::get_value, is at least the threshold. Gives hard edges.
+    Threshold {
+        /// The opacity of the surface.
+        opacity: Box<dyn Texture>,
+        /// The opacity below which the surface is cut out.
+        threshold: f64,
+    },
+    /// Each Ray sees the surface with a chance equal to the opacity, so that
+    /// partly opaque areas average out to partly see-through over many
+    /// samples. Gives soft edges, but noise with few samples.
+    Stochastic(Box<dyn Texture>),
+}
 
 /// Represents the material of a Hittable
 pub struct Material {
     /// The material's texture.
     pub texture: Box<dyn Texture>,
-    /// The material's reflectivity, if applicable.
-    pub reflectivity: Option<f64>,
-    /// The material's refraction index, if applicable.
-    pub refraction_index: Option<f64>,
+    /// The material's reflectivity, if applicable, read with Texture::get_value.
+    pub reflectivity: Option<Box<dyn Texture>>,
+    /// The material's refraction index, if applicable, read with Texture::get_value.
+    pub refraction_index: Option<Box<dyn Texture>>,
+    /// How the material bends its surface's normals, if it does.
+    pub normal_map: Option<NormalMap>,
+    /// How the material cuts holes in its surface, if it does.
+    pub opacity_mask: Option<OpacityMask>,
 }
 
 impl Material {
-    
+
     ///
     /// Returns a Material with the given arguments.
     ///
@@ -20,11 +64,210 @@ impl Material {
     /// * `reflectivity` - The material's reflectivity, if applicable.
     /// * `refraction_index` - The material's refraction index, if applicable.
     ///
-    pub fn new(texture: Box<dyn Texture>, reflectivity: Option<f64>, refraction_index: Option<f64>) -> Self {        
-        Self { 
-            texture: texture, 
+    pub fn new(texture: Box<dyn Texture>, reflectivity: Option<f64>, refraction_index: Option<f64>) -> Self {
+        Self::with_textures(
+            texture,
+            reflectivity.map(get_constant_texture),
+            refraction_index.map(get_constant_texture),
+        )
+    }
+
+    ///
+    /// Returns a Material whose parameters all vary across its surface.
+    ///
+    /// # Arguments
+    /// * `texture` - The material's texture.
+    /// * `reflectivity` - The material's reflectivity, if applicable.
+    /// * `refraction_index` - The material's refraction index, if applicable.
+    ///
+    pub fn with_textures(texture: Box<dyn Texture>, reflectivity: Option<Box<dyn Texture>>, refraction_index: Option<Box<dyn Texture>>) -> Self {
+        Self {
+            texture: texture,
             reflectivity: reflectivity,
-            refraction_index: refraction_index 
+            refraction_index: refraction_index,
+            normal_map: None,
+            opacity_mask: None,
+        }
+    }
+
+    ///
+    /// Returns the Material with the given NormalMap.
+    ///
+    /// # Arguments
+    /// * `self` - The Material.
+    /// * `normal_map` - The Material's normal_map.
+    ///
+    pub fn with_normal_map(mut self, normal_map: NormalMap) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    ///
+    /// Returns the Material with the given OpacityMask.
+    ///
+    /// # Arguments
+    /// * `self` - The Material.
+    /// * `opacity_mask` - The Material's opacity_mask.
+    ///
+    pub fn with_opacity_mask(mut self, opacity_mask: OpacityMask) -> Self {
+        self.opacity_mask = Some(opacity_mask);
+        self
+    }
+
+    ///
+    /// Returns false if the Material's opacity mask cuts the given point out
+    /// of its surface, so that the Ray should carry on past it. Hittables
+    /// check this as they intersect, before a Hit is shaded.
+    ///
+    /// # Arguments
+    /// * `&self` - The Material.
+    /// * `surface` - The point on the Hittable's surface.
+    ///
+    pub fn is_opaque(&self, surface: SurfacePoint) -> bool {
+        match &self.opacity_mask {
+            None => true,
+            Some(OpacityMask::Threshold { opacity, threshold }) => *threshold <= opacity.get_value(surface),
+            Some(OpacityMask::Stochastic(opacity)) => rand::random::<f64>() < opacity.get_value(surface),
         }
     }
-}
\ No newline at end of file
+
+    ///
+    /// Returns the outward unit normal to shade the given point with: the
+    /// surface's own normal bent by the Material's NormalMap, if it has one.
+    ///
+    /// # Arguments
+    /// * `&self` - The Material.
+    /// * `surface` - The point on the Hittable's surface.
+    ///
+    pub fn get_normal(&self, surface: SurfacePoint) -> Vector3 {
+
+        let normal = surface.normal;
+
+        let bent = match &self.normal_map {
+            None => return normal,
+
+            Some(NormalMap::Tangent(texture)) => {
+                let color = texture.get_color(surface);
+                let (tangent, bitangent) = surface.get_tangent_frame();
+                (2.0 * color.r - 1.0) * tangent + (2.0 * color.g - 1.0) * bitangent + (2.0 * color.b - 1.0) * normal
+            },
+
+            Some(NormalMap::Bump { height, strength }) => {
+
+                // Without a known dpdu, any frame will do, so long as it is
+                // used for both the surface and the height's slopes.
+                let (dpdu, dpdv) = if surface.dpdu.near_zero() || surface.dpdv.near_zero() {
+                    surface.get_tangent_frame()
+                } else {
+                    (surface.dpdu, surface.dpdv)
+                };
+
+                // The height is differenced across the footprint, so bumps
+                // smaller than a pixel are smoothed out, or across a tiny step
+                // if there is no footprint.
+                let footprint = surface.footprint;
+                let get_step = |dx: f64, dy: f64, dpd: Vector3| {
+                    let step = 0.5 * (dx.abs() + dy.abs());
+                    if 0.0 < step { step } else { 1e-3 / dpd.mag() }
+                };
+                let du = get_step(footprint.duvdx.0, footprint.duvdy.0, dpdu);
+                let dv = get_step(footprint.duvdx.1, footprint.duvdy.1, dpdv);
+
+                let center = height.get_value(surface);
+                let dhdu = strength * (height.get_value(surface.get_neighbour(du, 0.0, dpdu, dpdv)) - center) / du;
+                let dhdv = strength * (height.get_value(surface.get_neighbour(0.0, dv, dpdu, dpdv)) - center) / dv;
+
+                // The surface pushed out by the height is sloped along u and v
+                // by the height's slopes, and its normal is across both.
+                let bent = (dpdu + dhdu * normal).cross(dpdv + dhdv * normal);
+                if bent.dot(normal) < 0.0 { -bent } else { bent }
+            },
+        };
+
+        if bent.near_zero() || !bent.mag().is_finite() { normal } else { bent.unit() }
+    }
+
+    ///
+    /// Returns the Material's reflectivity at the given point, if applicable.
+    ///
+    /// # Arguments
+    /// * `&self` - The Material.
+    /// * `surface` - The point on the Hittable's surface.
+    ///
+    pub fn get_reflectivity(&self, surface: SurfacePoint) -> Option<f64> {
+        self.reflectivity.as_ref().map(|texture| texture.get_value(surface))
+    }
+
+    ///
+    /// Returns the Material's refraction index at the given point, if applicable.
+    ///
+    /// # Arguments
+    /// * `&self` - The Material.
+    /// * `surface` - The point on the Hittable's surface.
+    ///
+    pub fn get_refraction_index(&self, surface: SurfacePoint) -> Option<f64> {
+        self.refraction_index.as_ref().map(|texture| texture.get_value(surface))
+    }
+}
+
+///
+/// Returns a texture whose value is the given constant everywhere.
+///
+/// # Arguments
+/// * `value` - The texture's value.
+///
+fn get_constant_texture(value: f64) -> Box<dyn Texture> {
+    Box::new(Monochrome::new(Color::new(value, value, value)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::color::_WHITE;
+    use crate::hittable::Hittable;
+    use crate::hittable::quad::Quad;
+    use crate::ray::Ray;
+    use crate::vector3::Point3;
+
+    /// A height that rises linearly along u.
+    struct Ramp;
+
+    impl Texture for Ramp {
+        fn get_color(&self, surface: SurfacePoint) -> Color {
+            Color::new(surface.uv.0, surface.uv.0, surface.uv.0)
+        }
+    }
+
+    ///
+    /// Returns the shading normal of the Quad from (-1, -1, 0) along the given
+    /// edges, seen straight down the z axis through (0.2, -0.3).
+    ///
+    fn get_shading_normal(u: Vector3, v: Vector3, material: Material) -> Vector3 {
+        let quad = Quad::new(Point3::new(-1.0, -1.0, 0.0), u, v, material);
+        let ray = Ray::new(Point3::new(0.2, -0.3, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        quad.get_hit(ray, 0.0, f64::INFINITY).unwrap().normal
+    }
+
+    #[test]
+    fn flat_normal_map_keeps_the_geometric_normal() {
+        let (u, v) = (Vector3::new(2.0, 0.0, 1.0), Vector3::new(0.0, 2.0, 0.0));
+        let flat = Box::new(Monochrome::new(Color::new(0.5, 0.5, 1.0)));
+        let material = Material::new(Box::new(Monochrome::new(_WHITE)), None, None).with_normal_map(NormalMap::Tangent(flat));
+
+        let normal = get_shading_normal(u, v, material);
+        assert!((normal - u.cross(v).unit()).mag() < 1e-9);
+    }
+
+    #[test]
+    fn bump_ramp_tilts_the_normal_away_from_the_rise() {
+        let (u, v) = (Vector3::new(2.0, 0.0, 0.0), Vector3::new(0.0, 2.0, 0.0));
+        let material = Material::new(Box::new(Monochrome::new(_WHITE)), None, None)
+            .with_normal_map(NormalMap::Bump { height: Box::new(Ramp), strength: 1.0 });
+
+        // The surface rises by 1 over the 2 units of the u edge, so it leans
+        // back along x by half as much as it faces up.
+        let normal = get_shading_normal(u, v, material);
+        assert!((normal - Vector3::new(-1.0, 0.0, 2.0).unit()).mag() < 1e-6, "({}, {}, {})", normal.x, normal.y, normal.z);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::vector3::Vector3;
+
+use std::ops::Mul;
+
+/// Represents a 3x3 matrix, stored in row-major order.
+#[derive(Clone, Copy)]
+pub struct Matrix3 {
+    /// The matrix's entries, where m[row][col].
+    pub m: [[f64; 3]; 3],
+}
+
+impl Matrix3 {
+
+    ///
+    /// Returns a Matrix3 with the given entries.
+    ///
+    /// # Arguments
+    /// * `m` - The matrix's entries, where m[row][col].
+    ///
+    pub fn new(m: [[f64; 3]; 3]) -> Self {
+        Self { m: m }
+    }
+
+    ///
+    /// Returns the identity matrix.
+    ///
+    pub fn identity() -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ])
+    }
+
+    ///
+    /// Returns a Matrix3 with the given vectors as its columns.
+    ///
+    /// # Arguments
+    /// * `x` - The first column.
+    /// * `y` - The second column.
+    /// * `z` - The third column.
+    ///
+    pub fn from_columns(x: Vector3, y: Vector3, z: Vector3) -> Self {
+        Self::new([
+            [x.x, y.x, z.x],
+            [x.y, y.y, z.y],
+            [x.z, y.z, z.z],
+        ])
+    }
+
+    ///
+    /// Returns the given column of the matrix as a Vector3.
+    ///
+    /// # Arguments
+    /// * `&self` - The matrix.
+    /// * `col` - The index of the column, between [0, 2].
+    ///
+    pub fn get_column(&self, col: usize) -> Vector3 {
+        Vector3::new(self.m[0][col], self.m[1][col], self.m[2][col])
+    }
+
+    ///
+    /// Returns the transpose of the matrix.
+    ///
+    /// # Arguments
+    /// * `&self` - The matrix.
+    ///
+    pub fn transpose(&self) -> Self {
+        Self::from_columns(
+            Vector3::new(self.m[0][0], self.m[0][1], self.m[0][2]),
+            Vector3::new(self.m[1][0], self.m[1][1], self.m[1][2]),
+            Vector3::new(self.m[2][0], self.m[2][1], self.m[2][2]),
+        )
+    }
+
+    ///
+    /// Returns the determinant of the matrix.
+    ///
+    /// # Arguments
+    /// * `&self` - The matrix.
+    ///
+    pub fn determinant(&self) -> f64 {
+        self.get_column(0).dot(self.get_column(1).cross(self.get_column(2)))
+    }
+
+    ///
+    /// Returns the inverse of the matrix, or None if the matrix is singular.
+    ///
+    /// # Arguments
+    /// * `&self` - The matrix.
+    ///
+    pub fn inverse(&self) -> Option<Self> {
+
+        let determinant = self.determinant();
+        if !determinant.is_finite() || determinant.abs() < 1e-12 { return None; }
+
+        // The rows of the inverse are the cross products of the columns.
+        let x = self.get_column(0);
+        let y = self.get_column(1);
+        let z = self.get_column(2);
+        let inverse = Self::from_columns(y.cross(z), z.cross(x), x.cross(y)).transpose();
+
+        let mut m = inverse.m;
+        for row in m.iter_mut() {
+            for value in row.iter_mut() {
+                *value /= determinant;
+            }
+        }
+
+        Some(Self::new(m))
+    }
+
+    ///
+    /// Returns the given vector multiplied by the matrix.
+    ///
+    /// # Arguments
+    /// * `&self` - The matrix.
+    /// * `vector` - The vector to transform.
+    ///
+    pub fn transform(&self, vector: Vector3) -> Vector3 {
+        let m = &self.m;
+        Vector3::new(
+            m[0][0] * vector.x + m[0][1] * vector.y + m[0][2] * vector.z,
+            m[1][0] * vector.x + m[1][1] * vector.y + m[1][2] * vector.z,
+            m[2][0] * vector.x + m[2][1] * vector.y + m[2][2] * vector.z,
+        )
+    }
+}
+
+impl Mul for Matrix3 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        let mut m = [[0.0; 3]; 3];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = (0..3).map(|k| self.m[row][k] * rhs.m[k][col]).sum();
+            }
+        }
+        Self::new(m)
+    }
+}
+
+impl Mul<Vector3> for Matrix3 {
+    type Output = Vector3;
+    fn mul(self, rhs: Vector3) -> Vector3 {
+        self.transform(rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn assert_matrix_eq(a: Matrix3, b: Matrix3) {
+        for row in 0..3 {
+            for col in 0..3 {
+                assert!((a.m[row][col] - b.m[row][col]).abs() < 1e-9, "{:?} != {:?}", a.m, b.m);
+            }
+        }
+    }
+
+    fn get_matrix() -> Matrix3 {
+        Matrix3::new([
+            [2.0, -1.0, 0.5],
+            [0.0, 3.0, 1.0],
+            [1.0, 0.25, -2.0],
+        ])
+    }
+
+    #[test]
+    fn inverse_times_matrix_is_identity() {
+        let m = get_matrix();
+        let inverse = m.inverse().unwrap();
+        assert_matrix_eq(inverse * m, Matrix3::identity());
+        assert_matrix_eq(m * inverse, Matrix3::identity());
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        let x = Vector3::new(1.0, 2.0, 3.0);
+        let m = Matrix3::from_columns(x, 2.0 * x, Vector3::new(0.0, 1.0, 0.0));
+        assert_eq!(m.determinant(), 0.0);
+        assert!(m.inverse().is_none());
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let m = get_matrix();
+        let t = m.transpose();
+        for row in 0..3 {
+            for col in 0..3 {
+                assert_eq!(t.m[row][col], m.m[col][row]);
+            }
+        }
+        assert_matrix_eq(t.transpose(), m);
+    }
+
+    #[test]
+    fn columns_are_images_of_axes() {
+        let m = get_matrix();
+        for col in 0..3 {
+            let mut axis = [0.0; 3];
+            axis[col] = 1.0;
+            let image = m * Vector3::new(axis[0], axis[1], axis[2]);
+            let column = m.get_column(col);
+            assert_eq!((image.x, image.y, image.z), (column.x, column.y, column.z));
+        }
+    }
+
+    #[test]
+    fn determinant_of_product_is_product_of_determinants() {
+        let a = get_matrix();
+        let b = a.transpose() * a;
+        assert!((b.determinant() - a.determinant() * a.determinant()).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::matrix3::Matrix3;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use std::ops::Mul;
+
+/// Represents a 4x4 matrix, stored in row-major order.
+#[derive(Clone, Copy)]
+pub struct Matrix4 {
+    /// The matrix's entries, where m[row][col].
+    pub m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+
+    ///
+    /// Returns a Matrix4 with the given entries.
+    ///
+    /// # Arguments
+    /// * `m` - The matrix's entries, where m[row][col].
+    ///
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Self { m: m }
+    }
+
+    ///
+    /// Returns the identity matrix.
+    ///
+    pub fn identity() -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    ///
+    /// Returns a matrix that translates points by the given offset.
+    ///
+    /// # Arguments
+    /// * `offset` - The translation.
+    ///
+    pub fn translation(offset: Vector3) -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, offset.x],
+            [0.0, 1.0, 0.0, offset.y],
+            [0.0, 0.0, 1.0, offset.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    ///
+    /// Returns a matrix that scales each axis by the given factors.
+    ///
+    /// # Arguments
+    /// * `factors` - The scale along the x, y and z axes.
+    ///
+    pub fn scaling(factors: Vector3) -> Self {
+        Self::new([
+            [factors.x, 0.0, 0.0, 0.0],
+            [0.0, factors.y, 0.0, 0.0],
+            [0.0, 0.0, factors.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    ///
+    /// Returns an affine matrix made up of the given linear part and translation.
+    ///
+    /// # Arguments
+    /// * `linear` - The upper left 3x3 part of the matrix.
+    /// * `translation` - The translation in the last column of the matrix.
+    ///
+    pub fn from_matrix3(linear: Matrix3, translation: Vector3) -> Self {
+        let l = &linear.m;
+        Self::new([
+            [l[0][0], l[0][1], l[0][2], translation.x],
+            [l[1][0], l[1][1], l[1][2], translation.y],
+            [l[2][0], l[2][1], l[2][2], translation.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    ///
+    /// Returns the camera to world matrix of a camera at eye looking towards
+    /// target. The camera looks down its own negative z axis, with its x axis
+    /// pointing right and its y axis pointing up.
+    ///
+    /// # Arguments
+    /// * `eye` - The camera's location in space.
+    /// * `target` - The point in space the camera is looking to.
+    /// * `up` - The camera's vertical up direction.
+    ///
+    pub fn look_at(eye: Point3, target: Point3, up: Vector3) -> Self {
+        let w = (eye - target).unit();
+        let u = up.cross(w).unit();
+        let v = w.cross(u);
+        Self::from_matrix3(Matrix3::from_columns(u, v, w), eye)
+    }
+
+    ///
+    /// Returns a perspective projection matrix for a camera looking down its
+    /// negative z axis. Points between the near and far planes are mapped into
+    /// the cube [-1, 1] on every axis.
+    ///
+    /// # Arguments
+    /// * `vfov_degrees` - The vertical field of view in degrees.
+    /// * `aspect_ratio` - The width of the view divided by its height.
+    /// * `near` - The distance to the near clipping plane.
+    /// * `far` - The distance to the far clipping plane.
+    ///
+    pub fn perspective(vfov_degrees: f64, aspect_ratio: f64, near: f64, far: f64) -> Self {
+        let f = 1.0 / (vfov_degrees.to_radians() / 2.0).tan();
+        Self::new([
+            [f / aspect_ratio, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, (far + near) / (near - far), (2.0 * far * near) / (near - far)],
+            [0.0, 0.0, -1.0, 0.0],
+        ])
+    }
+
+    ///
+    /// Returns the upper left 3x3 part of the matrix.
+    ///
+    /// # Arguments
+    /// * `&self` - The matrix.
+    ///
+    pub fn get_matrix3(&self) -> Matrix3 {
+        let m = &self.m;
+        Matrix3::new([
+            [m[0][0], m[0][1], m[0][2]],
+            [m[1][0], m[1][1], m[1][2]],
+            [m[2][0], m[2][1], m[2][2]],
+        ])
+    }
+
+    ///
+    /// Returns the transpose of the matrix.
+    ///
+    /// # Arguments
+    /// * `&self` - The matrix.
+    ///
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = self.m[col][row];
+            }
+        }
+        Self::new(m)
+    }
+
+    ///
+    /// Returns the inverse of the matrix, or None if the matrix is singular.
+    /// Affine matrices are inverted through Matrix3::inverse of their linear
+    /// part. Anything else, such as a perspective projection, is inverted by
+    /// Gauss-Jordan elimination.
+    ///
+    /// # Arguments
+    /// * `&self` - The matrix.
+    ///
+    pub fn inverse(&self) -> Option<Self> {
+
+        if self.m[3] == [0.0, 0.0, 0.0, 1.0] {
+            let linear = self.get_matrix3().inverse()?;
+            let translation = Vector3::new(self.m[0][3], self.m[1][3], self.m[2][3]);
+            return Some(Self::from_matrix3(linear, -(linear * translation)));
+        }
+
+        // Reduce the matrix to the identity, doing the same row operations to
+        // the identity to turn it into the inverse.
+        let mut m = self.m;
+        let mut inverse = Self::identity().m;
+        for col in 0..4 {
+
+            // Pivot on the largest value left in the column, for stability.
+            let pivot = (col..4).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs())).unwrap();
+            if !m[pivot][col].is_finite() || m[pivot][col].abs() < 1e-12 { return None; }
+            m.swap(col, pivot);
+            inverse.swap(col, pivot);
+
+            let scale = 1.0 / m[col][col];
+            m[col] = m[col].map(|value| value * scale);
+            inverse[col] = inverse[col].map(|value| value * scale);
+
+            for row in 0..4 {
+                if row == col { continue; }
+                let factor = m[row][col];
+                m[row] = std::array::from_fn(|k| m[row][k] - factor * m[col][k]);
+                inverse[row] = std::array::from_fn(|k| inverse[row][k] - factor * inverse[col][k]);
+            }
+        }
+
+        Some(Self::new(inverse))
+    }
+
+    ///
+    /// Returns the given point transformed by the matrix. The point is treated
+    /// as having a w component of 1, so it is affected by translation.
+    ///
+    /// # Arguments
+    /// * `&self` - The matrix.
+    /// * `point` - The point to transform.
+    ///
+    pub fn transform_point(&self, point: Point3) -> Point3 {
+        let m = &self.m;
+        let x = m[0][0] * point.x + m[0][1] * point.y + m[0][2] * point.z + m[0][3];
+        let y = m[1][0] * point.x + m[1][1] * point.y + m[1][2] * point.z + m[1][3];
+        let z = m[2][0] * point.x + m[2][1] * point.y + m[2][2] * point.z + m[2][3];
+        let w = m[3][0] * point.x + m[3][1] * point.y + m[3][2] * point.z + m[3][3];
+        if w == 1.0 { Point3::new(x, y, z) } else { Point3::new(x, y, z) / w }
+    }
+
+    ///
+    /// Returns the given vector transformed by the matrix. The vector is treated
+    /// as having a w component of 0, so it is not affected by translation.
+    ///
+    /// # Arguments
+    /// * `&self` - The matrix.
+    /// * `vector` - The vector to transform.
+    ///
+    pub fn transform_vector(&self, vector: Vector3) -> Vector3 {
+        let m = &self.m;
+        Vector3::new(
+            m[0][0] * vector.x + m[0][1] * vector.y + m[0][2] * vector.z,
+            m[1][0] * vector.x + m[1][1] * vector.y + m[1][2] * vector.z,
+            m[2][0] * vector.x + m[2][1] * vector.y + m[2][2] * vector.z,
+        )
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[row][k] * rhs.m[k][col]).sum();
+            }
+        }
+        Self::new(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn assert_point_eq(a: Point3, b: Point3) {
+        assert!((a - b).mag() < 1e-9, "({}, {}, {}) != ({}, {}, {})", a.x, a.y, a.z, b.x, b.y, b.z);
+    }
+
+    fn assert_matrix_eq(a: Matrix4, b: Matrix4) {
+        for row in 0..4 {
+            for col in 0..4 {
+                assert!((a.m[row][col] - b.m[row][col]).abs() < 1e-9, "m[{}][{}]: {} != {}", row, col, a.m[row][col], b.m[row][col]);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_times_matrix_is_identity() {
+        let affine = Matrix4::translation(Vector3::new(1.0, -2.0, 3.0))
+            * Matrix4::from_matrix3(Matrix3::new([[2.0, 1.0, 0.0], [0.0, 1.0, -1.0], [1.0, 0.0, 3.0]]), Vector3::new(0.0, 0.0, 0.0))
+            * Matrix4::scaling(Vector3::new(0.5, 4.0, 2.0));
+        let projective = Matrix4::perspective(60.0, 1.5, 0.1, 100.0) * affine;
+        for m in [affine, projective] {
+            let inverse = m.inverse().unwrap();
+            assert_matrix_eq(inverse * m, Matrix4::identity());
+            assert_matrix_eq(m * inverse, Matrix4::identity());
+        }
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        assert!(Matrix4::scaling(Vector3::new(1.0, 0.0, 1.0)).inverse().is_none());
+        let mut m = Matrix4::perspective(60.0, 1.5, 0.1, 100.0).m;
+        m[3] = m[2];
+        assert!(Matrix4::new(m).inverse().is_none());
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let m = Matrix4::look_at(Point3::new(1.0, 2.0, 3.0), Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        let t = m.transpose();
+        for row in 0..4 {
+            for col in 0..4 {
+                assert_eq!(t.m[row][col], m.m[col][row]);
+            }
+        }
+    }
+
+    #[test]
+    fn product_applies_right_matrix_first() {
+        let translate = Matrix4::translation(Vector3::new(1.0, 0.0, 0.0));
+        let scale = Matrix4::scaling(Vector3::new(2.0, 2.0, 2.0));
+        let point = Point3::new(1.0, 1.0, 1.0);
+        assert_point_eq((translate * scale).transform_point(point), Point3::new(3.0, 2.0, 2.0));
+        assert_point_eq((scale * translate).transform_point(point), Point3::new(4.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn vectors_ignore_translation() {
+        let m = Matrix4::translation(Vector3::new(5.0, -1.0, 2.0));
+        let vector = m.transform_vector(Vector3::new(1.0, 2.0, 3.0));
+        assert_point_eq(vector, Vector3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn look_at_points_negative_z_at_target() {
+        let eye = Point3::new(1.0, 2.0, 3.0);
+        let target = Point3::new(-2.0, 0.0, 1.0);
+        let m = Matrix4::look_at(eye, target, Vector3::new(0.0, 1.0, 0.0));
+        assert_point_eq(m.transform_point(Point3::new(0.0, 0.0, 0.0)), eye);
+        let forward = m.transform_vector(Vector3::new(0.0, 0.0, -1.0));
+        assert_point_eq(forward, (target - eye).unit());
+        // The camera's x axis is level, and its y axis leans up.
+        assert!(m.transform_vector(Vector3::new(1.0, 0.0, 0.0)).y.abs() < 1e-9);
+        assert!(0.0 < m.transform_vector(Vector3::new(0.0, 1.0, 0.0)).y);
+    }
+
+    #[test]
+    fn perspective_maps_view_frustum_to_cube() {
+        let (near, far) = (0.5, 10.0);
+        let m = Matrix4::perspective(90.0, 2.0, near, far);
+        assert_point_eq(m.transform_point(Point3::new(0.0, 0.0, -near)), Point3::new(0.0, 0.0, -1.0));
+        assert_point_eq(m.transform_point(Point3::new(0.0, 0.0, -far)), Point3::new(0.0, 0.0, 1.0));
+        // A 90 degree field of view reaches as far up as it is deep, and the
+        // aspect ratio doubles how far it reaches across.
+        assert_point_eq(m.transform_point(Point3::new(8.0, 4.0, -4.0)), Point3::new(1.0, 1.0, m.transform_point(Point3::new(0.0, 0.0, -4.0)).z));
+    }
+}
@@ -0,0 +1,188 @@
+use std::f64::consts::PI;
+
+///
+/// Returns the real roots of a*x^2 + b*x + c = 0 in ascending order. Uses the
+/// numerically stable form of the quadratic formula so that a root close to
+/// zero does not lose its precision to cancellation.
+///
+/// # Arguments
+/// * `a` - The quadratic coefficient.
+/// * `b` - The linear coefficient.
+/// * `c` - The constant coefficient.
+///
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+
+    if a == 0.0 {
+        if b == 0.0 { return vec![]; }
+        return vec![-c / b];
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 { return vec![]; }
+
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    if q == 0.0 { return vec![0.0, 0.0]; }
+
+    let root1 = q / a;
+    let root2 = c / q;
+    if root1 < root2 { vec![root1, root2] } else { vec![root2, root1] }
+}
+
+///
+/// Returns the real roots of x^3 + a*x^2 + b*x + c = 0 in ascending order.
+/// Uses the trigonometric method when there are three real roots and Cardano's
+/// method otherwise.
+///
+/// # Arguments
+/// * `a` - The quadratic coefficient.
+/// * `b` - The linear coefficient.
+/// * `c` - The constant coefficient.
+///
+pub fn solve_cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+
+    let q = (a * a - 3.0 * b) / 9.0;
+    let r = (2.0 * a * a * a - 9.0 * a * b + 27.0 * c) / 54.0;
+    let q3 = q * q * q;
+
+    if r * r < q3 {
+        let theta = (r / q3.sqrt()).clamp(-1.0, 1.0).acos();
+        let sqrt_q = q.sqrt();
+        let mut roots = vec![
+            -2.0 * sqrt_q * (theta / 3.0).cos() - a / 3.0,
+            -2.0 * sqrt_q * ((theta + 2.0 * PI) / 3.0).cos() - a / 3.0,
+            -2.0 * sqrt_q * ((theta - 2.0 * PI) / 3.0).cos() - a / 3.0,
+        ];
+        roots.sort_by(f64::total_cmp);
+        roots
+    } else {
+        let big_a = -r.signum() * (r.abs() + (r * r - q3).sqrt()).cbrt();
+        let big_b = if big_a == 0.0 { 0.0 } else { q / big_a };
+        vec![(big_a + big_b) - a / 3.0]
+    }
+}
+
+///
+/// Returns the real roots of x^4 + a*x^3 + b*x^2 + c*x + d = 0 in ascending
+/// order. The roots are found with Ferrari's method and then polished with a
+/// few Newton-Raphson iterations against the original polynomial, which keeps
+/// them accurate even when the closed form loses precision.
+///
+/// # Arguments
+/// * `a` - The cubic coefficient.
+/// * `b` - The quadratic coefficient.
+/// * `c` - The linear coefficient.
+/// * `d` - The constant coefficient.
+///
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+
+    // Substitute x = y - a/4 to get y^4 + p*y^2 + q*y + r = 0.
+    let a2 = a * a;
+    let p = b - 3.0 * a2 / 8.0;
+    let q = c - a * b / 2.0 + a2 * a / 8.0;
+    let r = d - a * c / 4.0 + a2 * b / 16.0 - 3.0 * a2 * a2 / 256.0;
+
+    let mut depressed_roots: Vec<f64> = vec![];
+
+    if q.abs() < 1e-12 {
+
+        // Biquadratic, solve for z = y^2.
+        for z in solve_quadratic(1.0, p, r) {
+            if z < 0.0 { continue; }
+            let y = z.sqrt();
+            depressed_roots.push(y);
+            depressed_roots.push(-y);
+        }
+
+    } else {
+
+        // The resolvent cubic always has a positive root when q != 0.
+        let resolvent = solve_cubic(p, p * p / 4.0 - r, -q * q / 8.0);
+        let m = resolvent.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if m <= 0.0 { return vec![]; }
+
+        let s = (2.0 * m).sqrt();
+        let offset = q / (2.0 * s);
+        depressed_roots.extend(solve_quadratic(1.0, s, p / 2.0 + m - offset));
+        depressed_roots.extend(solve_quadratic(1.0, -s, p / 2.0 + m + offset));
+    }
+
+    let mut roots: Vec<f64> = depressed_roots.into_iter().map(|y| {
+        let mut x = y - a / 4.0;
+        for _ in 0..2 {
+            let f = (((x + a) * x + b) * x + c) * x + d;
+            let df = ((4.0 * x + 3.0 * a) * x + 2.0 * b) * x + c;
+            if df == 0.0 { break; }
+            x -= f / df;
+        }
+        x
+    }).collect();
+
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn assert_roots_eq(roots: Vec<f64>, expected: &[f64], tolerance: f64) {
+        assert_eq!(roots.len(), expected.len(), "{:?} != {:?}", roots, expected);
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < tolerance, "{:?} != {:?}", roots, expected);
+        }
+    }
+
+    #[test]
+    fn quadratic_roots_are_ascending() {
+        assert_roots_eq(solve_quadratic(1.0, -4.0, 3.0), &[1.0, 3.0], 1e-12);
+        assert_roots_eq(solve_quadratic(-2.0, 0.0, 8.0), &[-2.0, 2.0], 1e-12);
+        assert_roots_eq(solve_quadratic(1.0, 0.0, 1.0), &[], 0.0);
+    }
+
+    #[test]
+    fn quadratic_with_vanishing_leading_coefficient_keeps_its_finite_root() {
+        // A ray running along a cone's side makes the quadratic nearly linear.
+        // The finite root has to stay accurate while the other runs away.
+        let roots = solve_quadratic(1e-18, 2.0, -4.0);
+        assert_eq!(roots.len(), 2);
+        assert!((roots[1] - 2.0).abs() < 1e-12);
+        assert_roots_eq(solve_quadratic(0.0, 2.0, -4.0), &[2.0], 1e-12);
+        assert_roots_eq(solve_quadratic(0.0, 0.0, 1.0), &[], 0.0);
+    }
+
+    #[test]
+    fn cubic_finds_three_or_one_real_roots() {
+        // (x + 2)(x - 1)(x - 3)
+        assert_roots_eq(solve_cubic(-2.0, -5.0, 6.0), &[-2.0, 1.0, 3.0], 1e-9);
+        // (x - 1)(x^2 + x + 1)
+        assert_roots_eq(solve_cubic(0.0, 0.0, -1.0), &[1.0], 1e-9);
+    }
+
+    #[test]
+    fn quartic_finds_distinct_roots() {
+        // (x + 3)(x + 1)(x - 1)(x - 2)
+        assert_roots_eq(solve_quartic(1.0, -7.0, -1.0, 6.0), &[-3.0, -1.0, 1.0, 2.0], 1e-9);
+        // (x^2 + 1)(x^2 + 4)
+        assert_roots_eq(solve_quartic(0.0, 5.0, 0.0, 4.0), &[], 0.0);
+    }
+
+    #[test]
+    fn biquadratic_quartic_finds_symmetric_roots() {
+        // (x^2 - 1)(x^2 - 4)
+        assert_roots_eq(solve_quartic(0.0, -5.0, 0.0, 4.0), &[-2.0, -1.0, 1.0, 2.0], 1e-9);
+        // (x^2 - 1)(x^2 + 4)
+        assert_roots_eq(solve_quartic(0.0, 3.0, 0.0, -4.0), &[-1.0, 1.0], 1e-9);
+    }
+
+    #[test]
+    fn quartic_finds_repeated_roots() {
+        // (x^2 - 1)^2, a ray grazing a torus at two points.
+        assert_roots_eq(solve_quartic(0.0, -2.0, 0.0, 1.0), &[-1.0, -1.0, 1.0, 1.0], 1e-6);
+        // (x + 2)(x - 1)^2(x - 3)
+        let roots = solve_quartic(-3.0, -3.0, 11.0, -6.0);
+        for expected in [-2.0, 1.0, 3.0] {
+            assert!(roots.iter().any(|root| (root - expected).abs() < 1e-6), "{:?} is missing {}", roots, expected);
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use crate::matrix3::Matrix3;
+use crate::vector3::Vector3;
+
+use std::ops::Mul;
+
+/// Represents a quaternion. Unit quaternions are used to represent rotations.
+#[derive(Clone, Copy)]
+pub struct Quaternion {
+    /// The quaternion's scalar part.
+    pub w: f64,
+    /// The quaternion's i component.
+    pub x: f64,
+    /// The quaternion's j component.
+    pub y: f64,
+    /// The quaternion's k component.
+    pub z: f64,
+}
+
+impl Quaternion {
+
+    ///
+    /// Returns a Quaternion with the given arguments.
+    ///
+    /// # Arguments
+    /// * `w` - The quaternion's scalar part.
+    /// * `x` - The quaternion's i component.
+    /// * `y` - The quaternion's j component.
+    /// * `z` - The quaternion's k component.
+    ///
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w: w, x: x, y: y, z: z, }
+    }
+
+    ///
+    /// Returns the quaternion representing no rotation.
+    ///
+    pub fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    ///
+    /// Returns a unit quaternion that rotates counter-clockwise around the
+    /// given axis.
+    ///
+    /// # Arguments
+    /// * `axis` - The axis of rotation. Does not need to be a unit vector.
+    /// * `degrees` - The angle of rotation in degrees.
+    ///
+    pub fn from_axis_angle(axis: Vector3, degrees: f64) -> Self {
+        let a = axis.unit();
+        let (sin, cos) = (degrees.to_radians() / 2.0).sin_cos();
+        Self::new(cos, a.x * sin, a.y * sin, a.z * sin)
+    }
+
+    ///
+    /// Returns a unit quaternion from the given Euler angles. The rotations are
+    /// applied around the x axis first, then the y axis, then the z axis.
+    ///
+    /// # Arguments
+    /// * `x_degrees` - The rotation around the x axis in degrees.
+    /// * `y_degrees` - The rotation around the y axis in degrees.
+    /// * `z_degrees` - The rotation around the z axis in degrees.
+    ///
+    pub fn from_euler(x_degrees: f64, y_degrees: f64, z_degrees: f64) -> Self {
+        let qx = Self::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), x_degrees);
+        let qy = Self::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), y_degrees);
+        let qz = Self::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), z_degrees);
+        qz * qy * qx
+    }
+
+    ///
+    /// Returns the (axis, degrees) rotation represented by the unit quaternion.
+    /// If the quaternion is the identity, the axis is arbitrarily the x axis.
+    /// Nothing reads rotations back out yet, so this is only used by tests.
+    ///
+    /// # Arguments
+    /// * `self` - The quaternion.
+    ///
+    #[allow(dead_code)]
+    pub fn to_axis_angle(self) -> (Vector3, f64) {
+        let q = self.unit();
+        let sin = (1.0 - q.w * q.w).max(0.0).sqrt();
+        let degrees = (2.0 * q.w.clamp(-1.0, 1.0).acos()).to_degrees();
+        if sin < 1e-12 { return (Vector3::new(1.0, 0.0, 0.0), 0.0); }
+        (Vector3::new(q.x, q.y, q.z) / sin, degrees)
+    }
+
+    ///
+    /// Returns the (x, y, z) Euler angles in degrees of the unit quaternion, in
+    /// the same order used by from_euler. Only used by tests, like
+    /// to_axis_angle.
+    ///
+    /// # Arguments
+    /// * `self` - The quaternion.
+    ///
+    #[allow(dead_code)]
+    pub fn to_euler(self) -> (f64, f64, f64) {
+        let q = self.unit();
+        let x = (2.0 * (q.w * q.x + q.y * q.z)).atan2(1.0 - 2.0 * (q.x * q.x + q.y * q.y));
+        let y = (2.0 * (q.w * q.y - q.z * q.x)).clamp(-1.0, 1.0).asin();
+        let z = (2.0 * (q.w * q.z + q.x * q.y)).atan2(1.0 - 2.0 * (q.y * q.y + q.z * q.z));
+        (x.to_degrees(), y.to_degrees(), z.to_degrees())
+    }
+
+    ///
+    /// Returns the quaternion's dot product with another quaternion.
+    ///
+    /// # Arguments
+    /// * `&self` - The quaternion on the left side of the operation.
+    /// * `rhs` - The quaternion on the right side of the operation.
+    ///
+    pub fn dot(&self, rhs: Self) -> f64 {
+        self.w * rhs.w + self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    ///
+    /// Returns the quaternion's magnitude.
+    ///
+    /// # Arguments
+    /// * `&self` - The quaternion.
+    ///
+    pub fn mag(&self) -> f64 {
+        self.dot(*self).sqrt()
+    }
+
+    ///
+    /// Returns the quaternion scaled to a magnitude of one.
+    ///
+    /// # Arguments
+    /// * `&self` - The quaternion.
+    ///
+    pub fn unit(&self) -> Self {
+        let mag = self.mag();
+        Self::new(self.w / mag, self.x / mag, self.y / mag, self.z / mag)
+    }
+
+    ///
+    /// Returns the rotation matrix of the unit quaternion.
+    ///
+    /// # Arguments
+    /// * `self` - The quaternion.
+    ///
+    pub fn to_matrix3(self) -> Matrix3 {
+        let q = self.unit();
+        let (w, x, y, z) = (q.w, q.x, q.y, q.z);
+        Matrix3::new([
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+            [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)],
+            [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)],
+        ])
+    }
+
+    ///
+    /// Returns the spherical linear interpolation between two unit quaternions.
+    /// Always interpolates along the shorter arc.
+    ///
+    /// # Arguments
+    /// * `&self` - The rotation at t = 0.
+    /// * `rhs` - The rotation at t = 1.
+    /// * `t` - How far to interpolate, between [0, 1].
+    ///
+    pub fn slerp(&self, rhs: Self, t: f64) -> Self {
+
+        let a = self.unit();
+        let mut b = rhs.unit();
+        let mut cos = a.dot(b);
+
+        if cos < 0.0 {
+            b = Self::new(-b.w, -b.x, -b.y, -b.z);
+            cos = -cos;
+        }
+
+        // Fall back to a normalized linear interpolation when the quaternions
+        // are almost parallel, where the sine below approaches zero.
+        let (weight_a, weight_b) = if 0.9995 < cos {
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+
+        Self::new(
+            weight_a * a.w + weight_b * b.w,
+            weight_a * a.x + weight_b * b.x,
+            weight_a * a.y + weight_b * b.y,
+            weight_a * a.z + weight_b * b.z,
+        ).unit()
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            w: self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            x: self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            y: self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            z: self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn assert_vector_eq(a: Vector3, b: Vector3) {
+        assert!((a - b).mag() < 1e-9, "({}, {}, {}) != ({}, {}, {})", a.x, a.y, a.z, b.x, b.y, b.z);
+    }
+
+    fn assert_same_rotation(a: Quaternion, b: Quaternion) {
+        // q and -q are the same rotation.
+        assert!(1.0 - a.unit().dot(b.unit()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn axis_angle_round_trips() {
+        let axis = Vector3::new(1.0, -2.0, 0.5).unit();
+        let (round_axis, degrees) = Quaternion::from_axis_angle(axis, 73.0).to_axis_angle();
+        assert_vector_eq(round_axis, axis);
+        assert!((degrees - 73.0).abs() < 1e-9);
+        assert_eq!(Quaternion::identity().to_axis_angle().1, 0.0);
+    }
+
+    #[test]
+    fn euler_round_trips() {
+        let (x, y, z) = Quaternion::from_euler(30.0, -45.0, 120.0).to_euler();
+        assert!((x - 30.0).abs() < 1e-9);
+        assert!((y + 45.0).abs() < 1e-9);
+        assert!((z - 120.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn euler_applies_x_then_y_then_z() {
+        let q = Quaternion::from_euler(90.0, 90.0, 0.0);
+        // x takes y to z, then y takes z to x.
+        assert_vector_eq(q.to_matrix3() * Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn rotates_counter_clockwise() {
+        let q = Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), 90.0);
+        assert_vector_eq(q.to_matrix3() * Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn product_applies_right_rotation_first() {
+        let a = Quaternion::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), 90.0);
+        let b = Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), 90.0);
+        let v = Vector3::new(0.0, 0.0, 1.0);
+        assert_vector_eq((a * b).to_matrix3() * v, a.to_matrix3() * (b.to_matrix3() * v));
+    }
+
+    #[test]
+    fn slerp_hits_endpoints_and_midpoint() {
+        let axis = Vector3::new(0.0, 1.0, 0.0);
+        let a = Quaternion::from_axis_angle(axis, 10.0);
+        let b = Quaternion::from_axis_angle(axis, 130.0);
+        assert_same_rotation(a.slerp(b, 0.0), a);
+        assert_same_rotation(a.slerp(b, 1.0), b);
+        assert_same_rotation(a.slerp(b, 0.5), Quaternion::from_axis_angle(axis, 70.0));
+    }
+
+    #[test]
+    fn slerp_takes_shorter_arc() {
+        let axis = Vector3::new(0.0, 0.0, 1.0);
+        let a = Quaternion::from_axis_angle(axis, 170.0);
+        let b = Quaternion::from_axis_angle(axis, -170.0);
+        assert_same_rotation(a.slerp(b, 0.5), Quaternion::from_axis_angle(axis, 180.0));
+    }
+}
@@ -1,6 +1,30 @@
+use crate::transform::Transform;
 use crate::vector3::Vector3;
 use crate::vector3::Point3;
 
+/// The number of Rays in a RayPacket.
+pub const PACKET_SIZE: usize = 4;
+
+/// A few Rays that start close together and point in nearly the same
+/// direction, like the Rays through a 2x2 block of pixels. Tracing them
+/// together lets them share the work of walking an acceleration structure.
+pub type RayPacket = [Ray; PACKET_SIZE];
+
+/// The Rays through the neighbouring pixels of a camera Ray, one pixel over
+/// and one pixel up. How far apart they spread tells a texture how much of a
+/// surface one pixel covers, so that it can be filtered instead of aliasing.
+#[derive(Clone, Copy)]
+pub struct RayDifferentials {
+    /// The origin of the Ray one pixel over.
+    pub x_origin: Point3,
+    /// The direction of the Ray one pixel over.
+    pub x_direction: Vector3,
+    /// The origin of the Ray one pixel up.
+    pub y_origin: Point3,
+    /// The direction of the Ray one pixel up.
+    pub y_direction: Vector3,
+}
+
 /// Represents a ray in 3D space.
 #[derive(Clone, Copy)]
 pub struct Ray {
@@ -8,6 +32,11 @@ pub struct Ray {
     pub origin: Point3,
     /// The ray's direction.
     pub direction: Vector3,
+    /// The ray's differentials, if it has them. Only camera Rays do.
+    pub differentials: Option<RayDifferentials>,
+    /// Takes the ray's space into world space, if an Instance has moved the
+    /// ray into its object space, so that Hittables can shade in world space.
+    pub object_to_world: Option<Transform>,
 }
 
 impl Ray {
@@ -23,6 +52,25 @@ impl Ray {
         Self {
             origin: origin,
             direction: direction,
+            differentials: None,
+            object_to_world: None,
+        }
+    }
+
+    ///
+    /// Returns a Ray with the given arguments and differentials.
+    ///
+    /// # Arguments
+    /// * `origin` - The ray's origin.
+    /// * `direction` - The ray's direction.
+    /// * `differentials` - The ray's differentials.
+    ///
+    pub fn with_differentials(origin: Point3, direction: Vector3, differentials: RayDifferentials) -> Self {
+        Self {
+            origin: origin,
+            direction: direction,
+            differentials: Some(differentials),
+            object_to_world: None,
         }
     }
 
@@ -36,4 +84,30 @@ impl Ray {
     pub fn get_point(&self, distance: f64) -> Point3 {
         self.origin + (distance * self.direction)
     }
-}
\ No newline at end of file
+
+    ///
+    /// Returns how far the point on a surface moves when going one pixel over
+    /// and one pixel up, or None if the Ray has no differentials. The
+    /// neighbouring Rays are intersected with the plane touching the surface
+    /// at the point, which is close enough over the width of a pixel.
+    ///
+    /// # Arguments
+    /// * `&self` - The Ray that hit the surface.
+    /// * `point` - The point the Ray hit.
+    /// * `normal` - The surface's normal at the point. Either side will do.
+    ///
+    pub fn get_footprint(&self, point: Point3, normal: Vector3) -> Option<(Vector3, Vector3)> {
+
+        let differentials = self.differentials?;
+        let get_offset = |origin: Point3, direction: Vector3| {
+            let denominator = normal.dot(direction);
+            if denominator.abs() < 1e-12 { return None; }
+            let distance = normal.dot(point - origin) / denominator;
+            Some(origin + distance * direction - point)
+        };
+
+        let dpdx = get_offset(differentials.x_origin, differentials.x_direction)?;
+        let dpdy = get_offset(differentials.y_origin, differentials.y_direction)?;
+        Some((dpdx, dpdy))
+    }
+}
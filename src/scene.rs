@@ -1,53 +1,212 @@
+use crate::camera::Camera;
 use crate::color::*;
 use crate::hit::Hit;
-use crate::hittable::bvh_node::BvhNode;
-use crate::hittable::bvh_node::construct_bvh_root;
+use crate::hittable::accelerator::Accelerator;
+use crate::hittable::accelerator::AcceleratorKind;
+use crate::hittable::bvh_node::DEFAULT_MAX_LEAF_SIZE;
+use crate::hittable::bvh_node::MAX_REFIT_COST_GROWTH;
 use crate::hittable::Hittable;
+use crate::hittable::axis_aligned_box::AxisAlignedBox;
+use crate::hittable::capsule::Capsule;
+use crate::hittable::cone::Cone;
+use crate::hittable::csg::Csg;
+use crate::hittable::csg::CsgOperation;
+use crate::hittable::cylinder::Cylinder;
+use crate::hittable::disk::Disk;
+use crate::hittable::heightfield::Heightfield;
+use crate::hittable::instance::Instance;
+use crate::hittable::mesh::Mesh;
+use crate::hittable::mesh::ObjData;
+use crate::hittable::mesh::displace;
+use crate::hittable::mesh::get_smooth_normals;
+use crate::hittable::aabb::AABB;
+use crate::hittable::quad::Quad;
+use crate::hittable::sdf_shape::SdfShape;
 use crate::hittable::sphere::Sphere;
+use crate::hittable::top_level_bvh::TopLevelBvh;
+use crate::hittable::torus::Torus;
 use crate::material::Material;
+use crate::material::NormalMap;
+use crate::material::OpacityMask;
+use crate::quaternion::Quaternion;
+use crate::texture::adjust::HueSaturation;
+use crate::texture::adjust::Invert;
+use crate::texture::adjust::Levels;
+use crate::texture::bricks::Bricks;
+use crate::texture::checkered::Checkered;
+use crate::texture::color_ramp::ColorRamp;
+use crate::texture::combine::Add;
+use crate::texture::combine::Mix;
+use crate::texture::combine::Multiply;
+use crate::texture::gradient::Gradient;
+use crate::texture::image_texture::Filter;
+use crate::texture::image_texture::ImageTexture;
+use crate::texture::image_texture::WrapMode;
+use crate::texture::marble::Marble;
 use crate::texture::monochrome::Monochrome;
+use crate::texture::noisy::Noisy;
+use crate::texture::projection::CameraProjection;
+use crate::texture::projection::Projection;
+use crate::texture::projection::ProjectionKind;
+use crate::texture::solid_checkered::SolidCheckered;
+use crate::texture::solid_noise::SolidNoise;
+use crate::texture::Space;
+use crate::texture::Texture;
+use crate::texture::triplanar::Triplanar;
+use crate::texture::warp::DomainWarp;
+use crate::texture::warp::UvTransform;
+use crate::texture::wood::Wood;
+use crate::texture::worley::Worley;
+use crate::texture::worley::WorleyMode;
+use crate::transform::Transform;
 use crate::ray::Ray;
+use crate::sdf;
 use crate::vector3::Point3;
+use crate::vector3::Vector3;
 
+use noise::NoiseFn;
+use noise::Perlin;
 use rand::Rng;
+use std::cell::RefCell;
+use std::f64::consts::PI;
 use std::rc::Rc;
 
+/// Once more than this many Hittables have been pushed into a Scene since its
+/// Accelerator was last built, the Accelerator is rebuilt. Until then they are
+/// checked one by one alongside the Accelerator.
+const MAX_PENDING: usize = 16;
+
+/// Identifies a Hittable in a Scene, so that it can be removed later.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct HittableHandle(usize);
+
 /// Represents everything that could be seen in a rendered image.
 pub struct Scene {
-    /// All hittable objects within a scene.
-    hittables: Vec<Rc<dyn Hittable>>,
-    /// The root node of this scene's bounding volume hierarchy.
-    bvh_root: BvhNode,
+    /// All hittable objects within a scene, indexed by HittableHandle. Removed
+    /// hittables leave a None behind so that other handles stay valid.
+    hittables: Vec<Option<Rc<dyn Hittable>>>,
+    /// The kind of Accelerator the Scene is built around.
+    accelerator_kind: AcceleratorKind,
+    /// The most hittables a leaf of a BVH may hold.
+    max_leaf_size: usize,
+    /// This scene's Accelerator. It is behind a RefCell so that it can be
+    /// rebuilt lazily, the first time the Scene is intersected after a change.
+    accelerator: RefCell<SceneAccelerator>,
+}
+
+/// The Accelerator of a Scene.
+struct SceneAccelerator {
+    /// The Accelerator, or None if the Scene was empty when it was built.
+    root: Option<Box<dyn Accelerator>>,
+    /// The hittables pushed into the Scene since the Accelerator was last
+    /// built, with their handles.
+    pending: Vec<(HittableHandle, Rc<dyn Hittable>)>,
+    /// The Accelerator's SAH cost when it was last built.
+    built_cost: f64,
+    /// If the Accelerator needs to be rebuilt before it is used again.
+    is_stale: bool,
 }
 
 impl Scene {
 
     ///
-    /// Returns a Scene with the given arguments.
+    /// Adds a Rc<dyn Hittable> to the Scene's hittables field and returns its
+    /// handle. The Accelerator is not rebuilt right away. Instead, the hittable is
+    /// checked on its own until enough hittables have been pushed to make a
+    /// rebuild worthwhile.
     ///
     /// # Arguments
-    /// * `hittables` - All hittable objects within a scene.
+    /// * `&mut self` - The Scene.
+    /// * `hittable` - The hittable to add.
     ///
-    pub fn new(initial_hittable: Rc<dyn Hittable>) -> Self {
+    pub fn push(&mut self, hittable: Rc<dyn Hittable>) -> HittableHandle {
+        let handle = HittableHandle(self.hittables.len());
+        self.hittables.push(Some(Rc::clone(&hittable)));
+        let accelerator = self.accelerator.get_mut();
+        accelerator.pending.push((handle, hittable));
+        if MAX_PENDING < accelerator.pending.len() { accelerator.is_stale = true; }
+        handle
+    }
 
-        let mut hittables = vec![initial_hittable];
-        Self {
-            bvh_root: construct_bvh_root(&mut hittables, 0, 1),
-            hittables: hittables,
+    ///
+    /// Removes a hittable from the Scene and returns it, or returns None if it
+    /// was already removed. A hittable that is still pending is simply dropped
+    /// from the pending list. Otherwise it is in the Accelerator, which is
+    /// rebuilt the next time it is used.
+    ///
+    /// # Arguments
+    /// * `&mut self` - The Scene.
+    /// * `handle` - The handle push returned for the hittable.
+    ///
+    pub fn remove(&mut self, handle: HittableHandle) -> Option<Rc<dyn Hittable>> {
+        let hittable = self.hittables.get_mut(handle.0)?.take()?;
+        let accelerator = self.accelerator.get_mut();
+        match accelerator.pending.iter().position(|(pending, _)| *pending == handle) {
+            Some(i) => { accelerator.pending.swap_remove(i); },
+            None => accelerator.is_stale = true,
         }
+        Some(hittable)
+    }
+
+    ///
+    /// Returns the hittable with the given handle, or None if it was removed.
+    ///
+    /// # Arguments
+    /// * `&self` - The Scene.
+    /// * `handle` - The handle push returned for the hittable.
+    ///
+    pub fn get(&self, handle: HittableHandle) -> Option<&Rc<dyn Hittable>> {
+        self.hittables.get(handle.0)?.as_ref()
+    }
+
+    ///
+    /// Returns every hittable in the Scene.
+    ///
+    /// # Arguments
+    /// * `&self` - The Scene.
+    ///
+    pub fn get_hittables(&self) -> Vec<Rc<dyn Hittable>> {
+        self.hittables.iter().flatten().cloned().collect()
     }
 
     ///
-    /// Adds a Rc<dyn Hittable> to the Scene's hittables field.
+    /// Updates the Scene's Accelerator after hittables in it have moved, such
+    /// as Instances whose transformation was changed for the next frame of an
+    /// animation. A BVH is refit, which is much faster than rebuilding it but
+    /// makes it slower to trace as hittables drift away from where they were
+    /// when it was built. Once its SAH cost has grown past
+    /// MAX_REFIT_COST_GROWTH times what it was, it is rebuilt the next time it
+    /// is used. Accelerators that can't be refit are always rebuilt.
     ///
     /// # Arguments
     /// * `&mut self` - The Scene.
-    /// * `hittable` - The hittable to add.
     ///
-    pub fn push(&mut self, hittable: Rc<dyn Hittable>) {
-        self.hittables.push(hittable);
-        let length = self.hittables.len();
-        self.bvh_root = construct_bvh_root(&mut self.hittables, 0, length);
+    pub fn refit(&mut self) {
+        let accelerator = self.accelerator.get_mut();
+        if accelerator.is_stale { return; }
+        if let Some(root) = &accelerator.root {
+            if !root.can_refit() {
+                accelerator.is_stale = true;
+                return;
+            }
+            root.refit();
+            if accelerator.built_cost * MAX_REFIT_COST_GROWTH < root.get_sah_cost() { accelerator.is_stale = true; }
+        }
+    }
+
+    ///
+    /// Builds the Scene's Accelerator over every hittable in the Scene.
+    ///
+    /// # Arguments
+    /// * `&self` - The Scene.
+    ///
+    fn rebuild(&self) {
+        let hittables = self.get_hittables();
+        let mut accelerator = self.accelerator.borrow_mut();
+        accelerator.root = if hittables.is_empty() { None } else { Some(self.accelerator_kind.build(hittables, self.max_leaf_size)) };
+        accelerator.built_cost = accelerator.root.as_ref().map_or(0.0, |root| root.get_sah_cost());
+        accelerator.pending.clear();
+        accelerator.is_stale = false;
     }
 
     ///
@@ -62,9 +221,169 @@ impl Scene {
     ///
     pub fn get_intersect(&self, ray: Ray, min_dist: f64, max_dist: f64) -> Option<Hit> {
 
-        self.bvh_root.get_hit(ray, min_dist, max_dist)
+        if self.accelerator.borrow().is_stale { self.rebuild(); }
+        let accelerator = self.accelerator.borrow();
+
+        let mut closest_distance = max_dist;
+        let mut hit: Option<Hit> = None;
+
+        if let Some(root) = &accelerator.root {
+            if let Some(h) = root.get_hit(ray, min_dist, closest_distance) {
+                closest_distance = h.distance;
+                hit = Some(h);
+            }
+        }
+
+        for (_, hittable) in &accelerator.pending {
+            if let Some(h) = hittable.get_hit(ray, min_dist, closest_distance) {
+                closest_distance = h.distance;
+                hit = Some(h);
+            }
+        }
+
+        hit
+    }
+
+    ///
+    /// Returns true if the given ray hits any hittable within a scene between
+    /// min_dist and max_dist. Unlike get_intersect, this stops at the first
+    /// intersection found and never shades it, which is all shadow rays and
+    /// ambient occlusion need to know.
+    ///
+    /// # Arguments
+    /// * `&self` - The scene the ray exists in.
+    /// * `ray` - The ray that will possibly intersect with a hittable in the scene.
+    /// * `min_dist` - The minimum distance along the ray to look for an intersection.
+    /// * `max_dist` - The maximum distance along the ray to look for an intersection.
+    ///
+    pub fn is_occluded(&self, ray: Ray, min_dist: f64, max_dist: f64) -> bool {
+
+        if self.accelerator.borrow().is_stale { self.rebuild(); }
+        let accelerator = self.accelerator.borrow();
+
+        if let Some(root) = &accelerator.root {
+            if root.is_hit(ray, min_dist, max_dist) { return true; }
+        }
+
+        accelerator.pending.iter().any(|(_, hittable)| hittable.is_hit(ray, min_dist, max_dist))
+    }
+}
+
+/// Collects hittables and builds a Scene out of them, building the Scene's
+/// Accelerator only once.
+pub struct SceneBuilder {
+    /// The hittables that will make up the Scene.
+    hittables: Vec<Rc<dyn Hittable>>,
+    /// The kind of Accelerator the Scene will be built around.
+    accelerator_kind: AcceleratorKind,
+    /// The most hittables a leaf of the Scene's BVH may hold.
+    max_leaf_size: usize,
+}
+
+impl SceneBuilder {
 
+    ///
+    /// Returns an empty SceneBuilder.
+    ///
+    pub fn new() -> Self {
+        Self {
+            hittables: vec![],
+            accelerator_kind: AcceleratorKind::Bvh,
+            max_leaf_size: DEFAULT_MAX_LEAF_SIZE,
+        }
+    }
+
+    ///
+    /// Sets the kind of Accelerator the Scene will be built around. A BVH is
+    /// used unless another kind is set.
+    ///
+    /// # Arguments
+    /// * `&mut self` - The SceneBuilder.
+    /// * `accelerator_kind` - The kind of Accelerator.
+    ///
+    pub fn set_accelerator_kind(&mut self, accelerator_kind: AcceleratorKind) {
+        self.accelerator_kind = accelerator_kind;
+    }
+
+    ///
+    /// Sets the most hittables a leaf of the Scene's BVH may hold.
+    ///
+    /// # Arguments
+    /// * `&mut self` - The SceneBuilder.
+    /// * `max_leaf_size` - The most hittables a leaf may hold. Must be at least 1.
+    ///
+    pub fn set_max_leaf_size(&mut self, max_leaf_size: usize) {
+        self.max_leaf_size = max_leaf_size.max(1);
     }
+
+    ///
+    /// Adds a Rc<dyn Hittable> to the hittables that will make up the Scene and
+    /// returns the handle it will have in the Scene.
+    ///
+    /// # Arguments
+    /// * `&mut self` - The SceneBuilder.
+    /// * `hittable` - The hittable to add.
+    ///
+    pub fn push(&mut self, hittable: Rc<dyn Hittable>) -> HittableHandle {
+        self.hittables.push(hittable);
+        HittableHandle(self.hittables.len() - 1)
+    }
+
+    ///
+    /// Returns a Scene made of every hittable pushed into the SceneBuilder.
+    ///
+    /// # Arguments
+    /// * `self` - The SceneBuilder.
+    ///
+    pub fn build(self) -> Scene {
+        let scene = Scene {
+            hittables: self.hittables.into_iter().map(Some).collect(),
+            accelerator_kind: self.accelerator_kind,
+            max_leaf_size: self.max_leaf_size,
+            accelerator: RefCell::new(SceneAccelerator {
+                root: None,
+                pending: vec![],
+                built_cost: 0.0,
+                is_stale: true,
+            }),
+        };
+        scene.rebuild();
+        scene
+    }
+}
+
+/// The name of a scene that can be picked on the command line, with the
+/// function that constructs it.
+pub type NamedScene = (&'static str, fn() -> Scene);
+
+/// Every scene that can be picked on the command line.
+pub const NAMED_SCENES: [NamedScene; 15] = [
+    ("book1", construct_book1_final),
+    ("quads", construct_book2_quads),
+    ("shapes", construct_analytic_shapes),
+    ("ring", construct_instanced_ring),
+    ("csg", construct_csg),
+    ("sdf", construct_sdf_shapes),
+    ("terrain", construct_terrain),
+    ("meshes", construct_instanced_meshes),
+    ("solid", construct_solid_textures),
+    ("procedural", construct_procedural_textures),
+    ("graphs", construct_texture_graphs),
+    ("filtering", construct_texture_filtering),
+    ("bumps", construct_surface_detail),
+    ("cutouts", construct_cutouts),
+    ("projections", construct_projections),
+];
+
+///
+/// Returns the scene with the given name in NAMED_SCENES, or None if there is
+/// no such scene.
+///
+/// # Arguments
+/// * `name` - The name of the scene.
+///
+pub fn construct_named_scene(name: &str) -> Option<Scene> {
+    NAMED_SCENES.iter().find(|(n, _)| *n == name).map(|(_, construct)| construct())
 }
 
 ///
@@ -91,30 +410,46 @@ pub fn construct_book1_final() -> Scene {
         let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
     */
 
+    let mut builder = SceneBuilder::new();
+    for sphere in construct_book1_spheres(&mut rand::thread_rng()) {
+        builder.push(Rc::new(sphere));
+    }
+
+    builder.build()
+}
+
+///
+/// Returns the spheres of the final scene from the first book. See
+/// construct_book1_final. The given random number generator picks the small
+/// spheres' materials, so a seeded generator always gives the same spheres.
+///
+/// # Arguments
+/// * `rng` - The random number generator.
+///
+pub fn construct_book1_spheres(rng: &mut impl Rng) -> Vec<Sphere> {
+
     let ground_texture = Monochrome::new(_GREY);
     let ground_material = Material::new(Box::new(ground_texture), None, None);
     let ground = Sphere::new(Point3::new(0.0, -1000.0, -1.0), 1000.0, ground_material);
 
-    let mut scene = Scene::new(Rc::new(ground));
+    let mut spheres = vec![ground];
 
     let radius = 1.0;
 
     let left_texture = Monochrome::new(_LIGHT_RED);
     let left_material = Material::new(Box::new(left_texture), None, None);
     let left = Sphere::new(Point3::new(-3.0, radius, 0.0), radius, left_material);
-    scene.push(Rc::new(left));
+    spheres.push(left);
 
     let center_texture = Monochrome::new(_WHITE);
     let center_material = Material::new(Box::new(center_texture), None, Some(1.5));
     let center = Sphere::new(Point3::new(0.0, radius, 0.0), radius, center_material);
-    scene.push(Rc::new(center));
+    spheres.push(center);
 
     let right_texture = Monochrome::new(_LIGHT_BLUE);
     let right_material = Material::new(Box::new(right_texture), Some(0.05), None);
     let right = Sphere::new(Point3::new(3.0, radius, 0.0), radius, right_material);
-    scene.push(Rc::new(right));
-
-    let mut rng = rand::thread_rng();
+    spheres.push(right);
 
     for x in -15..10 {
         for z in -10..5 {
@@ -138,9 +473,1147 @@ pub fn construct_book1_final() -> Scene {
             }
 
             let sphere = Sphere::new(center, radius, material);
-            scene.push(Rc::new(sphere));
+            spheres.push(sphere);
         }
     }
 
-    scene
-}
\ No newline at end of file
+    spheres
+}
+
+///
+/// Returns the quads scene from the second book. Five colored quads facing
+/// the camera, with a box and a disk sitting in the middle of them.
+///
+pub fn construct_book2_quads() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 1.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 80.0;
+
+        let look_from = Vector3::new(0.0, 0.0, 9.0);
+        let look_to = Vector3::new(0.0, 0.0, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 10.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    let left_material = Material::new(Box::new(Monochrome::new(Color::new(1.0, 0.2, 0.2))), None, None);
+    let back_material = Material::new(Box::new(Monochrome::new(Color::new(0.2, 1.0, 0.2))), None, None);
+    let right_material = Material::new(Box::new(Monochrome::new(Color::new(0.2, 0.2, 1.0))), None, None);
+    let upper_material = Material::new(Box::new(Monochrome::new(Color::new(1.0, 0.5, 0.0))), None, None);
+    let lower_material = Material::new(Box::new(Monochrome::new(Color::new(0.2, 0.8, 0.8))), None, None);
+
+    let left = Quad::new(Point3::new(-3.0, -2.0, 5.0), Vector3::new(0.0, 0.0, -4.0), Vector3::new(0.0, 4.0, 0.0), left_material);
+    let back = Quad::new(Point3::new(-2.0, -2.0, 0.0), Vector3::new(4.0, 0.0, 0.0), Vector3::new(0.0, 4.0, 0.0), back_material);
+    let right = Quad::new(Point3::new(3.0, -2.0, 1.0), Vector3::new(0.0, 0.0, 4.0), Vector3::new(0.0, 4.0, 0.0), right_material);
+    let upper = Quad::new(Point3::new(-2.0, 3.0, 1.0), Vector3::new(4.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 4.0), upper_material);
+    let lower = Quad::new(Point3::new(-2.0, -3.0, 5.0), Vector3::new(4.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -4.0), lower_material);
+
+    let mut builder = SceneBuilder::new();
+    builder.push(Rc::new(left));
+    builder.push(Rc::new(back));
+    builder.push(Rc::new(right));
+    builder.push(Rc::new(upper));
+    builder.push(Rc::new(lower));
+
+    let box_material = Material::new(Box::new(Monochrome::new(_WHITE)), Some(0.1), None);
+    let cube = AxisAlignedBox::new(Point3::new(-1.5, -1.0, 1.5), Point3::new(-0.5, 0.0, 2.5), box_material);
+    builder.push(Rc::new(cube));
+
+    let disk_material = Material::new(Box::new(Monochrome::new(_LIGHT_YELLOW)), None, None);
+    let disk = Disk::new(Point3::new(1.0, -0.5, 2.0), Vector3::new(0.0, 1.0, 1.0), 0.75, disk_material);
+    builder.push(Rc::new(disk));
+
+    builder.build()
+}
+
+///
+/// Returns a scene with one of each of the analytic shapes sitting on a large
+/// ground sphere. From left to right: a cylinder, a cone, a capsule and a torus.
+///
+pub fn construct_analytic_shapes() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 40.0;
+
+        let look_from = Vector3::new(0.0, 3.0, 9.0);
+        let look_to = Vector3::new(0.0, 0.75, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 9.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    let ground_texture = Monochrome::new(_GREY);
+    let ground_material = Material::new(Box::new(ground_texture), None, None);
+    let ground = Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_material);
+
+    let mut builder = SceneBuilder::new();
+    builder.push(Rc::new(ground));
+
+    let cylinder_texture = Checkered::new(Box::new(Monochrome::new(_LIGHT_RED)), Box::new(Monochrome::new(_WHITE)), 5.0);
+    let cylinder_material = Material::new(Box::new(cylinder_texture), None, None);
+    let cylinder = Cylinder::new(Point3::new(-3.3, 0.0, 0.0), Point3::new(-3.3, 1.5, 0.0), 0.6, cylinder_material);
+    builder.push(Rc::new(cylinder));
+
+    let cone_material = Material::new(Box::new(Monochrome::new(_LIGHT_GREEN)), Some(0.2), None);
+    let cone = Cone::new(Point3::new(-1.2, 0.0, 0.0), Point3::new(-1.2, 1.75, 0.0), 0.7, cone_material);
+    builder.push(Rc::new(cone));
+
+    let capsule_material = Material::new(Box::new(Monochrome::new(_WHITE)), None, Some(1.5));
+    let capsule = Capsule::new(Point3::new(0.6, 0.5, 0.5), Point3::new(1.2, 1.25, -0.5), 0.5, capsule_material);
+    builder.push(Rc::new(capsule));
+
+    let torus_texture = Checkered::new(Box::new(Monochrome::new(_LIGHT_BLUE)), Box::new(Monochrome::new(_LIGHT_YELLOW)), 2.0);
+    let torus_material = Material::new(Box::new(torus_texture), None, None);
+    let torus = Torus::new(Point3::new(3.3, 0.9, 0.0), Vector3::new(0.0, 1.0, 1.5), 0.7, 0.25, torus_material);
+    builder.push(Rc::new(torus));
+
+    builder.build()
+}
+
+///
+/// Returns a scene with a ring of tilted, stretched boxes. Every box is an
+/// Instance of the same unit cube, so the cube's geometry only exists once.
+///
+pub fn construct_instanced_ring() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 40.0;
+
+        let look_from = Vector3::new(0.0, 6.0, 10.0);
+        let look_to = Vector3::new(0.0, 0.5, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 10.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    let ground_texture = Monochrome::new(_GREY);
+    let ground_material = Material::new(Box::new(ground_texture), None, None);
+    let ground = Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_material);
+
+    let mut builder = SceneBuilder::new();
+    builder.push(Rc::new(ground));
+
+    let cube_material = Material::new(Box::new(Monochrome::new(_LIGHT_PINK)), None, None);
+    let cube: Rc<dyn Hittable> = Rc::new(AxisAlignedBox::new(Point3::new(-0.5, 0.0, -0.5), Point3::new(0.5, 1.0, 0.5), cube_material));
+
+    let count = 16;
+    for i in 0..count {
+        let angle = 360.0 * i as f64 / count as f64;
+        let transform = Transform::rotation(Vector3::new(0.0, 1.0, 0.0), angle)
+            * Transform::translation(Vector3::new(4.0, 0.0, 0.0))
+            * Transform::rotation(Vector3::new(0.0, 0.0, 1.0), 20.0)
+            * Transform::scaling(Vector3::new(0.3, 1.0 + (i % 4) as f64 * 0.25, 0.3));
+        builder.push(Rc::new(Instance::new(Rc::clone(&cube), transform)));
+    }
+
+    builder.build()
+}
+
+///
+/// Returns a scene showing each of the CSG operations. From left to right: a
+/// box with a sphere cut out of it, a glass lens made from the intersection of
+/// two spheres, and the union of a cylinder and a sphere.
+///
+pub fn construct_csg() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 40.0;
+
+        let look_from = Vector3::new(2.0, 4.0, 8.0);
+        let look_to = Vector3::new(0.0, 0.75, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 9.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    let ground_texture = Monochrome::new(_GREY);
+    let ground_material = Material::new(Box::new(ground_texture), None, None);
+    let ground = Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_material);
+
+    let mut builder = SceneBuilder::new();
+    builder.push(Rc::new(ground));
+
+    let box_material = Material::new(Box::new(Monochrome::new(_LIGHT_BLUE)), None, None);
+    let cube = AxisAlignedBox::new(Point3::new(-3.5, 0.0, -0.75), Point3::new(-2.0, 1.5, 0.75), box_material);
+    let cutter_material = Material::new(Box::new(Monochrome::new(_LIGHT_YELLOW)), None, None);
+    let cutter = Sphere::new(Point3::new(-2.0, 1.5, 0.75), 1.0, cutter_material);
+    let difference = Csg::new(CsgOperation::Difference, Rc::new(cube), Rc::new(cutter));
+    builder.push(Rc::new(difference));
+
+    let lens_material1 = Material::new(Box::new(Monochrome::new(_WHITE)), None, Some(1.5));
+    let lens_material2 = Material::new(Box::new(Monochrome::new(_WHITE)), None, Some(1.5));
+    let lens_side1 = Sphere::new(Point3::new(0.0, 1.0, -1.2), 1.5, lens_material1);
+    let lens_side2 = Sphere::new(Point3::new(0.0, 1.0, 1.2), 1.5, lens_material2);
+    let lens = Csg::new(CsgOperation::Intersection, Rc::new(lens_side1), Rc::new(lens_side2));
+    builder.push(Rc::new(lens));
+
+    let post_material = Material::new(Box::new(Monochrome::new(_LIGHT_GREEN)), Some(0.1), None);
+    let post = Cylinder::new(Point3::new(2.75, 0.0, 0.0), Point3::new(2.75, 1.25, 0.0), 0.4, post_material);
+    let knob_material = Material::new(Box::new(Monochrome::new(_LIGHT_RED)), None, None);
+    let knob = Sphere::new(Point3::new(2.75, 1.25, 0.0), 0.6, knob_material);
+    let union = Csg::new(CsgOperation::Union, Rc::new(post), Rc::new(knob));
+    builder.push(Rc::new(union));
+
+    builder.build()
+}
+
+///
+/// Returns a scene of signed distance field shapes. From left to right: a
+/// rounded box smoothly joined with a sphere, a twisted torus, a row of
+/// repeated spheres and a Mandelbulb.
+///
+pub fn construct_sdf_shapes() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 40.0;
+
+        let look_from = Vector3::new(0.0, 3.0, 9.0);
+        let look_to = Vector3::new(0.0, 0.75, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 9.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    let ground_texture = Monochrome::new(_GREY);
+    let ground_material = Material::new(Box::new(ground_texture), None, None);
+    let ground = Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_material);
+
+    let mut builder = SceneBuilder::new();
+    builder.push(Rc::new(ground));
+
+    let blob_function = sdf::smooth_union(
+        sdf::rounded_box(Point3::new(-3.5, 0.5, 0.0), Vector3::new(0.4, 0.4, 0.4), 0.1),
+        sdf::sphere(Point3::new(-3.2, 1.2, 0.0), 0.45),
+        0.3,
+    );
+    let blob_bounds = AABB::new(Point3::new(-4.1, 0.0, -0.6), Point3::new(-2.6, 1.8, 0.6));
+    let blob_material = Material::new(Box::new(Monochrome::new(_LIGHT_RED)), None, None);
+    builder.push(Rc::new(SdfShape::new(blob_function, blob_bounds, blob_material)));
+
+    let twisted_function = sdf::translate(
+        sdf::twist(sdf::rounded_box(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.5, 0.8, 0.2), 0.05), 60.0),
+        Vector3::new(-1.3, 0.85, 0.0),
+    );
+    let twisted_bounds = AABB::new(Point3::new(-2.0, 0.0, -0.7), Point3::new(-0.6, 1.7, 0.7));
+    let twisted_material = Material::new(Box::new(Monochrome::new(_LIGHT_GREEN)), Some(0.2), None);
+    let mut twisted = SdfShape::new(twisted_function, twisted_bounds, twisted_material);
+    twisted.step_scale = 0.5;
+    builder.push(Rc::new(twisted));
+
+    let repeated_function = sdf::repeat(sdf::sphere(Point3::new(0.0, 0.0, 0.0), 0.2), Vector3::new(0.5, 0.0, 0.5));
+    let repeated_function = sdf::translate(repeated_function, Vector3::new(0.0, 0.2, 0.0));
+    let repeated_bounds = AABB::new(Point3::new(-0.25, 0.0, -0.75), Point3::new(1.25, 0.4, 0.75));
+    let repeated_material = Material::new(Box::new(Monochrome::new(_LIGHT_YELLOW)), None, None);
+    builder.push(Rc::new(SdfShape::new(repeated_function, repeated_bounds, repeated_material)));
+
+    let bulb_function = sdf::translate(sdf::scale(sdf::mandelbulb(8.0, 12), 1.1), Vector3::new(3.0, 1.35, 0.0));
+    let bulb_bounds = AABB::new(Point3::new(1.65, 0.0, -1.35), Point3::new(4.35, 2.7, 1.35));
+    let bulb_material = Material::new(Box::new(Monochrome::new(_LIGHT_BLUE)), None, None);
+    builder.push(Rc::new(SdfShape::new(bulb_function, bulb_bounds, bulb_material)));
+
+    // A row of constructive solid geometry behind the others.
+    let cut_center = Point3::new(-3.2, 0.65, -3.5);
+    let cut_function = sdf::round(
+        sdf::difference(
+            sdf::intersection(
+                sdf::rounded_box(cut_center, Vector3::new(0.6, 0.6, 0.6), 0.0),
+                sdf::sphere(cut_center, 0.8),
+            ),
+            sdf::torus(cut_center, 0.75, 0.2),
+        ),
+        0.03,
+    );
+    let cut_bounds = AABB::new(Point3::new(-3.9, 0.0, -4.2), Point3::new(-2.5, 1.3, -2.8));
+    let cut_material = Material::new(Box::new(Monochrome::new(_LIGHT_CYAN)), Some(0.3), None);
+    builder.push(Rc::new(SdfShape::new(cut_function, cut_bounds, cut_material)));
+
+    let bowl_function = sdf::smooth_difference(
+        sdf::intersection(
+            sdf::sphere(Point3::new(-0.1, 0.0, -3.5), 1.0),
+            sdf::plane(Vector3::new(0.0, 1.0, 0.0), 0.7),
+        ),
+        sdf::sphere(Point3::new(-0.1, 0.95, -3.5), 0.5),
+        0.15,
+    );
+    let bowl_bounds = AABB::new(Point3::new(-1.15, 0.0, -4.55), Point3::new(0.95, 0.75, -2.45));
+    let bowl_material = Material::new(Box::new(Monochrome::new(_LIGHT_YELLOW)), None, None);
+    builder.push(Rc::new(SdfShape::new(bowl_function, bowl_bounds, bowl_material)));
+
+    let ring_function = sdf::union(
+        sdf::torus(Point3::new(1.8, 0.25, -3.5), 0.7, 0.25),
+        sdf::sphere(Point3::new(1.8, 0.5, -3.5), 0.35),
+    );
+    let ring_bounds = AABB::new(Point3::new(0.8, 0.0, -4.5), Point3::new(2.8, 0.9, -2.5));
+    let ring_material = Material::new(Box::new(Monochrome::new(_LIGHT_RED)), None, None);
+    builder.push(Rc::new(SdfShape::new(ring_function, ring_bounds, ring_material)));
+
+    builder.build()
+}
+
+///
+/// Returns a scene of hilly terrain made from a Heightfield of layered perlin
+/// noise, with a mirrored sphere floating above it.
+///
+pub fn construct_terrain() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 45.0;
+
+        let look_from = Vector3::new(0.0, 6.0, 14.0);
+        let look_to = Vector3::new(0.0, 0.5, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 14.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    // Layered perlin noise, shifted to be between [0, 1].
+    let perlin = Perlin::new(7);
+    let height = |u: f64, v: f64| {
+        let mut accum = 0.0;
+        let mut weight = 0.5;
+        let mut frequency = 3.0;
+        for _ in 0..6 {
+            accum += weight * perlin.get([u * frequency, v * frequency]);
+            weight *= 0.5;
+            frequency *= 2.0;
+        }
+        (accum + 1.0) * 0.5
+    };
+
+    let terrain_texture = Checkered::new(Box::new(Monochrome::new(_LIGHT_GREEN)), Box::new(Monochrome::new(_DARK_GREEN)), 1.0);
+    let terrain_material = Material::new(Box::new(terrain_texture), None, None);
+    let terrain = Heightfield::from_function(
+        &height,
+        257,
+        257,
+        Point3::new(-10.0, -2.0, -10.0),
+        Vector3::new(20.0, 6.0, 20.0),
+        terrain_material,
+    );
+
+    let mut builder = SceneBuilder::new();
+    builder.push(Rc::new(terrain));
+
+    let sphere_material = Material::new(Box::new(Monochrome::new(_LIGHT_BLUE)), Some(0.9), None);
+    builder.push(Rc::new(Sphere::new(Point3::new(0.0, 3.0, 0.0), 1.0, sphere_material)));
+
+    builder.build()
+}
+
+///
+/// Returns a scene with a grid of vases and gems. There are only two Meshes,
+/// and every vase and gem is an Instance of one of them in a TopLevelBvh.
+///
+pub fn construct_instanced_meshes() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 35.0;
+
+        let look_from = Vector3::new(0.0, 5.0, 12.0);
+        let look_to = Vector3::new(0.0, 0.5, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 12.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    let ground_texture = Monochrome::new(_GREY);
+    let ground_material = Material::new(Box::new(ground_texture), None, None);
+    let ground = Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_material);
+
+    let mut builder = SceneBuilder::new();
+    builder.push(Rc::new(ground));
+
+    let vase_texture = Checkered::new(Box::new(Monochrome::new(_LIGHT_BLUE)), Box::new(Monochrome::new(_WHITE)), 4.0);
+    let vase: Rc<dyn Hittable> = Rc::new(construct_vase_mesh(Material::new(Box::new(vase_texture), None, None)));
+    let gem: Rc<dyn Hittable> = Rc::new(construct_gem_mesh(Material::new(Box::new(Monochrome::new(_LIGHT_RED)), Some(0.1), None)));
+
+    let mut instances = TopLevelBvh::new();
+    let mut rng = rand::thread_rng();
+
+    for x in -4..=4 {
+        for z in -3..=1 {
+            let position = Vector3::new(x as f64 * 1.2, 0.0, z as f64 * 1.2);
+            let spin = rng.gen_range(0.0..360.0);
+            if (x + z) % 2 == 0 {
+                let size = rng.gen_range(0.7..1.1);
+                let transform = Transform::from_trs(position, Quaternion::from_euler(0.0, spin, 0.0), Vector3::new(size, size, size));
+                instances.push(Rc::clone(&vase), transform);
+            } else {
+                // The gems are tipped over a little, as if they had been dropped.
+                let rotation = Quaternion::from_euler(rng.gen_range(-20.0..20.0), spin, 0.0);
+                let transform = Transform::from_trs(position + Vector3::new(0.0, 0.35, 0.0), rotation, Vector3::new(0.35, 0.35, 0.35));
+                instances.push(Rc::clone(&gem), transform);
+            }
+        }
+    }
+
+    builder.push(Rc::new(instances));
+    builder.build()
+}
+
+///
+/// Returns a scene of shapes cut out of solid textures, sitting on a ground
+/// checkered in 3D. From left to right: a marble sphere, a wooden box, a noisy
+/// torus and a checkered capsule. The marble and wood are laid out in object
+/// space, so they would move with their shapes.
+///
+pub fn construct_solid_textures() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 40.0;
+
+        let look_from = Vector3::new(0.0, 3.0, 9.0);
+        let look_to = Vector3::new(0.0, 0.75, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 9.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    let ground_texture = SolidCheckered::new(Box::new(Monochrome::new(_GREY)), Box::new(Monochrome::new(_WHITE)), 1.0, Space::World);
+    let ground_material = Material::new(Box::new(ground_texture), None, None);
+    let ground = Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_material);
+
+    let mut builder = SceneBuilder::new();
+    builder.push(Rc::new(ground));
+
+    let marble_texture = Marble::new(1, 2.0, 4.0, _DARK_BLUE, _WHITE, Space::Object);
+    let marble_material = Material::new(Box::new(marble_texture), None, None);
+    let marble = Sphere::new(Point3::new(-3.3, 1.0, 0.0), 1.0, marble_material);
+    builder.push(Rc::new(marble));
+
+    let wood_texture = Wood::new(2, 6.0, 0.6, Color::new(0.8, 0.6, 0.4), Color::new(0.4, 0.25, 0.1), Space::Object);
+    let wood_material = Material::new(Box::new(wood_texture), None, None);
+    let wood = AxisAlignedBox::new(Point3::new(-1.8, 0.0, -0.7), Point3::new(-0.4, 1.4, 0.7), wood_material);
+    builder.push(Rc::new(wood));
+
+    let noise_texture = SolidNoise::new(3, 3.0, 6, true, _LIGHT_GREEN, Space::World);
+    let noise_material = Material::new(Box::new(noise_texture), None, None);
+    let torus = Torus::new(Point3::new(1.1, 0.9, 0.0), Vector3::new(0.0, 1.0, 1.5), 0.7, 0.25, noise_material);
+    builder.push(Rc::new(torus));
+
+    let checker_texture = SolidCheckered::new(Box::new(Monochrome::new(_LIGHT_RED)), Box::new(Monochrome::new(_WHITE)), 4.0, Space::Object);
+    let checker_material = Material::new(Box::new(checker_texture), None, None);
+    let capsule = Capsule::new(Point3::new(3.3, 0.5, 0.0), Point3::new(3.3, 1.5, 0.0), 0.5, checker_material);
+    builder.push(Rc::new(capsule));
+
+    builder.build()
+}
+
+///
+/// Returns a scene of procedural patterns: three spheres in front of a brick
+/// wall, on cobblestones made of Worley noise. From left to right the spheres
+/// are colored by Worley F1, Worley F2 and a repeating rainbow gradient.
+///
+pub fn construct_procedural_textures() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 40.0;
+
+        let look_from = Vector3::new(0.0, 3.0, 9.0);
+        let look_to = Vector3::new(0.0, 1.0, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 9.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    let stone_ramp = ColorRamp::new(vec![(0.0, _DARK_YELLOW * 0.4), (0.08, _GREY), (0.4, Color::new(0.7, 0.7, 0.65))]);
+    let ground_texture = Worley::new(1, 1.5, WorleyMode::F2MinusF1, stone_ramp, Space::World);
+    let ground_material = Material::new(Box::new(ground_texture), None, None);
+    let ground = Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_material);
+
+    let mut builder = SceneBuilder::new();
+    builder.push(Rc::new(ground));
+
+    let wall_texture = Bricks::new(Color::new(0.6, 0.2, 0.1), Color::new(0.8, 0.8, 0.75), 6.0, 0.1, 0.4, 2);
+    let wall_material = Material::new(Box::new(wall_texture), None, None);
+    let wall = Quad::new(Point3::new(-6.0, 0.0, -2.0), Vector3::new(12.0, 0.0, 0.0), Vector3::new(0.0, 6.0, 0.0), wall_material);
+    builder.push(Rc::new(wall));
+
+    let cells_ramp = ColorRamp::new(vec![(0.0, _WHITE), (0.3, _LIGHT_BLUE), (1.0, _DARK_BLUE)]);
+    let cells_texture = Worley::new(3, 3.0, WorleyMode::F1, cells_ramp, Space::Object);
+    let cells_material = Material::new(Box::new(cells_texture), None, None);
+    let cells = Sphere::new(Point3::new(-2.4, 1.0, 0.0), 1.0, cells_material);
+    builder.push(Rc::new(cells));
+
+    let bumps_ramp = ColorRamp::from_colors(_LIGHT_YELLOW, _DARK_RED);
+    let bumps_texture = Worley::new(4, 3.0, WorleyMode::F2, bumps_ramp, Space::Object);
+    let bumps_material = Material::new(Box::new(bumps_texture), None, None);
+    let bumps = Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, bumps_material);
+    builder.push(Rc::new(bumps));
+
+    let rainbow_ramp = ColorRamp::new(vec![
+        (0.0, _RED), (0.2, _YELLOW), (0.4, _GREEN), (0.6, _CYAN), (0.8, _BLUE), (1.0, _RED),
+    ]);
+    let rainbow_texture = Gradient::new(rainbow_ramp, Vector3::new(1.0, 1.0, 0.0), 1.0, true, Space::Object);
+    let rainbow_material = Material::new(Box::new(rainbow_texture), None, None);
+    let rainbow = Sphere::new(Point3::new(2.4, 1.0, 0.0), 1.0, rainbow_material);
+    builder.push(Rc::new(rainbow));
+
+    builder.build()
+}
+
+///
+/// Returns a scene of spheres whose textures are graphs of combinator
+/// textures, one of which also drives the reflectivity of its material.
+///
+pub fn construct_texture_graphs() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 40.0;
+
+        let look_from = Vector3::new(0.0, 3.0, 9.0);
+        let look_to = Vector3::new(0.0, 1.0, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 9.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    // One noise node feeds several of the graphs below. Layered noise sits
+    // around 1, so it is stretched to roughly [0, 1] first.
+    let raw_noise = SolidNoise::new(5, 1.0, 5, false, _WHITE, Space::World);
+    let noise: Rc<dyn Texture> = Rc::new(Levels::new(Box::new(raw_noise), 0.6, 1.4, 1.0, 0.0, 1.0));
+
+    let grass = Multiply::new(Box::new(Monochrome::new(_DARK_GREEN)), Box::new(Levels::remap(Box::new(noise.clone()), 0.6, 1.2)));
+    let dirt = Monochrome::new(Color::new(0.35, 0.25, 0.15));
+    let patches = Levels::new(Box::new(noise.clone()), 0.45, 0.55, 1.0, 0.0, 1.0);
+    let ground_texture = Mix::new(Box::new(grass), Box::new(dirt), Box::new(patches));
+    let ground_material = Material::new(Box::new(ground_texture), None, None);
+    let ground = Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_material);
+
+    let mut builder = SceneBuilder::new();
+    builder.push(Rc::new(ground));
+
+    let squares = SolidCheckered::new(Box::new(Monochrome::new(_WHITE)), Box::new(Monochrome::new(_DARK_BLUE)), 2.0, Space::Object);
+    let warped_texture = DomainWarp::new(Box::new(squares), Box::new(noise.clone()), 1.5);
+    let warped_material = Material::new(Box::new(warped_texture), None, None);
+    let warped = Sphere::new(Point3::new(-2.4, 1.0, 0.0), 1.0, warped_material);
+    builder.push(Rc::new(warped));
+
+    // Brushed where the noise is dark and polished where it is light.
+    let roughness = Levels::remap(Box::new(Invert::new(Box::new(noise.clone()))), 0.0, 0.6);
+    let gold_texture = Add::new(Box::new(Monochrome::new(Color::new(0.6, 0.45, 0.1))), Box::new(Levels::remap(Box::new(noise.clone()), 0.0, 0.2)));
+    let gold_material = Material::with_textures(Box::new(gold_texture), Some(Box::new(roughness)), None);
+    let gold = Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, gold_material);
+    builder.push(Rc::new(gold));
+
+    let stripes = Checkered::new(Box::new(Monochrome::new(_RED)), Box::new(Monochrome::new(_YELLOW)), 10.0);
+    let turned = UvTransform::new(Box::new(stripes), (2.0, 1.0), PI / 4.0, (0.0, 0.0));
+    let shifted: Rc<dyn Texture> = Rc::new(turned);
+    let recolored = HueSaturation::new(Box::new(shifted.clone()), 0.5, 0.6, 0.9);
+    let tiles_texture = Mix::new(Box::new(shifted), Box::new(recolored), Box::new(noise));
+    let tiles_material = Material::new(Box::new(tiles_texture), None, None);
+    let tiles = Sphere::new(Point3::new(2.4, 1.0, 0.0), 1.0, tiles_material);
+    builder.push(Rc::new(tiles));
+
+    builder.build()
+}
+
+///
+/// Returns a scene of textures with detail far finer than a pixel in the
+/// distance, which alias into moire patterns unless they are filtered over
+/// each pixel's footprint.
+///
+pub fn construct_texture_filtering() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 40.0;
+
+        let look_from = Vector3::new(0.0, 1.5, 6.0);
+        let look_to = Vector3::new(0.0, 1.0, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 6.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    let mut builder = SceneBuilder::new();
+
+    let checkered_texture = Checkered::new(Box::new(Monochrome::new(_WHITE)), Box::new(Monochrome::new(_BLACK)), 0.02);
+    let checkered_material = Material::new(Box::new(checkered_texture), None, None);
+    let checkered = Quad::new(Point3::new(-200.0, 0.0, 6.0), Vector3::new(200.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -400.0), checkered_material);
+    builder.push(Rc::new(checkered));
+
+    // A small image of thin grid lines, tiled many times over.
+    let size = 16;
+    let texels = (0..size * size).map(|i| {
+        if i % size == 0 || i / size == 0 { _DARK_BLUE } else { _LIGHT_YELLOW }
+    }).collect();
+    let grid_image = ImageTexture::new(texels, size, size, Filter::Ewa, WrapMode::Repeat);
+    let grid_texture = UvTransform::new(Box::new(grid_image), (200.0, 400.0), 0.0, (0.0, 0.0));
+    let grid_material = Material::new(Box::new(grid_texture), None, None);
+    let grid = Quad::new(Point3::new(0.0, 0.0, 6.0), Vector3::new(200.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -400.0), grid_material);
+    builder.push(Rc::new(grid));
+
+    let marble_texture = Marble::new(6, 8.0, 6.0, _DARK_GREEN, _WHITE, Space::Object);
+    let marble_material = Material::new(Box::new(marble_texture), None, None);
+    let marble = Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, marble_material);
+    builder.push(Rc::new(marble));
+
+    builder.build()
+}
+
+///
+/// Returns a scene of surfaces with detail from normal maps, bump maps and
+/// true displacement.
+///
+pub fn construct_surface_detail() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 40.0;
+
+        let look_from = Vector3::new(0.0, 3.0, 9.0);
+        let look_to = Vector3::new(0.0, 1.0, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 9.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    let mut builder = SceneBuilder::new();
+
+    let ripples = Noisy::new(Perlin::new(7), 60.0, 4, false, _WHITE);
+    let ground_material = Material::new(Box::new(Monochrome::new(_GREY)), None, None)
+        .with_normal_map(NormalMap::Bump { height: Box::new(ripples), strength: 0.05 });
+    let ground = Quad::new(Point3::new(-20.0, 0.0, 20.0), Vector3::new(40.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -40.0), ground_material);
+    builder.push(Rc::new(ground));
+
+    let bumps = SolidNoise::new(8, 4.0, 4, true, _WHITE, Space::Object);
+    let bumpy_material = Material::new(Box::new(Monochrome::new(Color::new(0.8, 0.3, 0.2))), None, None)
+        .with_normal_map(NormalMap::Bump { height: Box::new(bumps), strength: 0.1 });
+    let bumpy = Sphere::new(Point3::new(-2.4, 1.0, 0.0), 1.0, bumpy_material);
+    builder.push(Rc::new(bumpy));
+
+    // A normal map of round dimples, tiled over the sphere.
+    let size = 64;
+    let texels = (0..size * size).map(|i| {
+        let x = 2.0 * ((i % size) as f64 + 0.5) / size as f64 - 1.0;
+        let y = 1.0 - 2.0 * ((i / size) as f64 + 0.5) / size as f64;
+        let r2 = x * x + y * y;
+        let normal = if r2 < 0.64 { Vector3::new(-x, -y, (1.0 - r2).sqrt()).unit() } else { Vector3::new(0.0, 0.0, 1.0) };
+        Color::new(0.5 * (normal.x + 1.0), 0.5 * (normal.y + 1.0), 0.5 * (normal.z + 1.0))
+    }).collect();
+    let dimples = ImageTexture::new(texels, size, size, Filter::Trilinear, WrapMode::Repeat);
+    let dimples = UvTransform::new(Box::new(dimples), (16.0, 8.0), 0.0, (0.0, 0.0));
+    let dimpled_material = Material::new(Box::new(Monochrome::new(_WHITE)), Some(0.05), None)
+        .with_normal_map(NormalMap::Tangent(Box::new(dimples)));
+    let dimpled = Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, dimpled_material);
+    builder.push(Rc::new(dimpled));
+
+    // An octahedron, split up and pushed out by noise into a rock.
+    let positions = vec![
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(-1.0, 0.0, 0.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Point3::new(0.0, -1.0, 0.0),
+        Point3::new(0.0, 0.0, 1.0),
+        Point3::new(0.0, 0.0, -1.0),
+    ];
+    let triangles = vec![
+        [0, 2, 4], [4, 2, 1], [1, 2, 5], [5, 2, 0],
+        [4, 3, 0], [1, 3, 4], [5, 3, 1], [0, 3, 5],
+    ];
+    let normals = positions.clone();
+    let lumps = SolidNoise::new(9, 1.5, 4, false, _WHITE, Space::Object);
+    let ObjData { positions, triangles, normals, uvs } = displace(&positions, &triangles, &normals, &[], &lumps, 0.3, 5);
+    let rock_material = Material::new(Box::new(Monochrome::new(Color::new(0.5, 0.45, 0.4))), None, None);
+    let rock = Mesh::new(positions, triangles, normals, uvs, rock_material);
+    let rock = Instance::new(Rc::new(rock), Transform::translation(Vector3::new(2.4, 1.15, 0.0)));
+    builder.push(Rc::new(rock));
+
+    builder.build()
+}
+
+///
+/// Returns a scene of surfaces with holes cut in them by opacity masks. A
+/// lattice fence and a few leaves have hard edged cutouts, and a ghostly
+/// sphere in front is only partly there.
+///
+pub fn construct_cutouts() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 40.0;
+
+        let look_from = Vector3::new(0.0, 2.0, 9.0);
+        let look_to = Vector3::new(0.0, 1.2, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 9.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    let mut builder = SceneBuilder::new();
+
+    let ground_texture = Checkered::new(Box::new(Monochrome::new(_GREY)), Box::new(Monochrome::new(_WHITE)), 2.0);
+    let ground_material = Material::new(Box::new(ground_texture), None, None);
+    let ground = Quad::new(Point3::new(-20.0, 0.0, 20.0), Vector3::new(40.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -40.0), ground_material);
+    builder.push(Rc::new(ground));
+
+    // A diagonal lattice, opaque along the slats and see-through between them.
+    let size = 64;
+    let slat_width = 0.15;
+    let texels = (0..size * size).map(|i| {
+        let x = ((i % size) as f64 + 0.5) / size as f64;
+        let y = ((i / size) as f64 + 0.5) / size as f64;
+        let is_slat = |t: f64| (t - t.round()).abs() < slat_width;
+        if is_slat(x + y) || is_slat(x - y) { _WHITE } else { _BLACK }
+    }).collect();
+    let lattice = ImageTexture::new(texels, size, size, Filter::Bilinear, WrapMode::Repeat);
+    let lattice = UvTransform::new(Box::new(lattice), (8.0, 3.0), 0.0, (0.0, 0.0));
+    let fence_material = Material::new(Box::new(Monochrome::new(Color::new(0.55, 0.4, 0.25))), None, None)
+        .with_opacity_mask(OpacityMask::Threshold { opacity: Box::new(lattice), threshold: 0.5 });
+    let fence = Quad::new(Point3::new(-4.0, 0.0, -1.5), Vector3::new(8.0, 0.0, 0.0), Vector3::new(0.0, 3.0, 0.0), fence_material);
+    builder.push(Rc::new(fence));
+
+    let behind = Sphere::new(Point3::new(1.5, 1.0, -3.5), 1.0, Material::new(Box::new(Monochrome::new(Color::new(0.2, 0.4, 0.8))), None, None));
+    builder.push(Rc::new(behind));
+
+    // A leaf shaped mask: a pointed oval around the middle of the uv square.
+    let texels = (0..size * size).map(|i| {
+        let u = ((i % size) as f64 + 0.5) / size as f64;
+        let v = ((i / size) as f64 + 0.5) / size as f64;
+        let half_width = 0.45 * (PI * v).sin().powf(1.5);
+        if (u - 0.5).abs() < half_width { _WHITE } else { _BLACK }
+    }).collect();
+    let leaf_shape = Rc::new(ImageTexture::new(texels, size, size, Filter::Bilinear, WrapMode::Clamp));
+
+    let leaves = [
+        (Point3::new(-2.5, 0.8, 0.5), Vector3::new(0.8, 0.0, 0.2), Vector3::new(0.3, 1.2, 0.4)),
+        (Point3::new(-1.8, 0.6, 1.0), Vector3::new(0.7, 0.0, -0.3), Vector3::new(-0.2, 1.0, 0.6)),
+        (Point3::new(-3.2, 0.4, 1.2), Vector3::new(0.6, 0.1, 0.4), Vector3::new(0.4, 1.1, -0.2)),
+    ];
+    for (origin, u, v) in leaves {
+        let leaf_material = Material::new(Box::new(Monochrome::new(Color::new(0.2, 0.6, 0.15))), None, None)
+            .with_opacity_mask(OpacityMask::Threshold { opacity: Box::new(leaf_shape.clone()), threshold: 0.5 });
+        let leaf = Quad::new(origin, u, v, leaf_material);
+        builder.push(Rc::new(leaf));
+    }
+
+    // Seen through stochastically, the sphere fades out towards its bottom.
+    let fade = ColorRamp::new(vec![(0.2, _BLACK), (1.0, _WHITE)]);
+    let fade = Gradient::new(fade, Vector3::new(0.0, 1.0, 0.0), 1.0 / 2.2, false, Space::World);
+    let ghost_material = Material::new(Box::new(Monochrome::new(Color::new(0.9, 0.9, 0.95))), None, None)
+        .with_opacity_mask(OpacityMask::Stochastic(Box::new(fade)));
+    let ghost = Sphere::new(Point3::new(1.5, 1.3, 1.5), 0.9, ghost_material);
+    builder.push(Rc::new(ghost));
+
+    builder.build()
+}
+
+///
+/// Returns a scene of textures mapped onto Hittables without good uv
+/// coordinates of their own. A box and an SDF blob are triplanar mapped, a
+/// rounded SDF pillar has a cylindrical projection, a turned cube has a
+/// spherical one and a plinth a planar one, and a target is projected over the
+/// back of the scene from a camera off to the side.
+///
+pub fn construct_projections() -> Scene {
+
+    /*
+        Here are the camera args that were used for this scene, in case it needs
+        to be recreated exactly.
+
+        let aspect_ratio = 16.0 / 9.0;
+        let image_height = 1080;
+        let image_width = (image_height as f64 * aspect_ratio) as i32;
+        let vfov = 40.0;
+
+        let look_from = Vector3::new(0.0, 3.0, 9.0);
+        let look_to = Vector3::new(0.0, 1.0, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 9.0;
+        let aperature = 0.0;
+
+        let cam = Camera::new(look_from, look_to, up, vfov, aspect_ratio, aperature, dist_to_focus);
+    */
+
+    let mut builder = SceneBuilder::new();
+
+    // A target of rings, projected onto the ground and back wall from a
+    // camera up and to the right.
+    let size = 128;
+    let texels = (0..size * size).map(|i| {
+        let x = 2.0 * ((i % size) as f64 + 0.5) / size as f64 - 1.0;
+        let y = 2.0 * ((i / size) as f64 + 0.5) / size as f64 - 1.0;
+        let ring = ((x * x + y * y).sqrt() * 5.0) as u32;
+        if ring.is_multiple_of(2) { Color::new(0.9, 0.3, 0.2) } else { _WHITE }
+    }).collect();
+    let target = Rc::new(ImageTexture::new(texels, size, size, Filter::Trilinear, WrapMode::Clamp));
+    let get_projector = || Camera::new(Point3::new(5.0, 4.0, 4.0), Point3::new(0.0, 1.0, -2.0), Vector3::new(0.0, 1.0, 0.0), 30.0, 1.0, 0.0, 1.0);
+
+    let ground_texture = CameraProjection::new(Box::new(target.clone()), get_projector(), Box::new(Monochrome::new(_GREY)));
+    let ground_material = Material::new(Box::new(ground_texture), None, None);
+    let ground = Quad::new(Point3::new(-20.0, 0.0, 20.0), Vector3::new(40.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -40.0), ground_material);
+    builder.push(Rc::new(ground));
+
+    let wall_texture = CameraProjection::new(Box::new(target.clone()), get_projector(), Box::new(Monochrome::new(_GREY)));
+    let wall_material = Material::new(Box::new(wall_texture), None, None);
+    let wall = Quad::new(Point3::new(-20.0, 0.0, -2.0), Vector3::new(40.0, 0.0, 0.0), Vector3::new(0.0, 10.0, 0.0), wall_material);
+    builder.push(Rc::new(wall));
+
+    // Sharp blending keeps the bricks on each side of the box apart.
+    let bricks = Bricks::new(Color::new(0.6, 0.2, 0.1), Color::new(0.8, 0.8, 0.75), 2.0, 0.1, 0.4, 5);
+    let box_texture = Triplanar::new(Box::new(bricks), 1.5, 8.0, Space::Object);
+    let box_material = Material::new(Box::new(box_texture), None, None);
+    let cube = AxisAlignedBox::new(Point3::new(-3.5, 0.0, -0.75), Point3::new(-2.0, 1.5, 0.75), box_material);
+    builder.push(Rc::new(cube));
+
+    // Soft blending hides the seams on the blob's curves.
+    let checker = Checkered::new(Box::new(Monochrome::new(_WHITE)), Box::new(Monochrome::new(Color::new(0.2, 0.5, 0.3))), 10.0);
+    let blob_texture = Triplanar::new(Box::new(checker), 1.0, 2.0, Space::Object);
+    let blob_function = sdf::smooth_union(
+        sdf::rounded_box(Point3::new(-0.3, 0.5, 0.0), Vector3::new(0.5, 0.5, 0.5), 0.1),
+        sdf::sphere(Point3::new(0.2, 1.3, 0.0), 0.5),
+        0.3,
+    );
+    let blob_bounds = AABB::new(Point3::new(-1.0, 0.0, -0.7), Point3::new(0.8, 1.9, 0.7));
+    let blob_material = Material::new(Box::new(blob_texture), None, None);
+    builder.push(Rc::new(SdfShape::new(blob_function, blob_bounds, blob_material)));
+
+    // The pillar's bricks wrap around it, 6 bricks around and 4 rows a unit up.
+    let bricks = Bricks::new(Color::new(0.75, 0.7, 0.6), Color::new(0.3, 0.3, 0.3), 1.0, 0.08, 0.3, 9);
+    let wrapped = UvTransform::new(Box::new(bricks), (6.0, 2.0), 0.0, (0.0, 0.0));
+    let placement = Transform::translation(Vector3::new(2.5, 0.0, 0.0));
+    let pillar_texture = Projection::new(Box::new(wrapped), ProjectionKind::Cylindrical, placement, Space::World);
+    let pillar_function = sdf::rounded_box(Point3::new(2.5, 0.9, 0.0), Vector3::new(0.35, 0.75, 0.35), 0.15);
+    let pillar_bounds = AABB::new(Point3::new(1.9, 0.0, -0.6), Point3::new(3.1, 1.8, 0.6));
+    let pillar_material = Material::new(Box::new(pillar_texture), None, None);
+    builder.push(Rc::new(SdfShape::new(pillar_function, pillar_bounds, pillar_material)));
+
+    // A turned cube with checks wrapped around it like a globe. The projection
+    // is in object space, so it turns with the cube.
+    let checker = Checkered::new(Box::new(Monochrome::new(_WHITE)), Box::new(Monochrome::new(Color::new(0.2, 0.3, 0.6))), 1.25);
+    let placement = Transform::translation(Vector3::new(0.0, 0.5, 0.0));
+    let globe_texture = Projection::new(Box::new(checker), ProjectionKind::Spherical, placement, Space::Object);
+    let globe_material = Material::new(Box::new(globe_texture), None, None);
+    let globe = AxisAlignedBox::new(Point3::new(-0.5, 0.0, -0.5), Point3::new(0.5, 1.0, 0.5), globe_material);
+    let object_to_world = Transform::translation(Vector3::new(-1.4, 0.0, 2.0))
+        * Transform::rotation(Vector3::new(0.0, 1.0, 0.0), 35.0)
+        * Transform::scaling(Vector3::new(0.8, 0.8, 0.8));
+    builder.push(Rc::new(Instance::new(Rc::new(globe), object_to_world)));
+
+    // The target projected straight down onto a rounded plinth. The sides
+    // are smeared with the edges of the image.
+    let placement = Transform::translation(Vector3::new(0.8, 0.0, 2.6)) * Transform::rotation(Vector3::new(1.0, 0.0, 0.0), -90.0);
+    let plinth_texture = Projection::new(Box::new(target), ProjectionKind::Planar, placement, Space::World);
+    let plinth_function = sdf::rounded_box(Point3::new(1.3, 0.2, 2.1), Vector3::new(0.5, 0.2, 0.5), 0.1);
+    let plinth_bounds = AABB::new(Point3::new(0.7, 0.0, 1.5), Point3::new(1.9, 0.5, 2.7));
+    let plinth_material = Material::new(Box::new(plinth_texture), None, None);
+    builder.push(Rc::new(SdfShape::new(plinth_function, plinth_bounds, plinth_material)));
+
+    builder.build()
+}
+
+///
+/// Returns a smooth vase shaped Mesh, made by spinning a curve around the y
+/// axis. The vase sits on the origin and is 1.2 units tall.
+///
+/// # Arguments
+/// * `material` - The vase's material.
+///
+fn construct_vase_mesh(material: Material) -> Mesh {
+
+    let segments = 48;
+    let rings = 32;
+    let height = 1.2;
+
+    let mut positions = vec![];
+    let mut uvs = vec![];
+    for ring in 0..=rings {
+        let v = ring as f64 / rings as f64;
+        let radius = 0.25 + 0.12 * (v * 2.0 * PI).sin() + 0.05 * (v * 5.0 * PI).cos();
+        for segment in 0..segments {
+            let u = segment as f64 / segments as f64;
+            let (sin, cos) = (u * 2.0 * PI).sin_cos();
+            positions.push(Point3::new(radius * cos, v * height, -radius * sin));
+            uvs.push((u, v));
+        }
+    }
+
+    let mut triangles = vec![];
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let a = ring * segments + segment;
+            let b = ring * segments + (segment + 1) % segments;
+            let c = a + segments;
+            let d = b + segments;
+            triangles.push([a, b, d]);
+            triangles.push([a, d, c]);
+        }
+    }
+
+    let normals = get_smooth_normals(&positions, &triangles);
+    Mesh::new(positions, triangles, normals, uvs, material)
+}
+
+///
+/// Returns a flat shaded octahedron Mesh centered on the origin.
+///
+/// # Arguments
+/// * `material` - The gem's material.
+///
+fn construct_gem_mesh(material: Material) -> Mesh {
+    let positions = vec![
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(-1.0, 0.0, 0.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Point3::new(0.0, -1.0, 0.0),
+        Point3::new(0.0, 0.0, 1.0),
+        Point3::new(0.0, 0.0, -1.0),
+    ];
+    let triangles = vec![
+        [0, 2, 4], [4, 2, 1], [1, 2, 5], [5, 2, 0],
+        [4, 3, 0], [1, 3, 4], [5, 3, 1], [0, 3, 5],
+    ];
+    Mesh::new(positions, triangles, vec![], vec![], material)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::hittable::accelerator::ACCELERATOR_KINDS;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn get_sphere(x: f64) -> Rc<dyn Hittable> {
+        Rc::new(Sphere::new(Point3::new(x, 0.0, 0.0), 0.5, Material::new(Box::new(Monochrome::new(_GREY)), None, None)))
+    }
+
+    fn get_ray(x: f64) -> Ray {
+        Ray::new(Point3::new(x, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0))
+    }
+
+    #[test]
+    fn pushed_hittables_are_pending_until_there_are_too_many() {
+        let mut builder = SceneBuilder::new();
+        builder.push(get_sphere(0.0));
+        let mut scene = builder.build();
+
+        for i in 1..=MAX_PENDING {
+            scene.push(get_sphere(i as f64 * 2.0));
+        }
+        assert!(!scene.accelerator.borrow().is_stale);
+        assert!(scene.get_intersect(get_ray(MAX_PENDING as f64 * 2.0), 0.001, f64::INFINITY).is_some());
+
+        scene.push(get_sphere(-2.0));
+        assert!(scene.accelerator.borrow().is_stale);
+        assert!(scene.get_intersect(get_ray(-2.0), 0.001, f64::INFINITY).is_some());
+        assert!(scene.accelerator.borrow().pending.is_empty());
+    }
+
+    #[test]
+    fn removing_a_pending_hittable_does_not_rebuild() {
+        let mut builder = SceneBuilder::new();
+        let built = builder.push(get_sphere(0.0));
+        let mut scene = builder.build();
+        let pushed = scene.push(get_sphere(2.0));
+
+        assert!(scene.remove(pushed).is_some());
+        assert!(scene.get(pushed).is_none());
+        assert!(scene.remove(pushed).is_none());
+        assert!(!scene.accelerator.borrow().is_stale);
+        assert!(!scene.is_occluded(get_ray(2.0), 0.001, f64::INFINITY));
+
+        assert!(scene.remove(built).is_some());
+        assert!(scene.accelerator.borrow().is_stale);
+        assert!(!scene.is_occluded(get_ray(0.0), 0.001, f64::INFINITY));
+    }
+
+    /// Returns a Scene of Instances of one sphere, in a row along the x axis.
+    fn get_instanced_scene(count: usize) -> (Scene, Vec<Rc<Instance>>) {
+        let sphere = get_sphere(0.0);
+        let instances: Vec<Rc<Instance>> = (0..count)
+            .map(|i| Rc::new(Instance::new(Rc::clone(&sphere), Transform::translation(Vector3::new(i as f64 * 2.0, 0.0, 0.0)))))
+            .collect();
+        let mut builder = SceneBuilder::new();
+        builder.set_max_leaf_size(1);
+        for instance in &instances {
+            builder.push(Rc::clone(instance) as Rc<dyn Hittable>);
+        }
+        (builder.build(), instances)
+    }
+
+    #[test]
+    fn refit_keeps_hits_on_moved_instances() {
+        let (mut scene, instances) = get_instanced_scene(8);
+
+        // A small move is refit, not rebuilt.
+        instances[3].set_transform(Transform::translation(Vector3::new(6.0, 0.6, 0.0)));
+        scene.refit();
+        assert!(!scene.accelerator.borrow().is_stale);
+
+        let above = Ray::new(Point3::new(6.0, 0.9, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let below = Ray::new(Point3::new(6.0, -0.3, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!(scene.get_intersect(above, 0.001, f64::INFINITY).is_some());
+        assert!(scene.is_occluded(above, 0.001, f64::INFINITY));
+        assert!(scene.get_intersect(below, 0.001, f64::INFINITY).is_none());
+        assert!(!scene.is_occluded(below, 0.001, f64::INFINITY));
+    }
+
+    #[test]
+    fn refit_rebuilds_once_the_cost_has_grown_too_much() {
+        let (mut scene, instances) = get_instanced_scene(32);
+        let built_cost = scene.accelerator.borrow().built_cost;
+
+        // Shuffling the row leaves every node stretched across most of it.
+        for (i, instance) in instances.iter().enumerate() {
+            instance.set_transform(Transform::translation(Vector3::new((i * 13 % 32) as f64 * 2.0, 0.0, 0.0)));
+        }
+        scene.refit();
+        assert!(scene.accelerator.borrow().is_stale);
+
+        assert!(scene.get_intersect(get_ray(10.0), 0.001, f64::INFINITY).is_some());
+        let accelerator = scene.accelerator.borrow();
+        assert!(!accelerator.is_stale);
+        assert!((accelerator.built_cost - built_cost).abs() < 1e-9);
+    }
+
+    #[test]
+    fn accelerators_that_cannot_refit_are_rebuilt() {
+        let (mut scene, instances) = get_instanced_scene(8);
+        scene.accelerator_kind = AcceleratorKind::KdTree;
+        scene.rebuild();
+        instances[0].set_transform(Transform::translation(Vector3::new(0.0, 0.1, 0.0)));
+        scene.refit();
+        assert!(scene.accelerator.borrow().is_stale);
+    }
+
+    #[test]
+    fn occlusion_agrees_with_intersection_for_every_accelerator() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let get_point = |rng: &mut StdRng, size: f64| Point3::new(rng.gen_range(-size..size), rng.gen_range(-size..size), rng.gen_range(-size..size));
+
+        // Some of the quads are cut out in a checkerboard, which both queries
+        // have to see through.
+        let hittables: Vec<Rc<dyn Hittable>> = (0..200).map(|i| -> Rc<dyn Hittable> {
+            let center = get_point(&mut rng, 8.0);
+            if i % 2 == 0 {
+                Rc::new(Sphere::new(center, 0.5, Material::new(Box::new(Monochrome::new(_GREY)), None, None)))
+            } else {
+                let checks = Checkered::new(Box::new(Monochrome::new(_WHITE)), Box::new(Monochrome::new(Color::new(0.0, 0.0, 0.0))), 10.0);
+                let material = Material::new(Box::new(Monochrome::new(_GREY)), None, None)
+                    .with_opacity_mask(OpacityMask::Threshold { opacity: Box::new(checks), threshold: 0.5 });
+                Rc::new(Quad::new(center, get_point(&mut rng, 2.0), get_point(&mut rng, 2.0), material))
+            }
+        }).collect();
+        let rays: Vec<(Ray, f64)> = (0..1000).map(|_| (Ray::new(get_point(&mut rng, 12.0), get_point(&mut rng, 1.0)), rng.gen_range(1.0..30.0))).collect();
+
+        for kind in ACCELERATOR_KINDS {
+            let mut builder = SceneBuilder::new();
+            builder.set_accelerator_kind(kind);
+            for hittable in &hittables[1..] {
+                builder.push(Rc::clone(hittable));
+            }
+            let mut scene = builder.build();
+            // One is pending, so it is checked outside of the Accelerator.
+            scene.push(Rc::clone(&hittables[0]));
+
+            for (ray, max_dist) in &rays {
+                let is_hit = scene.get_intersect(*ray, 0.001, *max_dist).is_some();
+                assert_eq!(scene.is_occluded(*ray, 0.001, *max_dist), is_hit, "{}", kind.get_name());
+            }
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+/// A signed distance function. Returns the distance from a point to the
+/// closest surface, which is negative when the point is inside the surface.
+pub type DistanceFunction = Box<dyn Fn(Point3) -> f64>;
+
+///
+/// Returns the distance function of a sphere.
+///
+/// # Arguments
+/// * `center` - The sphere's center.
+/// * `radius` - The sphere's radius.
+///
+pub fn sphere(center: Point3, radius: f64) -> DistanceFunction {
+    Box::new(move |p| (p - center).mag() - radius)
+}
+
+///
+/// Returns the distance function of a box with rounded edges.
+///
+/// # Arguments
+/// * `center` - The box's center.
+/// * `half_extents` - Half of the box's width, height and depth, before rounding.
+/// * `radius` - The radius of the rounded edges.
+///
+pub fn rounded_box(center: Point3, half_extents: Vector3, radius: f64) -> DistanceFunction {
+    Box::new(move |p| {
+        let local = p - center;
+        let q = Vector3::new(
+            local.x.abs() - half_extents.x,
+            local.y.abs() - half_extents.y,
+            local.z.abs() - half_extents.z,
+        );
+        let outside = Vector3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).mag();
+        let inside = q.x.max(q.y).max(q.z).min(0.0);
+        outside + inside - radius
+    })
+}
+
+///
+/// Returns the distance function of a torus lying in the xz plane.
+///
+/// # Arguments
+/// * `center` - The torus's center.
+/// * `major_radius` - The distance from the center to the middle of the tube.
+/// * `minor_radius` - The radius of the tube.
+///
+pub fn torus(center: Point3, major_radius: f64, minor_radius: f64) -> DistanceFunction {
+    Box::new(move |p| {
+        let local = p - center;
+        let ring = (local.x * local.x + local.z * local.z).sqrt() - major_radius;
+        (ring * ring + local.y * local.y).sqrt() - minor_radius
+    })
+}
+
+///
+/// Returns the distance function of an infinite plane.
+///
+/// # Arguments
+/// * `normal` - The plane's normal. Does not need to be a unit vector.
+/// * `offset` - The plane's distance from the origin along its normal.
+///
+pub fn plane(normal: Vector3, offset: f64) -> DistanceFunction {
+    let n = normal.unit();
+    Box::new(move |p| p.dot(n) - offset)
+}
+
+///
+/// Returns the distance estimator of a Mandelbulb fractal centered on the origin.
+/// The estimate is a lower bound on the true distance, which is all sphere
+/// tracing needs.
+///
+/// # Arguments
+/// * `power` - The power of the fractal. 8 gives the classic Mandelbulb.
+/// * `iterations` - How many times the formula is iterated. More gives finer detail.
+///
+pub fn mandelbulb(power: f64, iterations: u32) -> DistanceFunction {
+    Box::new(move |p| {
+        let mut z = p;
+        let mut dr = 1.0;
+        let mut r = 0.0;
+
+        for _ in 0..iterations {
+            r = z.mag();
+            if 2.0 < r || r == 0.0 { break; }
+
+            let theta = (z.z / r).acos() * power;
+            let phi = z.y.atan2(z.x) * power;
+            let zr = r.powf(power);
+            dr = r.powf(power - 1.0) * power * dr + 1.0;
+
+            z = zr * Vector3::new(theta.sin() * phi.cos(), phi.sin() * theta.sin(), theta.cos()) + p;
+        }
+
+        if r == 0.0 { return 0.0; }
+        0.5 * r.ln() * r / dr
+    })
+}
+
+///
+/// Returns the union of two distance functions.
+///
+/// # Arguments
+/// * `a` - One of the distance functions.
+/// * `b` - One of the distance functions.
+///
+pub fn union(a: DistanceFunction, b: DistanceFunction) -> DistanceFunction {
+    Box::new(move |p| a(p).min(b(p)))
+}
+
+///
+/// Returns the intersection of two distance functions.
+///
+/// # Arguments
+/// * `a` - One of the distance functions.
+/// * `b` - One of the distance functions.
+///
+pub fn intersection(a: DistanceFunction, b: DistanceFunction) -> DistanceFunction {
+    Box::new(move |p| a(p).max(b(p)))
+}
+
+///
+/// Returns the distance function of a with b cut out of it.
+///
+/// # Arguments
+/// * `a` - The distance function that is cut.
+/// * `b` - The distance function that is removed.
+///
+pub fn difference(a: DistanceFunction, b: DistanceFunction) -> DistanceFunction {
+    Box::new(move |p| a(p).max(-b(p)))
+}
+
+///
+/// Returns the smooth minimum of two distances. Behaves like min, except the
+/// two values are blended together when they are within k of each other.
+///
+/// # Arguments
+/// * `a` - One of the distances.
+/// * `b` - One of the distances.
+/// * `k` - The size of the blended region.
+///
+pub fn smooth_min(a: f64, b: f64, k: f64) -> f64 {
+    if k <= 0.0 { return a.min(b); }
+    let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+    b + (a - b) * h - k * h * (1.0 - h)
+}
+
+///
+/// Returns the union of two distance functions with a smooth blend where
+/// they meet.
+///
+/// # Arguments
+/// * `a` - One of the distance functions.
+/// * `b` - One of the distance functions.
+/// * `k` - The size of the blended region.
+///
+pub fn smooth_union(a: DistanceFunction, b: DistanceFunction, k: f64) -> DistanceFunction {
+    Box::new(move |p| smooth_min(a(p), b(p), k))
+}
+
+///
+/// Returns the distance function of a with b smoothly cut out of it.
+///
+/// # Arguments
+/// * `a` - The distance function that is cut.
+/// * `b` - The distance function that is removed.
+/// * `k` - The size of the blended region.
+///
+pub fn smooth_difference(a: DistanceFunction, b: DistanceFunction, k: f64) -> DistanceFunction {
+    Box::new(move |p| -smooth_min(-a(p), b(p), k))
+}
+
+///
+/// Returns the given distance function moved by an offset.
+///
+/// # Arguments
+/// * `f` - The distance function.
+/// * `offset` - The translation.
+///
+pub fn translate(f: DistanceFunction, offset: Vector3) -> DistanceFunction {
+    Box::new(move |p| f(p - offset))
+}
+
+///
+/// Returns the given distance function uniformly scaled around the origin.
+///
+/// # Arguments
+/// * `f` - The distance function.
+/// * `factor` - The scale. Must be positive.
+///
+pub fn scale(f: DistanceFunction, factor: f64) -> DistanceFunction {
+    Box::new(move |p| f(p / factor) * factor)
+}
+
+///
+/// Returns the given distance function with its surface pushed outwards,
+/// rounding off any sharp edges.
+///
+/// # Arguments
+/// * `f` - The distance function.
+/// * `radius` - How far the surface is pushed.
+///
+pub fn round(f: DistanceFunction, radius: f64) -> DistanceFunction {
+    Box::new(move |p| f(p) - radius)
+}
+
+///
+/// Returns the given distance function repeated infinitely on a grid. The
+/// original should fit inside one cell of the grid, centered on the origin.
+/// An SdfShape's bounding box is what keeps the repetition finite.
+///
+/// # Arguments
+/// * `f` - The distance function.
+/// * `period` - The size of a grid cell along each axis. Zero disables repetition on that axis.
+///
+pub fn repeat(f: DistanceFunction, period: Vector3) -> DistanceFunction {
+    let wrap = |x: f64, period: f64| if period == 0.0 { x } else { x - period * (x / period).round() };
+    Box::new(move |p| f(Point3::new(wrap(p.x, period.x), wrap(p.y, period.y), wrap(p.z, period.z))))
+}
+
+///
+/// Returns the given distance function twisted around the y axis. The result
+/// is no longer an exact distance, so large twists may need a SdfShape with a
+/// smaller step scale.
+///
+/// # Arguments
+/// * `f` - The distance function.
+/// * `degrees_per_unit` - How far a point is rotated per unit of height.
+///
+pub fn twist(f: DistanceFunction, degrees_per_unit: f64) -> DistanceFunction {
+    Box::new(move |p| {
+        let (sin, cos) = (degrees_per_unit.to_radians() * p.y).sin_cos();
+        f(Point3::new(cos * p.x - sin * p.z, p.y, sin * p.x + cos * p.z))
+    })
+}
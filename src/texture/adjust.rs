@@ -0,0 +1,238 @@
+use crate::color::Color;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+
+/// Represents a texture that remaps another texture's channels from one range
+/// to another, like an image editor's levels. Values are clamped to the input
+/// range, bent by the gamma, then stretched over the output range.
+pub struct Levels {
+    /// The texture being remapped.
+    pub texture: Box<dyn Texture>,
+    /// The value that becomes output_min.
+    pub input_min: f64,
+    /// The value that becomes output_max.
+    pub input_max: f64,
+    /// Above 1 brightens the middle values, and below 1 darkens them.
+    pub gamma: f64,
+    /// The lowest value out.
+    pub output_min: f64,
+    /// The highest value out.
+    pub output_max: f64,
+}
+
+impl Levels {
+
+    ///
+    /// Returns a Levels texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `texture` - The Levels texture's texture field.
+    /// * `input_min` - The Levels texture's input_min field.
+    /// * `input_max` - The Levels texture's input_max field. Must be above input_min.
+    /// * `gamma` - The Levels texture's gamma field. Must be above 0.
+    /// * `output_min` - The Levels texture's output_min field.
+    /// * `output_max` - The Levels texture's output_max field.
+    ///
+    pub fn new(texture: Box<dyn Texture>, input_min: f64, input_max: f64, gamma: f64, output_min: f64, output_max: f64) -> Self {
+        assert!(input_min < input_max, "Levels needs input_min below input_max");
+        assert!(0.0 < gamma, "Levels needs a positive gamma");
+        Self {
+            texture: texture,
+            input_min: input_min,
+            input_max: input_max,
+            gamma: gamma,
+            output_min: output_min,
+            output_max: output_max,
+        }
+    }
+
+    ///
+    /// Returns a Levels texture that only stretches the texture's values from
+    /// [0, 1] to [min, max], such as to turn a noise texture into a range of
+    /// reflectivities.
+    ///
+    /// # Arguments
+    /// * `texture` - The texture being remapped.
+    /// * `min` - The value 0 becomes.
+    /// * `max` - The value 1 becomes.
+    ///
+    pub fn remap(texture: Box<dyn Texture>, min: f64, max: f64) -> Self {
+        Self::new(texture, 0.0, 1.0, 1.0, min, max)
+    }
+
+    ///
+    /// Returns the given channel value remapped.
+    ///
+    /// # Arguments
+    /// * `&self` - The Levels texture.
+    /// * `value` - The channel value.
+    ///
+    fn remap_value(&self, value: f64) -> f64 {
+        let t = ((value - self.input_min) / (self.input_max - self.input_min)).clamp(0.0, 1.0);
+        self.output_min + t.powf(1.0 / self.gamma) * (self.output_max - self.output_min)
+    }
+}
+
+impl Texture for Levels {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+        let color = self.texture.get_color(surface);
+        Color::new(self.remap_value(color.r), self.remap_value(color.g), self.remap_value(color.b))
+    }
+
+    ///
+    /// Returns the texture's value, remapped the same way as its channels.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the value exists.
+    ///
+    fn get_value(&self, surface: SurfacePoint) -> f64 {
+        self.remap_value(self.texture.get_value(surface))
+    }
+}
+
+/// Represents a texture that inverts another texture, so that 0 becomes 1
+/// and 1 becomes 0.
+pub struct Invert {
+    /// The texture being inverted.
+    pub texture: Box<dyn Texture>,
+}
+
+impl Invert {
+
+    ///
+    /// Returns an Invert texture constructed from the given argument.
+    ///
+    /// # Argument
+    /// * `texture` - The Invert texture's texture field.
+    ///
+    pub fn new(texture: Box<dyn Texture>) -> Self {
+        Self {
+            texture: texture,
+        }
+    }
+}
+
+impl Texture for Invert {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+        let color = self.texture.get_color(surface);
+        Color::new(1.0 - color.r, 1.0 - color.g, 1.0 - color.b)
+    }
+}
+
+/// Represents a texture that shifts another texture's hue and scales its
+/// saturation and value, working in HSV.
+pub struct HueSaturation {
+    /// The texture being adjusted.
+    pub texture: Box<dyn Texture>,
+    /// How far around the color wheel to turn the hue, in turns. 0.5 turns
+    /// every color into its complement.
+    pub hue_shift: f64,
+    /// What to multiply the saturation by. 0 gives greys, and the result is
+    /// capped at fully saturated.
+    pub saturation: f64,
+    /// What to multiply the value, or brightness, by.
+    pub value: f64,
+}
+
+impl HueSaturation {
+
+    ///
+    /// Returns a HueSaturation texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `texture` - The HueSaturation texture's texture field.
+    /// * `hue_shift` - The HueSaturation texture's hue_shift field.
+    /// * `saturation` - The HueSaturation texture's saturation field.
+    /// * `value` - The HueSaturation texture's value field.
+    ///
+    pub fn new(texture: Box<dyn Texture>, hue_shift: f64, saturation: f64, value: f64) -> Self {
+        Self {
+            texture: texture,
+            hue_shift: hue_shift,
+            saturation: saturation,
+            value: value,
+        }
+    }
+}
+
+///
+/// Returns the given Color as hue in turns between [0, 1), saturation and
+/// value.
+///
+/// # Arguments
+/// * `color` - The Color to convert.
+///
+fn to_hsv(color: Color) -> (f64, f64, f64) {
+    let max = color.r.max(color.g).max(color.b);
+    let min = color.r.min(color.g).min(color.b);
+    let chroma = max - min;
+
+    let hue = if chroma <= 0.0 {
+        0.0
+    } else if max == color.r {
+        ((color.g - color.b) / chroma).rem_euclid(6.0)
+    } else if max == color.g {
+        (color.b - color.r) / chroma + 2.0
+    } else {
+        (color.r - color.g) / chroma + 4.0
+    };
+    let saturation = if max <= 0.0 { 0.0 } else { chroma / max };
+
+    (hue / 6.0, saturation, max)
+}
+
+///
+/// Returns the Color with the given hue, saturation and value.
+///
+/// # Arguments
+/// * `hue` - The hue in turns. Wrapped into [0, 1).
+/// * `saturation` - The saturation.
+/// * `value` - The value.
+///
+fn from_hsv(hue: f64, saturation: f64, value: f64) -> Color {
+    let chroma = value * saturation;
+    let sector = hue.rem_euclid(1.0) * 6.0;
+    let x = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let min = value - chroma;
+    Color::new(r + min, g + min, b + min)
+}
+
+impl Texture for HueSaturation {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+        let (hue, saturation, value) = to_hsv(self.texture.get_color(surface));
+        from_hsv(hue + self.hue_shift, (saturation * self.saturation).min(1.0), value * self.value)
+    }
+}
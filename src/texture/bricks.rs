@@ -0,0 +1,79 @@
+use crate::color::Color;
+use crate::texture::get_cell_random;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+
+/// Represents a brick wall laid over a Hittable's uv coordinates: rows of
+/// bricks, each row shifted by half a brick, with mortar between them. It
+/// takes no Space, since the wall follows the uv coordinates rather than a
+/// point in space.
+#[derive(Clone, Copy)]
+pub struct Bricks {
+    /// The color of the bricks.
+    pub brick_color: Color,
+    /// The color of the mortar.
+    pub mortar_color: Color,
+    /// The number of bricks across the uv coordinates in each row. There are
+    /// twice as many rows, since bricks are twice as wide as they are tall.
+    pub scale: f64,
+    /// The width of the mortar, as a fraction of a brick's height.
+    pub mortar_width: f64,
+    /// How much the brightness of each brick is varied, between [0, 1].
+    pub variation: f64,
+    /// The seed that picks each brick's brightness.
+    pub seed: u32,
+}
+
+impl Bricks {
+
+    ///
+    /// Returns a Bricks texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `brick_color` - The texture's brick_color field.
+    /// * `mortar_color` - The texture's mortar_color field.
+    /// * `scale` - The texture's scale field.
+    /// * `mortar_width` - The texture's mortar_width field.
+    /// * `variation` - The texture's variation field.
+    /// * `seed` - The texture's seed field.
+    ///
+    pub fn new(brick_color: Color, mortar_color: Color, scale: f64, mortar_width: f64, variation: f64, seed: u32) -> Self {
+        Self {
+            brick_color: brick_color,
+            mortar_color: mortar_color,
+            scale: scale,
+            mortar_width: mortar_width,
+            variation: variation,
+            seed: seed,
+        }
+    }
+}
+
+impl Texture for Bricks {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+
+        // In brick heights, so a brick is 2 wide and 1 tall.
+        let y = surface.uv.1 * 2.0 * self.scale;
+        let row = y.floor();
+        let offset = if (row as i64).rem_euclid(2) == 0 { 0.0 } else { 1.0 };
+        let x = surface.uv.0 * 2.0 * self.scale + offset;
+        let column = (x / 2.0).floor();
+
+        // The distance from the point to the nearest edge of its brick.
+        let half_mortar = 0.5 * self.mortar_width;
+        let x_edge = (x - 2.0 * column).min(2.0 * column + 2.0 - x);
+        let y_edge = (y - row).min(row + 1.0 - y);
+        if x_edge < half_mortar || y_edge < half_mortar { return self.mortar_color; }
+
+        let brightness = 1.0 - self.variation * get_cell_random([column as i64, row as i64, 0], self.seed, 0);
+        self.brick_color * brightness
+    }
+}
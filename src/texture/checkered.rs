@@ -1,4 +1,5 @@
 use crate::color::Color;
+use crate::texture::SurfacePoint;
 use crate::texture::Texture;
 
 use std::f64::consts::PI;
@@ -31,23 +32,69 @@ impl Checkered {
     }
 }
 
+///
+/// Returns the fraction of [x - width / 2, x + width / 2] where floor(x) is
+/// odd, which is how much of a footprint of that width lands on odd squares.
+/// A width of zero gives the point sample, 0 or 1.
+///
+/// # Arguments
+/// * `x` - The center of the footprint, in squares.
+/// * `width` - The width of the footprint, in squares.
+///
+pub fn get_odd_fraction(x: f64, width: f64) -> f64 {
+
+    if width <= 0.0 {
+        return if x.floor().rem_euclid(2.0) == 1.0 { 1.0 } else { 0.0 };
+    }
+
+    // The integral of the odd squares from 0 to x.
+    let integral = |x: f64| {
+        let half = 0.5 * x;
+        half.floor() + 2.0 * (half - half.floor() - 0.5).max(0.0)
+    };
+    (integral(x + 0.5 * width) - integral(x - 0.5 * width)) / width
+}
+
 impl Texture for Checkered {
 
     ///
     /// Returns the Color value for the texture using the given arguments.
+    /// Squares smaller than the surface's footprint are blended together
+    /// rather than point sampled.
     ///
     /// # Arguments
     /// * `&self` - The texture.
-    /// * `point` - The point in space where the color exists.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
     ///
-    fn get_color(&self, uv: (f64, f64)) -> Color {
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+
         let scale = (1.0 / self.scale) * 20.0;
-        let u = (uv.0 * (scale * PI)).sin();
-        let v = (uv.1 * (scale * PI)).sin();
-        if (u < 0.0 && 0.0 < v) || (0.0 < u && v < 0.0) {
-            self.texture1.get_color(uv)
+        let footprint = surface.footprint;
+
+        if footprint.get_uv_width() <= 0.0 {
+            let u = (surface.uv.0 * (scale * PI)).sin();
+            let v = (surface.uv.1 * (scale * PI)).sin();
+            return if (u < 0.0 && 0.0 < v) || (0.0 < u && v < 0.0) {
+                self.texture1.get_color(surface)
+            } else {
+                self.texture2.get_color(surface)
+            };
+        }
+
+        // The sine is negative on the odd squares, and texture1 shows where
+        // exactly one of u and v is on an odd square.
+        let u_width = footprint.duvdx.0.abs().max(footprint.duvdy.0.abs()) * scale;
+        let v_width = footprint.duvdx.1.abs().max(footprint.duvdy.1.abs()) * scale;
+        let u_odd = get_odd_fraction(surface.uv.0 * scale, u_width);
+        let v_odd = get_odd_fraction(surface.uv.1 * scale, v_width);
+        let t = u_odd + v_odd - 2.0 * u_odd * v_odd;
+
+        if t <= 0.0 {
+            self.texture2.get_color(surface)
+        } else if 1.0 <= t {
+            self.texture1.get_color(surface)
         } else {
-            self.texture2.get_color(uv)
+            t * self.texture1.get_color(surface) + (1.0 - t) * self.texture2.get_color(surface)
         }
     }
 }
\ No newline at end of file
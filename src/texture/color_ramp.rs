@@ -0,0 +1,61 @@
+use crate::color::Color;
+
+/// Represents a color ramp: a list of colors at positions between [0, 1],
+/// blended linearly between them. Procedural textures use one to turn a
+/// value, such as a distance or an amount of noise, into a color. It takes no
+/// Space or seed, since it maps a value rather than a point on a surface.
+#[derive(Clone)]
+pub struct ColorRamp {
+    /// The ramp's (position, color) stops, sorted by position.
+    stops: Vec<(f64, Color)>,
+}
+
+impl ColorRamp {
+
+    ///
+    /// Returns a ColorRamp constructed from the given stops, which do not need
+    /// to be sorted.
+    ///
+    /// # Arguments
+    /// * `stops` - The ramp's (position, color) stops. Must not be empty.
+    ///
+    pub fn new(stops: Vec<(f64, Color)>) -> Self {
+        assert!(!stops.is_empty(), "a ColorRamp needs at least one stop");
+        let mut stops = stops;
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            stops: stops,
+        }
+    }
+
+    ///
+    /// Returns a ColorRamp that blends from one color to another.
+    ///
+    /// # Arguments
+    /// * `start` - The color at 0.
+    /// * `end` - The color at 1.
+    ///
+    pub fn from_colors(start: Color, end: Color) -> Self {
+        Self::new(vec![(0.0, start), (1.0, end)])
+    }
+
+    ///
+    /// Returns the ColorRamp's color at the given position. Positions before
+    /// the first stop or after the last one get that stop's color.
+    ///
+    /// # Arguments
+    /// * `&self` - The ColorRamp.
+    /// * `t` - The position along the ramp.
+    ///
+    pub fn get_color(&self, t: f64) -> Color {
+
+        let next = self.stops.partition_point(|&(position, _)| position <= t);
+        if next == 0 { return self.stops[0].1; }
+        if next == self.stops.len() { return self.stops[next - 1].1; }
+
+        let (start, start_color) = self.stops[next - 1];
+        let (end, end_color) = self.stops[next];
+        let s = (t - start) / (end - start);
+        (1.0 - s) * start_color + s * end_color
+    }
+}
@@ -0,0 +1,135 @@
+use crate::color::Color;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+
+/// Represents a texture that adds two textures together.
+pub struct Add {
+    /// The first texture.
+    pub texture1: Box<dyn Texture>,
+    /// The second texture.
+    pub texture2: Box<dyn Texture>,
+}
+
+impl Add {
+
+    ///
+    /// Returns an Add texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `texture1` - The Add texture's texture1 field.
+    /// * `texture2` - The Add texture's texture2 field.
+    ///
+    pub fn new(texture1: Box<dyn Texture>, texture2: Box<dyn Texture>) -> Self {
+        Self {
+            texture1: texture1,
+            texture2: texture2,
+        }
+    }
+}
+
+impl Texture for Add {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+        self.texture1.get_color(surface) + self.texture2.get_color(surface)
+    }
+}
+
+/// Represents a texture that multiplies two textures together, channel by
+/// channel. Useful for tinting, or for darkening one texture with another.
+pub struct Multiply {
+    /// The first texture.
+    pub texture1: Box<dyn Texture>,
+    /// The second texture.
+    pub texture2: Box<dyn Texture>,
+}
+
+impl Multiply {
+
+    ///
+    /// Returns a Multiply texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `texture1` - The Multiply texture's texture1 field.
+    /// * `texture2` - The Multiply texture's texture2 field.
+    ///
+    pub fn new(texture1: Box<dyn Texture>, texture2: Box<dyn Texture>) -> Self {
+        Self {
+            texture1: texture1,
+            texture2: texture2,
+        }
+    }
+}
+
+impl Texture for Multiply {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+        self.texture1.get_color(surface) * self.texture2.get_color(surface)
+    }
+}
+
+/// Represents a texture that blends between two textures using a third as a
+/// mask.
+pub struct Mix {
+    /// The texture where the mask is 0.
+    pub texture1: Box<dyn Texture>,
+    /// The texture where the mask is 1.
+    pub texture2: Box<dyn Texture>,
+    /// The mask, read with Texture::get_value and clamped to [0, 1].
+    pub mask: Box<dyn Texture>,
+}
+
+impl Mix {
+
+    ///
+    /// Returns a Mix texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `texture1` - The Mix texture's texture1 field.
+    /// * `texture2` - The Mix texture's texture2 field.
+    /// * `mask` - The Mix texture's mask field.
+    ///
+    pub fn new(texture1: Box<dyn Texture>, texture2: Box<dyn Texture>, mask: Box<dyn Texture>) -> Self {
+        Self {
+            texture1: texture1,
+            texture2: texture2,
+            mask: mask,
+        }
+    }
+}
+
+impl Texture for Mix {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+
+        // Only the textures that show are looked up.
+        let t = self.mask.get_value(surface).clamp(0.0, 1.0);
+        if t <= 0.0 {
+            self.texture1.get_color(surface)
+        } else if 1.0 <= t {
+            self.texture2.get_color(surface)
+        } else {
+            (1.0 - t) * self.texture1.get_color(surface) + t * self.texture2.get_color(surface)
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::color::Color;
+use crate::texture::color_ramp::ColorRamp;
+use crate::texture::Space;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+use crate::vector3::Vector3;
+
+/// Represents a gradient texture: a ColorRamp laid out along a direction in
+/// space, such as a sky that fades from the horizon up, or a shape that fades
+/// from one end to the other. Unlike the noise textures it takes no seed,
+/// since nothing about it is random.
+pub struct Gradient {
+    /// The colors of the gradient.
+    pub ramp: ColorRamp,
+    /// The unit vector the gradient runs along.
+    pub direction: Vector3,
+    /// The number of times the gradient runs per unit of distance along the
+    /// direction, starting from the origin of the space.
+    pub scale: f64,
+    /// If the gradient starts over every 1 / scale units, rather than stopping.
+    pub is_repeating: bool,
+    /// The space the gradient is laid out in.
+    pub space: Space,
+}
+
+impl Gradient {
+
+    ///
+    /// Returns a Gradient texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `ramp` - The texture's ramp field.
+    /// * `direction` - The texture's direction field. Does not need to be a unit vector.
+    /// * `scale` - The texture's scale field.
+    /// * `is_repeating` - The texture's is_repeating field.
+    /// * `space` - The texture's space field.
+    ///
+    pub fn new(ramp: ColorRamp, direction: Vector3, scale: f64, is_repeating: bool, space: Space) -> Self {
+        Self {
+            ramp: ramp,
+            direction: direction.unit(),
+            scale: scale,
+            is_repeating: is_repeating,
+            space: space,
+        }
+    }
+}
+
+impl Texture for Gradient {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+        let t = surface.get_point(self.space).dot(self.direction) * self.scale;
+        self.ramp.get_color(if self.is_repeating { t.rem_euclid(1.0) } else { t })
+    }
+}
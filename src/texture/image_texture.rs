@@ -0,0 +1,632 @@
+use crate::color::Color;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+
+use image::codecs::hdr::HdrDecoder;
+use image::ImageError;
+use image::ImageFormat;
+use image::io::Reader;
+use image::Rgb;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+
+/// How an ImageTexture blends the texels around the point being looked up.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Filter {
+    /// The single closest texel. Blocky up close, but shows the exact pixels.
+    Nearest,
+    /// A weighted blend of the four closest texels.
+    Bilinear,
+    /// A Catmull-Rom blend of the sixteen closest texels. Smoother than
+    /// bilinear up close, at four times the cost.
+    Bicubic,
+    /// A bilinear blend on the two mipmap levels closest to the size of the
+    /// footprint, blended together. Doesn't alias in the distance, but blurs
+    /// surfaces seen at an angle.
+    Trilinear,
+    /// An elliptical weighted average over the footprint's ellipse on the
+    /// mipmap. Keeps surfaces seen at an angle sharp, at a higher cost.
+    Ewa,
+}
+
+/// What an ImageTexture does with texture coordinates outside of [0, 1].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WrapMode {
+    /// The image is tiled.
+    Repeat,
+    /// The image's edge texels are stretched outwards.
+    Clamp,
+    /// The image is tiled, with every other tile flipped so that tiles meet
+    /// without a seam.
+    Mirror,
+}
+
+/// Every Filter, in the order they are listed on the command line.
+pub const FILTERS: [Filter; 5] = [Filter::Nearest, Filter::Bilinear, Filter::Bicubic, Filter::Trilinear, Filter::Ewa];
+
+/// Every WrapMode, in the order they are listed on the command line.
+pub const WRAP_MODES: [WrapMode; 3] = [WrapMode::Repeat, WrapMode::Clamp, WrapMode::Mirror];
+
+impl Filter {
+
+    ///
+    /// Returns the Filter with the given name, as returned by get_name, or
+    /// None if there is no such Filter.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the Filter.
+    ///
+    pub fn from_name(name: &str) -> Option<Self> {
+        FILTERS.into_iter().find(|filter| filter.get_name() == name)
+    }
+
+    ///
+    /// Returns the name of the Filter, as used on the command line.
+    ///
+    /// # Arguments
+    /// * `&self` - The Filter.
+    ///
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Filter::Nearest => "nearest",
+            Filter::Bilinear => "bilinear",
+            Filter::Bicubic => "bicubic",
+            Filter::Trilinear => "trilinear",
+            Filter::Ewa => "ewa",
+        }
+    }
+}
+
+impl WrapMode {
+
+    ///
+    /// Returns the WrapMode with the given name, as returned by get_name, or
+    /// None if there is no such WrapMode.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the WrapMode.
+    ///
+    pub fn from_name(name: &str) -> Option<Self> {
+        WRAP_MODES.into_iter().find(|wrap_mode| wrap_mode.get_name() == name)
+    }
+
+    ///
+    /// Returns the name of the WrapMode, as used on the command line.
+    ///
+    /// # Arguments
+    /// * `&self` - The WrapMode.
+    ///
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            WrapMode::Repeat => "repeat",
+            WrapMode::Clamp => "clamp",
+            WrapMode::Mirror => "mirror",
+        }
+    }
+}
+
+/// The most times longer than it is wide an Ewa filter's ellipse may be. Longer
+/// ellipses are widened, which blurs them a little but bounds their cost.
+const MAX_ANISOTROPY: f64 = 8.0;
+
+/// One level of an ImageTexture's mipmap: the image shrunk to half the width
+/// and height of the level before it.
+pub struct MipLevel {
+    /// The level's texels, row by row from the top left, in linear color.
+    pub texels: Vec<Color>,
+    /// The level's width in texels.
+    pub width: usize,
+    /// The level's height in texels.
+    pub height: usize,
+}
+
+/// Represents a texture that looks its colors up in an image.
+pub struct ImageTexture {
+    /// The image's mipmap, from the full size image down to a single texel.
+    pub levels: Vec<MipLevel>,
+    /// How texels are blended.
+    pub filter: Filter,
+    /// What happens outside of the image.
+    pub wrap_mode: WrapMode,
+}
+
+impl ImageTexture {
+
+    ///
+    /// Returns an ImageTexture constructed from the given arguments, with its
+    /// mipmap built from the texels.
+    ///
+    /// # Arguments
+    /// * `texels` - The full size image's texels, row by row from the top left. Must hold width * height texels.
+    /// * `width` - The full size image's width.
+    /// * `height` - The full size image's height.
+    /// * `filter` - The ImageTexture's filter field.
+    /// * `wrap_mode` - The ImageTexture's wrap_mode field.
+    ///
+    pub fn new(texels: Vec<Color>, width: usize, height: usize, filter: Filter, wrap_mode: WrapMode) -> Self {
+
+        assert!(0 < width && 0 < height, "an ImageTexture needs at least one texel");
+        assert_eq!(texels.len(), width * height, "an ImageTexture needs width * height texels");
+
+        let mut levels = vec![MipLevel { texels: texels, width: width, height: height }];
+        while let Some(level) = levels.last().and_then(get_next_mip_level) {
+            levels.push(level);
+        }
+
+        Self {
+            levels: levels,
+            filter: filter,
+            wrap_mode: wrap_mode,
+        }
+    }
+
+    ///
+    /// Returns an ImageTexture loaded from a PNG, JPEG, PPM/PGM or Radiance
+    /// HDR file. The format is worked out from the file's contents.
+    ///
+    /// Color maps, like a photo or a painted albedo, are stored in sRGB and
+    /// should be decoded to linear color before they are lit. Data maps, like
+    /// a roughness map, are already linear and should not be. HDR files are
+    /// always linear, so is_srgb is ignored for them.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the image file.
+    /// * `is_srgb` - If the file's colors are sRGB encoded.
+    /// * `filter` - How texels are blended.
+    /// * `wrap_mode` - What happens outside of the image.
+    ///
+    pub fn from_file(path: &str, is_srgb: bool, filter: Filter, wrap_mode: WrapMode) -> io::Result<Self> {
+
+        let to_io_error = |e: ImageError| io::Error::new(io::ErrorKind::InvalidData, e);
+        let to_color = |pixel: &Rgb<f32>| Color::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64);
+
+        let reader = Reader::open(path)?.with_guessed_format()?;
+
+        let (texels, width, height): (Vec<Color>, usize, usize) = if reader.format() == Some(ImageFormat::Hdr) {
+            // Decoding an HDR file like any other image squashes it down to 8
+            // bits, so its floats are read directly instead.
+            let decoder = HdrDecoder::new(BufReader::new(File::open(path)?)).map_err(to_io_error)?;
+            let metadata = decoder.metadata();
+            let pixels = decoder.read_image_hdr().map_err(to_io_error)?;
+            (pixels.iter().map(to_color).collect(), metadata.width as usize, metadata.height as usize)
+        } else {
+            let image = reader.decode().map_err(to_io_error)?.into_rgb32f();
+            let texels = image.pixels().map(|pixel| {
+                if is_srgb { to_color(pixel).srgb_to_linear() } else { to_color(pixel) }
+            }).collect();
+            (texels, image.width() as usize, image.height() as usize)
+        };
+
+        if width == 0 || height == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "image has no pixels"));
+        }
+
+        Ok(Self::new(texels, width, height, filter, wrap_mode))
+    }
+
+    ///
+    /// Returns an ImageTexture of the alpha channel of an image file, as grey
+    /// texels, for use as an OpacityMask. Images without an alpha channel are
+    /// opaque everywhere.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the image file.
+    /// * `filter` - How texels are blended.
+    /// * `wrap_mode` - What happens outside of the image.
+    ///
+    pub fn alpha_from_file(path: &str, filter: Filter, wrap_mode: WrapMode) -> io::Result<Self> {
+
+        let to_io_error = |e: ImageError| io::Error::new(io::ErrorKind::InvalidData, e);
+
+        let image = Reader::open(path)?.with_guessed_format()?.decode().map_err(to_io_error)?.into_rgba32f();
+        let texels = image.pixels().map(|pixel| {
+            let alpha = pixel[3] as f64;
+            Color::new(alpha, alpha, alpha)
+        }).collect();
+
+        if image.width() == 0 || image.height() == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "image has no pixels"));
+        }
+
+        Ok(Self::new(texels, image.width() as usize, image.height() as usize, filter, wrap_mode))
+    }
+
+    ///
+    /// Returns the texel at the given column and row of a mipmap level, which
+    /// are wrapped into the level first.
+    ///
+    /// # Arguments
+    /// * `&self` - The ImageTexture.
+    /// * `level` - The mipmap level, where 0 is the full size image.
+    /// * `x` - The texel's column, counting from the left.
+    /// * `y` - The texel's row, counting from the top.
+    ///
+    fn get_texel(&self, level: usize, x: i64, y: i64) -> Color {
+        let level = &self.levels[level];
+        let x = wrap(x, level.width, self.wrap_mode);
+        let y = wrap(y, level.height, self.wrap_mode);
+        level.texels[y * level.width + x]
+    }
+
+    ///
+    /// Returns the bilinear blend of the four texels of a mipmap level closest
+    /// to the given uv coordinates.
+    ///
+    /// # Arguments
+    /// * `&self` - The ImageTexture.
+    /// * `level` - The mipmap level, where 0 is the full size image.
+    /// * `uv` - The uv coordinates.
+    ///
+    fn get_bilinear(&self, level: usize, uv: (f64, f64)) -> Color {
+        let (x, y) = self.to_texels(level, uv);
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = (1.0 - tx) * self.get_texel(level, x0, y0) + tx * self.get_texel(level, x0 + 1, y0);
+        let bottom = (1.0 - tx) * self.get_texel(level, x0, y0 + 1) + tx * self.get_texel(level, x0 + 1, y0 + 1);
+        (1.0 - ty) * top + ty * bottom
+    }
+
+    ///
+    /// Returns the elliptical weighted average of the texels of a mipmap level
+    /// under the ellipse with the given center and axes.
+    ///
+    /// # Arguments
+    /// * `&self` - The ImageTexture.
+    /// * `level` - The mipmap level, where 0 is the full size image.
+    /// * `uv` - The ellipse's center, in uv coordinates.
+    /// * `axis1` - One of the ellipse's axes, in uv coordinates.
+    /// * `axis2` - The other of the ellipse's axes, in uv coordinates.
+    ///
+    fn get_ewa(&self, level: usize, uv: (f64, f64), axis1: (f64, f64), axis2: (f64, f64)) -> Color {
+
+        let (width, height) = (self.levels[level].width as f64, self.levels[level].height as f64);
+        let (x, y) = self.to_texels(level, uv);
+        let (x, y) = (x - 0.5, y - 0.5);
+
+        // v runs up the image while rows run down it.
+        let (ax1, ay1) = (axis1.0 * width, -axis1.1 * height);
+        let (ax2, ay2) = (axis2.0 * width, -axis2.1 * height);
+
+        // The ellipse as the implicit a * x^2 + b * x * y + c * y^2 < 1. The
+        // ones keep it from shrinking below a texel.
+        let mut a = ay1 * ay1 + ay2 * ay2 + 1.0;
+        let mut b = -2.0 * (ax1 * ay1 + ax2 * ay2);
+        let mut c = ax1 * ax1 + ax2 * ax2 + 1.0;
+        let scale = 1.0 / (a * c - b * b * 0.25);
+        a *= scale;
+        b *= scale;
+        c *= scale;
+
+        // The ellipse's bounding box, in texels.
+        let determinant = 4.0 * a * c - b * b;
+        let x_extent = 2.0 * (determinant * c).sqrt() / determinant;
+        let y_extent = 2.0 * (determinant * a).sqrt() / determinant;
+        let (x0, x1) = ((x - x_extent).ceil() as i64, (x + x_extent).floor() as i64);
+        let (y0, y1) = ((y - y_extent).ceil() as i64, (y + y_extent).floor() as i64);
+
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        let mut total_weight = 0.0;
+        for row in y0..=y1 {
+            let dy = row as f64 - y;
+            for column in x0..=x1 {
+                let dx = column as f64 - x;
+                let r2 = a * dx * dx + b * dx * dy + c * dy * dy;
+                if r2 < 1.0 {
+                    // A Gaussian, shifted down to reach zero at the ellipse's edge.
+                    let weight = (-2.0 * r2).exp() - (-2.0_f64).exp();
+                    color += weight * self.get_texel(level, column, row);
+                    total_weight += weight;
+                }
+            }
+        }
+
+        if total_weight <= 0.0 { return self.get_bilinear(level, uv); }
+        color / total_weight
+    }
+
+    ///
+    /// Returns the given uv coordinates in texels of a mipmap level, from the
+    /// top left.
+    ///
+    /// # Arguments
+    /// * `&self` - The ImageTexture.
+    /// * `level` - The mipmap level, where 0 is the full size image.
+    /// * `uv` - The uv coordinates.
+    ///
+    fn to_texels(&self, level: usize, uv: (f64, f64)) -> (f64, f64) {
+        let level = &self.levels[level];
+        (uv.0 * level.width as f64, (1.0 - uv.1) * level.height as f64)
+    }
+
+    ///
+    /// Returns the mipmap level whose texels are the given width, as a
+    /// fraction between two levels, clamped to the levels there are.
+    ///
+    /// # Arguments
+    /// * `&self` - The ImageTexture.
+    /// * `width` - The width, in texels of the full size image.
+    ///
+    fn get_level(&self, width: f64) -> f64 {
+        width.max(1.0).log2().min((self.levels.len() - 1) as f64)
+    }
+
+    ///
+    /// Returns a blend of a lookup on the two mipmap levels either side of the
+    /// given fractional level.
+    ///
+    /// # Arguments
+    /// * `&self` - The ImageTexture.
+    /// * `level` - The fractional mipmap level.
+    /// * `lookup` - Returns the color of the lookup on one level.
+    ///
+    fn blend_levels(&self, level: f64, lookup: impl Fn(usize) -> Color) -> Color {
+        let lower = level.floor() as usize;
+        let t = level - lower as f64;
+        if t <= 0.0 || lower + 1 == self.levels.len() {
+            lookup(lower)
+        } else {
+            (1.0 - t) * lookup(lower) + t * lookup(lower + 1)
+        }
+    }
+}
+
+///
+/// Returns the mipmap level after the given one, with half its width and
+/// height, or None if the given level is a single texel. Each texel is the
+/// average of the texels it covers on the level before, so odd sizes round
+/// down and average three texels instead of two along that axis.
+///
+/// # Arguments
+/// * `level` - The level to shrink.
+///
+fn get_next_mip_level(level: &MipLevel) -> Option<MipLevel> {
+
+    if level.width == 1 && level.height == 1 { return None; }
+
+    let width = (level.width / 2).max(1);
+    let height = (level.height / 2).max(1);
+    let mut texels = Vec::with_capacity(width * height);
+
+    for y in 0..height {
+        let (y0, y1) = (y * level.height / height, ((y + 1) * level.height).div_ceil(height));
+        for x in 0..width {
+            let (x0, x1) = (x * level.width / width, ((x + 1) * level.width).div_ceil(width));
+            let mut color = Color::new(0.0, 0.0, 0.0);
+            for row in y0..y1 {
+                for column in x0..x1 {
+                    color += level.texels[row * level.width + column];
+                }
+            }
+            texels.push(color / ((y1 - y0) * (x1 - x0)) as f64);
+        }
+    }
+
+    Some(MipLevel { texels: texels, width: width, height: height })
+}
+
+///
+/// Returns the given texel index moved into [0, size) by the given WrapMode.
+///
+/// # Arguments
+/// * `index` - The texel index, which may be outside of the image.
+/// * `size` - The number of texels along the axis.
+/// * `wrap_mode` - How to move the index into the image.
+///
+fn wrap(index: i64, size: usize, wrap_mode: WrapMode) -> usize {
+    let size = size as i64;
+    let wrapped = match wrap_mode {
+        WrapMode::Repeat => index.rem_euclid(size),
+        WrapMode::Clamp => index.clamp(0, size - 1),
+        WrapMode::Mirror => {
+            let period = index.rem_euclid(2 * size);
+            if period < size { period } else { 2 * size - 1 - period }
+        },
+    };
+    wrapped as usize
+}
+
+///
+/// Returns the Catmull-Rom weights of the four texels around a point, given
+/// how far the point is past the second of them.
+///
+/// # Arguments
+/// * `t` - How far the point is past the second texel, between [0, 1].
+///
+fn get_cubic_weights(t: f64) -> [f64; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ]
+}
+
+impl Texture for ImageTexture {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments. u
+    /// goes from the left of the image to the right, and v goes from the
+    /// bottom to the top. Only the Trilinear and Ewa filters use the surface's
+    /// footprint, and the others always look up the full size image.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+
+        // The point in texels from the top left. Texel centers sit half a
+        // texel in from the edges, so the blending filters shift by half.
+        let (x, y) = self.to_texels(0, surface.uv);
+        let (width, height) = (self.levels[0].width as f64, self.levels[0].height as f64);
+        let footprint = surface.footprint;
+
+        match self.filter {
+
+            Filter::Nearest => self.get_texel(0, x.floor() as i64, y.floor() as i64),
+
+            Filter::Bilinear => self.get_bilinear(0, surface.uv),
+
+            Filter::Trilinear => {
+                let x_width = (footprint.duvdx.0 * width).hypot(footprint.duvdx.1 * height);
+                let y_width = (footprint.duvdy.0 * width).hypot(footprint.duvdy.1 * height);
+                let level = self.get_level(x_width.max(y_width));
+                self.blend_levels(level, |level| self.get_bilinear(level, surface.uv))
+            },
+
+            Filter::Ewa => {
+                let x_width = (footprint.duvdx.0 * width).hypot(footprint.duvdx.1 * height);
+                let y_width = (footprint.duvdy.0 * width).hypot(footprint.duvdy.1 * height);
+                let (mut major, mut minor) = (footprint.duvdx, footprint.duvdy);
+                let (mut major_width, mut minor_width) = if x_width < y_width {
+                    std::mem::swap(&mut major, &mut minor);
+                    (y_width, x_width)
+                } else {
+                    (x_width, y_width)
+                };
+                if major_width <= 0.0 { return self.get_bilinear(0, surface.uv); }
+
+                // At grazing angles the footprint can stretch without bound.
+                // Past the size of the image, it would only wrap around it again
+                // at the cost of more texels, so it is shrunk to fit. One that
+                // isn't finite is just the average of the image, the last level.
+                if !major_width.is_finite() {
+                    return self.get_bilinear(self.levels.len() - 1, surface.uv);
+                }
+                let max_width = width.max(height);
+                if max_width < major_width {
+                    let scale = max_width / major_width;
+                    major = (major.0 * scale, major.1 * scale);
+                    minor = (minor.0 * scale, minor.1 * scale);
+                    major_width = max_width;
+                    minor_width *= scale;
+                }
+
+                // Very long ellipses cover too many texels, so they are widened.
+                // A flat one first gets a minor axis at a right angle.
+                if minor_width * MAX_ANISOTROPY < major_width {
+                    if minor_width <= 0.0 {
+                        minor = (-major.1 * height / width, major.0 * width / height);
+                        minor_width = (minor.0 * width).hypot(minor.1 * height);
+                    }
+                    let scale = major_width / (MAX_ANISOTROPY * minor_width);
+                    minor = (minor.0 * scale, minor.1 * scale);
+                    minor_width = major_width / MAX_ANISOTROPY;
+                }
+
+                // The level is picked by the minor axis, so the ellipse covers a
+                // few texels across and the major axis keeps its detail.
+                let level = self.get_level(minor_width);
+                self.blend_levels(level, |level| self.get_ewa(level, surface.uv, major, minor))
+            },
+
+            Filter::Bicubic => {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let x_weights = get_cubic_weights(x - x0);
+                let y_weights = get_cubic_weights(y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let mut color = Color::new(0.0, 0.0, 0.0);
+                for (j, y_weight) in y_weights.into_iter().enumerate() {
+                    for (i, x_weight) in x_weights.into_iter().enumerate() {
+                        color += x_weight * y_weight * self.get_texel(0, x0 + i as i64 - 1, y0 + j as i64 - 1);
+                    }
+                }
+
+                // Catmull-Rom overshoots next to sharp edges, which can push a
+                // channel below zero.
+                Color::new(color.r.max(0.0), color.g.max(0.0), color.b.max(0.0))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::vector3::Point3;
+    use crate::vector3::Vector3;
+
+    /// Returns a 4 by 2 texture whose texels are greys numbered 0 to 7, row by
+    /// row from the top left.
+    fn get_texture(filter: Filter, wrap_mode: WrapMode) -> ImageTexture {
+        let texels = (0..8).map(|i| Color::new(i as f64, i as f64, i as f64)).collect();
+        ImageTexture::new(texels, 4, 2, filter, wrap_mode)
+    }
+
+    fn sample(texture: &ImageTexture, uv: (f64, f64)) -> f64 {
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        texture.get_color(SurfacePoint::new(uv, origin, origin, Vector3::new(0.0, 0.0, 1.0))).r
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for filter in FILTERS {
+            assert_eq!(Filter::from_name(filter.get_name()), Some(filter));
+        }
+        for wrap_mode in WRAP_MODES {
+            assert_eq!(WrapMode::from_name(wrap_mode.get_name()), Some(wrap_mode));
+        }
+        assert_eq!(Filter::from_name("box"), None);
+    }
+
+    #[test]
+    fn texel_centres_give_the_texel() {
+        for filter in [Filter::Nearest, Filter::Bilinear, Filter::Bicubic] {
+            for wrap_mode in WRAP_MODES {
+                let texture = get_texture(filter, wrap_mode);
+                for i in 0..8 {
+                    let uv = ((i % 4) as f64 / 4.0 + 0.125, 0.75 - (i / 4) as f64 * 0.5);
+                    assert!((sample(&texture, uv) - i as f64).abs() < 1e-9, "{:?} {:?} texel {}", filter, wrap_mode, i);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn edges_blend_with_the_wrapped_texels() {
+        // The left and right edges of the top row sit halfway between a texel
+        // inside the image and one wrapped in from outside.
+        for (wrap_mode, left, right) in [(WrapMode::Repeat, 1.5, 1.5), (WrapMode::Clamp, 0.0, 3.0), (WrapMode::Mirror, 0.0, 3.0)] {
+            let texture = get_texture(Filter::Bilinear, wrap_mode);
+            assert!((sample(&texture, (0.0, 0.75)) - left).abs() < 1e-9, "{:?}", wrap_mode);
+            assert!((sample(&texture, (1.0, 0.75)) - right).abs() < 1e-9, "{:?}", wrap_mode);
+        }
+    }
+
+    #[test]
+    fn texels_outside_the_image_are_wrapped() {
+        // One and two texels past each side of the top row.
+        for (wrap_mode, expected) in [
+            (WrapMode::Repeat, [3.0, 2.0, 0.0, 1.0]),
+            (WrapMode::Clamp, [0.0, 0.0, 3.0, 3.0]),
+            (WrapMode::Mirror, [0.0, 1.0, 3.0, 2.0]),
+        ] {
+            let texture = get_texture(Filter::Nearest, wrap_mode);
+            for (u, expected) in [-0.125, -0.375, 1.125, 1.375].into_iter().zip(expected) {
+                assert_eq!(sample(&texture, (u, 0.75)), expected, "{:?} at u = {}", wrap_mode, u);
+            }
+        }
+    }
+
+    #[test]
+    fn grazing_footprints_average_the_image() {
+        // Footprints far longer than the image would take forever to filter
+        // if they weren't shrunk to fit it first.
+        let texture = get_texture(Filter::Ewa, WrapMode::Repeat);
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        for length in [1e12, f64::INFINITY] {
+            let mut surface = SurfacePoint::new((0.5, 0.5), origin, origin, Vector3::new(0.0, 0.0, 1.0));
+            surface.footprint.duvdx = (length, 0.0);
+            surface.footprint.duvdy = (0.0, 1e-3);
+            assert!((texture.get_color(surface).r - 3.5).abs() < 1e-9, "footprint {} long", length);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::color::Color;
+use crate::texture::solid_noise::get_turbulence;
+use crate::texture::Space;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+
+use noise::Perlin;
+
+/// The number of layers of noise used to bend the marble's veins.
+const MARBLE_LAYERS: u32 = 7;
+
+/// Represents a marble texture: bands of color running through 3D space along
+/// the z axis, bent into veins by turbulent noise.
+#[derive(Clone, Copy)]
+pub struct Marble {
+    /// The perlin noise that bends the veins.
+    pub perlin: Perlin,
+    /// The marble's scale. Larger values create narrower bands.
+    pub scale: f64,
+    /// How strongly the noise bends the veins. Zero gives straight stripes.
+    pub turbulence: f64,
+    /// The color of the veins.
+    pub vein_color: Color,
+    /// The color between the veins.
+    pub base_color: Color,
+    /// The space the marble is laid out in.
+    pub space: Space,
+}
+
+impl Marble {
+
+    ///
+    /// Returns a Marble texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `seed` - The seed of the texture's perlin noise.
+    /// * `scale` - The texture's scale field.
+    /// * `turbulence` - The texture's turbulence field.
+    /// * `vein_color` - The texture's vein_color field.
+    /// * `base_color` - The texture's base_color field.
+    /// * `space` - The texture's space field.
+    ///
+    pub fn new(seed: u32, scale: f64, turbulence: f64, vein_color: Color, base_color: Color, space: Space) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale: scale,
+            turbulence: turbulence,
+            vein_color: vein_color,
+            base_color: base_color,
+            space: space,
+        }
+    }
+}
+
+impl Texture for Marble {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+        let point = surface.get_point(self.space) * self.scale;
+        let footprint_width = surface.footprint.get_width() * self.scale;
+        let phase = point.z + self.turbulence * get_turbulence(&self.perlin, point, MARBLE_LAYERS, footprint_width);
+        let t = 0.5 * (1.0 + phase.sin());
+        t * self.base_color + (1.0 - t) * self.vein_color
+    }
+}
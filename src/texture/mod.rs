@@ -1,8 +1,380 @@
 pub mod monochrome;
 pub mod checkered;
 pub mod noisy;
+pub mod image_texture;
+pub mod solid_checkered;
+pub mod solid_noise;
+pub mod marble;
+pub mod wood;
+pub mod color_ramp;
+pub mod gradient;
+pub mod worley;
+pub mod bricks;
+pub mod combine;
+pub mod adjust;
+pub mod warp;
+pub mod triplanar;
+pub mod projection;
 
 use crate::color::Color;
+use crate::matrix3::Matrix3;
+use crate::ray::Ray;
+use crate::vector3::Point3;
+use crate::vector3::orthonormal_basis;
+use crate::vector3::Vector3;
+
+use std::rc::Rc;
+
+/// Where on a Hittable's surface a texture is being looked up.
+#[derive(Clone, Copy)]
+pub struct SurfacePoint {
+    /// The uv coordinates of the point on the Hittable's surface.
+    pub uv: (f64, f64),
+    /// The point in world space, even if the Hittable is inside an Instance.
+    pub point: Point3,
+    /// The point relative to the Hittable itself, such as a Sphere's center or
+    /// a Quad's origin, so that it stays the same when the Hittable or the
+    /// Instance it is inside of is moved.
+    pub object_point: Point3,
+    /// The outward unit normal of the surface at the point, in world space.
+    pub normal: Vector3,
+    /// The outward unit normal of the surface in the same space as the
+    /// object_point.
+    pub object_normal: Vector3,
+    /// Takes a step away from the point into a step away from the
+    /// object_point. The identity, unless the Hittable is inside an Instance.
+    pub to_object: Matrix3,
+    /// How far the point moves as u increases. Zero if it isn't known.
+    pub dpdu: Vector3,
+    /// How far the point moves as v increases. Zero if it isn't known.
+    pub dpdv: Vector3,
+    /// How much of the surface around the point one pixel covers.
+    pub footprint: Footprint,
+}
+
+impl SurfacePoint {
+
+    ///
+    /// Returns a SurfacePoint constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `uv` - The SurfacePoint's uv field.
+    /// * `point` - The SurfacePoint's point field.
+    /// * `object_point` - The SurfacePoint's object_point field.
+    /// * `normal` - The SurfacePoint's normal field.
+    ///
+    pub fn new(uv: (f64, f64), point: Point3, object_point: Point3, normal: Vector3) -> Self {
+        Self {
+            uv: uv,
+            point: point,
+            object_point: object_point,
+            normal: normal,
+            object_normal: normal,
+            to_object: Matrix3::identity(),
+            dpdu: Vector3::new(0.0, 0.0, 0.0),
+            dpdv: Vector3::new(0.0, 0.0, 0.0),
+            footprint: Footprint::NONE,
+        }
+    }
+
+    ///
+    /// Returns the SurfacePoint with its uv derivatives worked out: dpdu and
+    /// dpdv, and the footprint from the Ray that hit it. If the Ray has no
+    /// differentials, the footprint is left empty and textures are point
+    /// sampled. If the Ray was moved into an Instance's object space, the
+    /// SurfacePoint is then moved into world space.
+    ///
+    /// # Arguments
+    /// * `self` - The SurfacePoint, with its point and normal in the same
+    ///   space as the Ray.
+    /// * `ray` - The Ray that hit the surface.
+    /// * `is_periodic` - If u and v wrap around, like on a Sphere, so that a
+    ///   step across the seam from 0.99 to 0.01 is a small step.
+    /// * `get_uv` - Returns the uv coordinates of a point near the surface, in
+    ///   the same space as the Ray.
+    ///
+    pub fn with_derivatives(mut self, ray: Ray, is_periodic: bool, get_uv: impl Fn(Point3) -> (f64, f64)) -> Self {
+        if let Some((dpdx, dpdy)) = ray.get_footprint(self.point, self.normal) {
+            self.footprint.dpdx = dpdx;
+            self.footprint.dpdy = dpdy;
+        }
+        self.with_uv_derivatives(is_periodic, get_uv).in_world_space(ray)
+    }
+
+    ///
+    /// Returns the SurfacePoint moved into world space, if the Ray that hit it
+    /// was moved into an Instance's object space. The uv coordinates and their
+    /// steps don't depend on the space, so only the point and the vectors
+    /// move.
+    ///
+    /// # Arguments
+    /// * `self` - The SurfacePoint, in the same space as the Ray.
+    /// * `ray` - The Ray that hit the surface.
+    ///
+    pub fn in_world_space(mut self, ray: Ray) -> Self {
+
+        // Normals move by the inverse transpose, to stay perpendicular to the
+        // surface under non-uniform scaling.
+        if let Some(object_to_world) = ray.object_to_world {
+            let linear = object_to_world.get_linear();
+            self.point = object_to_world.transform_point(self.point);
+            self.normal = object_to_world.transform_normal(self.normal).unit();
+            self.dpdu = linear * self.dpdu;
+            self.dpdv = linear * self.dpdv;
+            self.footprint.dpdx = linear * self.footprint.dpdx;
+            self.footprint.dpdy = linear * self.footprint.dpdy;
+            self.to_object = object_to_world.inverse().get_linear();
+        }
+        self
+    }
+
+    ///
+    /// Returns the SurfacePoint with new uv coordinates from the given mapping,
+    /// and its dpdu, dpdv and footprint worked out again to match. Used by
+    /// textures that lay their own uv coordinates over a surface, such as
+    /// projections onto Hittables without good uv coordinates of their own.
+    ///
+    /// # Arguments
+    /// * `self` - The SurfacePoint.
+    /// * `is_periodic` - If u and v wrap around, so that a step across the
+    ///   seam from 0.99 to 0.01 is a small step.
+    /// * `get_uv` - Returns the uv coordinates of a point near the surface, in
+    ///   the same space as the point.
+    ///
+    pub fn with_uv_mapping(mut self, is_periodic: bool, get_uv: impl Fn(Point3) -> (f64, f64)) -> Self {
+        self.uv = get_uv(self.point);
+        self.dpdu = Vector3::new(0.0, 0.0, 0.0);
+        self.dpdv = Vector3::new(0.0, 0.0, 0.0);
+        self.with_uv_derivatives(is_periodic, get_uv)
+    }
+
+    ///
+    /// Returns the SurfacePoint with dpdu and dpdv worked out from the given
+    /// mapping, and the footprint's uv steps worked out from its dpdx and dpdy.
+    ///
+    /// # Arguments
+    /// * `self` - The SurfacePoint.
+    /// * `is_periodic` - If u and v wrap around.
+    /// * `get_uv` - Returns the uv coordinates of a point near the surface.
+    ///
+    fn with_uv_derivatives(mut self, is_periodic: bool, get_uv: impl Fn(Point3) -> (f64, f64)) -> Self {
+
+        // uv is stepped across the surface and differenced, which saves every
+        // Hittable from working out its own uv derivatives.
+        let get_step = |offset: Vector3| {
+            let uv = get_uv(self.point + offset);
+            let mut step = (uv.0 - self.uv.0, uv.1 - self.uv.1);
+            if is_periodic {
+                step = (step.0 - step.0.round(), step.1 - step.1.round());
+            }
+            step
+        };
+
+        // How uv changes along two directions across the tangent plane, which
+        // is inverted to find how the point changes along u and v.
+        let (t1, t2) = orthonormal_basis(self.normal);
+        let h = 1e-6 * (1.0 + self.point.mag());
+        let get_slope = |direction: Vector3| {
+            let (forward, backward) = (get_step(h * direction), get_step(-h * direction));
+            ((forward.0 - backward.0) / (2.0 * h), (forward.1 - backward.1) / (2.0 * h))
+        };
+        let ((du1, dv1), (du2, dv2)) = (get_slope(t1), get_slope(t2));
+        let determinant = du1 * dv2 - du2 * dv1;
+        if 1e-12 < determinant.abs() {
+            self.dpdu = (dv2 * t1 - dv1 * t2) / determinant;
+            self.dpdv = (du1 * t2 - du2 * t1) / determinant;
+        }
+
+        // Without a footprint, the uv steps are left at zero so that textures
+        // are point sampled.
+        if 0.0 < self.footprint.get_width() {
+            self.footprint.duvdx = get_step(self.footprint.dpdx);
+            self.footprint.duvdy = get_step(self.footprint.dpdy);
+        }
+        self
+    }
+
+    ///
+    /// Returns the SurfacePoint moved the given distance across the surface in
+    /// uv, with the point moved along the given derivatives to match. Used to
+    /// look a texture up at a nearby point, such as to find its slope.
+    ///
+    /// # Arguments
+    /// * `&self` - The SurfacePoint.
+    /// * `du` - How far to move in u.
+    /// * `dv` - How far to move in v.
+    /// * `dpdu` - How far the point moves as u increases.
+    /// * `dpdv` - How far the point moves as v increases.
+    ///
+    pub fn get_neighbour(&self, du: f64, dv: f64, dpdu: Vector3, dpdv: Vector3) -> Self {
+        let offset = du * dpdu + dv * dpdv;
+        let mut neighbour = *self;
+        neighbour.uv = (self.uv.0 + du, self.uv.1 + dv);
+        neighbour.point += offset;
+        neighbour.object_point += self.to_object * offset;
+        neighbour
+    }
+
+    ///
+    /// Returns the unit tangent and bitangent at the SurfacePoint, which with
+    /// the normal make up the frame that tangent space normal maps are in.
+    /// The tangent follows u and the bitangent is on the same side as v. If
+    /// dpdu isn't known, any frame around the normal is returned.
+    ///
+    /// # Arguments
+    /// * `&self` - The SurfacePoint.
+    ///
+    pub fn get_tangent_frame(&self) -> (Vector3, Vector3) {
+        let tangent = self.dpdu - self.dpdu.dot(self.normal) * self.normal;
+        if tangent.near_zero() || !tangent.mag().is_finite() {
+            return orthonormal_basis(self.normal);
+        }
+        let tangent = tangent.unit();
+        let bitangent = self.normal.cross(tangent);
+        if bitangent.dot(self.dpdv) < 0.0 { (tangent, -bitangent) } else { (tangent, bitangent) }
+    }
+
+    ///
+    /// Returns the SurfacePoint's position in the given Space.
+    ///
+    /// # Arguments
+    /// * `&self` - The SurfacePoint.
+    /// * `space` - The Space to return the position in.
+    ///
+    pub fn get_point(&self, space: Space) -> Point3 {
+        match space {
+            Space::World => self.point,
+            Space::Object => self.object_point,
+        }
+    }
+
+    ///
+    /// Returns a world space point near the SurfacePoint in the given Space,
+    /// such as a point stepped to while working out a mapping's derivatives.
+    ///
+    /// # Arguments
+    /// * `&self` - The SurfacePoint.
+    /// * `point` - The point near the SurfacePoint, in world space.
+    /// * `space` - The Space to return the point in.
+    ///
+    pub fn get_nearby_point(&self, point: Point3, space: Space) -> Point3 {
+        match space {
+            Space::World => point,
+            Space::Object => self.object_point + self.to_object * (point - self.point),
+        }
+    }
+
+    ///
+    /// Returns the SurfacePoint's normal in the given Space.
+    ///
+    /// # Arguments
+    /// * `&self` - The SurfacePoint.
+    /// * `space` - The Space to return the normal in.
+    ///
+    pub fn get_normal(&self, space: Space) -> Vector3 {
+        match space {
+            Space::World => self.normal,
+            Space::Object => self.object_normal,
+        }
+    }
+}
+
+/// How much of a surface one pixel covers around a SurfacePoint, as how far
+/// the point and its uv coordinates move when going one pixel over and one
+/// pixel up. Textures use it to average away detail finer than a pixel,
+/// instead of aliasing.
+#[derive(Clone, Copy)]
+pub struct Footprint {
+    /// How far the point moves going one pixel over.
+    pub dpdx: Vector3,
+    /// How far the point moves going one pixel up.
+    pub dpdy: Vector3,
+    /// How far the uv coordinates move going one pixel over.
+    pub duvdx: (f64, f64),
+    /// How far the uv coordinates move going one pixel up.
+    pub duvdy: (f64, f64),
+}
+
+impl Footprint {
+
+    /// The footprint of a point sample, such as a SurfacePoint hit by a
+    /// bounced Ray, which has no differentials.
+    pub const NONE: Footprint = Footprint {
+        dpdx: Vector3 { x: 0.0, y: 0.0, z: 0.0 },
+        dpdy: Vector3 { x: 0.0, y: 0.0, z: 0.0 },
+        duvdx: (0.0, 0.0),
+        duvdy: (0.0, 0.0),
+    };
+
+    ///
+    /// Returns the width of the footprint in the space the point is in.
+    ///
+    /// # Arguments
+    /// * `&self` - The Footprint.
+    ///
+    pub fn get_width(&self) -> f64 {
+        self.dpdx.mag().max(self.dpdy.mag())
+    }
+
+    ///
+    /// Returns the width of the footprint in uv coordinates.
+    ///
+    /// # Arguments
+    /// * `&self` - The Footprint.
+    ///
+    pub fn get_uv_width(&self) -> f64 {
+        let x = (self.duvdx.0 * self.duvdx.0 + self.duvdx.1 * self.duvdx.1).sqrt();
+        let y = (self.duvdy.0 * self.duvdy.0 + self.duvdy.1 * self.duvdy.1).sqrt();
+        x.max(y)
+    }
+}
+
+///
+/// Returns how much of an octave of noise to keep, given how many cycles of
+/// it fit across the footprint. Octaves much finer than the footprint would
+/// only alias, so they fade out to their average before they get there.
+///
+/// # Arguments
+/// * `frequency` - The octave's frequency, in cycles per unit.
+/// * `footprint_width` - The footprint's width, in the same units.
+///
+pub fn get_octave_weight(frequency: f64, footprint_width: f64) -> f64 {
+    let cycles = frequency * footprint_width;
+    (2.0 - 4.0 * cycles).clamp(0.0, 1.0)
+}
+
+/// The space a solid texture is laid out in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Space {
+    /// The texture is fixed in the scene, and Hittables move through it.
+    World,
+    /// The texture is fixed to each Hittable, and moves with it.
+    Object,
+}
+
+///
+/// Returns a pseudorandom f64 between [0, 1) for a cell of a lattice, such as
+/// one cell of a Worley texture or one brick of a wall. The same arguments
+/// always give the same value, so nothing has to be stored per cell.
+///
+/// # Arguments
+/// * `cell` - The cell's coordinates.
+/// * `seed` - Picks a different, unrelated set of values for every cell.
+/// * `index` - Picks one of several unrelated values for the same cell.
+///
+pub fn get_cell_random(cell: [i64; 3], seed: u32, index: u32) -> f64 {
+
+    // Each coordinate is mixed in with a round of SplitMix64.
+    let mut hash = (seed as u64) << 32 | index as u64;
+    for coordinate in cell {
+        let mut z = (hash ^ coordinate as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        hash = z ^ (z >> 31);
+    }
+
+    (hash >> 11) as f64 / (1_u64 << 53) as f64
+}
 
 pub trait Texture {
 
@@ -11,8 +383,47 @@ pub trait Texture {
     ///
     /// # Arguments
     /// * `&self` - The texture.
-    /// * `point` - The point in space where the color exists.
-    /// * `uv` - The uv coordinates of the point on the texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color;
+
+    ///
+    /// Returns the texture as a single value rather than a Color, for driving
+    /// a material parameter like reflectivity or a mask. By default this is
+    /// the average of the Color's channels.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the value exists.
+    ///
+    fn get_value(&self, surface: SurfacePoint) -> f64 {
+        let color = self.get_color(surface);
+        (color.r + color.g + color.b) / 3.0
+    }
+}
+
+/// A shared texture, so one node can feed several others in a texture graph.
+impl<T: Texture + ?Sized> Texture for Rc<T> {
+
+    ///
+    /// Returns the shared texture's Color.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+        (**self).get_color(surface)
+    }
+
+    ///
+    /// Returns the shared texture's value.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the value exists.
     ///
-    fn get_color(&self, uv: (f64, f64)) -> Color;
-}
\ No newline at end of file
+    fn get_value(&self, surface: SurfacePoint) -> f64 {
+        (**self).get_value(surface)
+    }
+}
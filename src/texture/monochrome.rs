@@ -1,4 +1,5 @@
 use crate::color::Color;
+use crate::texture::SurfacePoint;
 use crate::texture::Texture;
 
 /// Represents a monochromatic texture.
@@ -30,11 +31,10 @@ impl Texture for Monochrome {
     ///
     /// # Arguments
     /// * `&self` - The texture.
-    /// * `point` - The point in space where the color exists.
-    /// * `uv` - The uv coordinates of the point on the texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
     ///
     #[allow(unused_variables)]
-    fn get_color(&self, uv: (f64, f64)) -> Color {
+    fn get_color(&self, surface: SurfacePoint) -> Color {
         self.color
     }
 }
\ No newline at end of file
@@ -1,4 +1,6 @@
 use crate::color::Color;
+use crate::texture::get_octave_weight;
+use crate::texture::SurfacePoint;
 use crate::texture::Texture;
 
 use noise::Perlin;
@@ -49,28 +51,33 @@ impl Texture for Noisy {
     ///
     /// # Arguments
     /// * `&self` - The texture.
-    /// * `point` - The point in space where the color exists.
-    /// * `uv` - The uv coordinates of the point on the texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
     ///
     #[allow(unused_variables)]
-    fn get_color(&self, uv: (f64, f64)) -> Color {
+    fn get_color(&self, surface: SurfacePoint) -> Color {
 
         let mut accum = 0.0;
-        let mut u = uv.0 * self.scale;
-        let mut v = uv.1 * self.scale;
+        let mut u = surface.uv.0 * self.scale;
+        let mut v = surface.uv.1 * self.scale;
         let mut weight = 1.0;
+        let footprint_width = surface.footprint.get_uv_width() * self.scale;
+        let mut frequency = 1.0;
 
+        // Octaves finer than the footprint fade to their average, 0 or 0.5.
         for level in 0..self.layers {
-            let mut noise: f64;
-            if self.is_turbulent {
-                noise = self.perlin.get([u, v]);
-            } else {
-                noise = (self.perlin.get([u, v]) + 1.0) * 0.5;
+            let octave_weight = get_octave_weight(frequency, footprint_width);
+            let mut noise = 0.0;
+            if 0.0 < octave_weight {
+                noise = octave_weight * self.perlin.get([u, v]);
+            }
+            if !self.is_turbulent {
+                noise = (noise + 1.0) * 0.5;
             }
             accum += weight * noise;
             weight *= 0.5;
             u *= 2.0;
             v *= 2.0;
+            frequency *= 2.0;
         }
 
         self.color * accum.abs()
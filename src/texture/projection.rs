@@ -0,0 +1,264 @@
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::texture::Space;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+use crate::transform::Transform;
+use crate::vector3::Point3;
+
+use std::f64::consts::PI;
+
+/// The shape a Projection wraps its uv coordinates around, in the projector's
+/// own space.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProjectionKind {
+    /// u and v are the x and y coordinates, as if projected along z onto the
+    /// xy plane. One uv tile covers the unit square.
+    Planar,
+    /// u runs once around the y axis and v is the y coordinate, as if wrapped
+    /// around a cylinder.
+    Cylindrical,
+    /// u runs once around the y axis and v runs from the bottom to the top, as
+    /// if wrapped around a sphere, the same way a Sphere's are.
+    Spherical,
+}
+
+/// Represents a texture whose uv coordinates are projected onto a surface from
+/// a plane, cylinder or sphere placed in space, in place of the Hittable's own.
+pub struct Projection {
+    /// The texture being projected.
+    pub texture: Box<dyn Texture>,
+    /// The shape the uv coordinates are wrapped around.
+    pub kind: ProjectionKind,
+    /// Takes points from the space the texture is projected in into the
+    /// projector's own space.
+    pub to_projector: Transform,
+    /// The space the texture is projected in.
+    pub space: Space,
+}
+
+impl Projection {
+
+    ///
+    /// Returns a Projection texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `texture` - The Projection texture's texture field.
+    /// * `kind` - The Projection texture's kind field.
+    /// * `placement` - Where the projector is placed, from its own space into
+    ///   the space the texture is projected in. Scaling it up grows the uv tiles.
+    /// * `space` - The Projection texture's space field.
+    ///
+    pub fn new(texture: Box<dyn Texture>, kind: ProjectionKind, placement: Transform, space: Space) -> Self {
+        Self {
+            texture: texture,
+            kind: kind,
+            to_projector: placement.inverse(),
+            space: space,
+        }
+    }
+
+    ///
+    /// Returns the uv coordinates projected onto the given point.
+    ///
+    /// # Arguments
+    /// * `&self` - The Projection texture.
+    /// * `point` - The point, in the space the texture is projected in.
+    ///
+    fn get_uv(&self, point: Point3) -> (f64, f64) {
+        let point = self.to_projector.transform_point(point);
+        let get_u = |x: f64, z: f64| ((-z).atan2(x) + PI) / (2.0 * PI);
+        match self.kind {
+            ProjectionKind::Planar => (point.x, point.y),
+            ProjectionKind::Cylindrical => (get_u(point.x, point.z), point.y),
+            ProjectionKind::Spherical => {
+                let distance = point.mag();
+                if distance <= 0.0 {
+                    return (0.0, 0.0);
+                }
+                (get_u(point.x, point.z), (-point.y / distance).acos() / PI)
+            },
+        }
+    }
+
+    ///
+    /// Returns the surface with the projected uv coordinates in place of its own.
+    ///
+    /// # Arguments
+    /// * `&self` - The Projection texture.
+    /// * `surface` - The point on the Hittable's surface.
+    ///
+    fn get_surface(&self, surface: SurfacePoint) -> SurfacePoint {
+        let is_periodic = self.kind != ProjectionKind::Planar;
+        surface.with_uv_mapping(is_periodic, |point| self.get_uv(surface.get_nearby_point(point, self.space)))
+    }
+}
+
+impl Texture for Projection {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+        self.texture.get_color(self.get_surface(surface))
+    }
+
+    ///
+    /// Returns the projected texture's value.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the value exists.
+    ///
+    fn get_value(&self, surface: SurfacePoint) -> f64 {
+        self.texture.get_value(self.get_surface(surface))
+    }
+}
+
+/// Represents a texture projected onto the scene from a Camera, like a slide
+/// projector, so that an image lines up with the scene as seen from that
+/// Camera. u and v run across and up the Camera's view. The projection goes
+/// through everything, so surfaces hidden behind others, or facing away, are
+/// painted too.
+pub struct CameraProjection {
+    /// The texture being projected.
+    pub texture: Box<dyn Texture>,
+    /// The Camera the texture is projected from, in world space.
+    pub camera: Camera,
+    /// The texture of points behind the Camera or outside of its view.
+    pub outside: Box<dyn Texture>,
+}
+
+impl CameraProjection {
+
+    ///
+    /// Returns a CameraProjection texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `texture` - The CameraProjection texture's texture field.
+    /// * `camera` - The CameraProjection texture's camera field.
+    /// * `outside` - The CameraProjection texture's outside field.
+    ///
+    pub fn new(texture: Box<dyn Texture>, camera: Camera, outside: Box<dyn Texture>) -> Self {
+        Self {
+            texture: texture,
+            camera: camera,
+            outside: outside,
+        }
+    }
+
+    ///
+    /// Returns the surface with the uv coordinates projected from the Camera
+    /// in place of its own, or None if it is behind the Camera or outside of
+    /// its view.
+    ///
+    /// # Arguments
+    /// * `&self` - The CameraProjection texture.
+    /// * `surface` - The point on the Hittable's surface.
+    ///
+    fn get_surface(&self, surface: SurfacePoint) -> Option<SurfacePoint> {
+        let is_inside = |(u, v): (f64, f64)| (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v);
+        match self.camera.project(surface.point) {
+            Some(uv) if is_inside(uv) => Some(surface.with_uv_mapping(false, |point| self.camera.project(point).unwrap_or(uv))),
+            _ => None,
+        }
+    }
+}
+
+impl Texture for CameraProjection {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+        match self.get_surface(surface) {
+            Some(projected) => self.texture.get_color(projected),
+            None => self.outside.get_color(surface),
+        }
+    }
+
+    ///
+    /// Returns the projected texture's value, or the outside texture's.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the value exists.
+    ///
+    fn get_value(&self, surface: SurfacePoint) -> f64 {
+        match self.get_surface(surface) {
+            Some(projected) => self.texture.get_value(projected),
+            None => self.outside.get_value(surface),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::vector3::Vector3;
+
+    /// Shows its uv coordinates as red and green, and its value is just u.
+    struct UvColor;
+
+    impl Texture for UvColor {
+        fn get_color(&self, surface: SurfacePoint) -> Color {
+            Color::new(surface.uv.0, surface.uv.1, 0.0)
+        }
+
+        fn get_value(&self, surface: SurfacePoint) -> f64 {
+            surface.uv.0
+        }
+    }
+
+    ///
+    /// Returns a point in the projector's own space that each kind of
+    /// Projection maps to the given uv coordinates.
+    ///
+    fn get_projector_point(kind: ProjectionKind, (u, v): (f64, f64)) -> Point3 {
+        let phi = 2.0 * PI * u - PI;
+        let theta = PI * v;
+        match kind {
+            ProjectionKind::Planar => Point3::new(u, v, 0.7),
+            ProjectionKind::Cylindrical => Point3::new(2.0 * phi.cos(), v, -2.0 * phi.sin()),
+            ProjectionKind::Spherical => 3.0 * Point3::new(theta.sin() * phi.cos(), -theta.cos(), -theta.sin() * phi.sin()),
+        }
+    }
+
+    #[test]
+    fn uvs_round_trip_through_each_kind() {
+
+        let placement = Transform::translation(Vector3::new(1.0, -2.0, 0.5))
+            * Transform::rotation(Vector3::new(1.0, 2.0, 3.0), 40.0)
+            * Transform::scaling(Vector3::new(2.0, 1.5, 0.5));
+
+        for kind in [ProjectionKind::Planar, ProjectionKind::Cylindrical, ProjectionKind::Spherical] {
+            for uv in [(0.1, 0.2), (0.5, 0.5), (0.8, 0.35), (0.3, 0.9)] {
+
+                let point = placement.transform_point(get_projector_point(kind, uv));
+                let normal = Vector3::new(0.0, 0.0, 1.0);
+
+                // In object space, the projection follows the object_point
+                // however far the Hittable has been moved from it.
+                let offset = Vector3::new(5.0, 6.0, 7.0);
+                let world = SurfacePoint::new((0.0, 0.0), point, point - offset, normal);
+                let object = SurfacePoint::new((0.0, 0.0), point + offset, point, normal);
+
+                for (space, surface) in [(Space::World, world), (Space::Object, object)] {
+                    let texture = Projection::new(Box::new(UvColor), kind, placement, space);
+                    let color = texture.get_color(surface);
+                    assert!((color.r - uv.0).abs() < 1e-9 && (color.g - uv.1).abs() < 1e-9, "{:?} {:?} at {:?}", kind, space, uv);
+                    assert!((texture.get_value(surface) - uv.0).abs() < 1e-9, "{:?} {:?} at {:?}", kind, space, uv);
+                }
+            }
+        }
+    }
+}
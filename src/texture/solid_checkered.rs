@@ -0,0 +1,73 @@
+use crate::color::Color;
+use crate::texture::Space;
+use crate::texture::checkered::get_odd_fraction;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+
+/// Represents a checkered texture made of cubes filling 3D space, rather than
+/// squares laid over a Hittable's uv coordinates. Hittables are cut out of the
+/// pattern like a block of stone, so it never stretches or pinches.
+pub struct SolidCheckered {
+    /// The texture of one set of cubes.
+    pub texture1: Box<dyn Texture>,
+    /// The texture of the other set of cubes.
+    pub texture2: Box<dyn Texture>,
+    /// The number of cubes per unit of distance along each axis.
+    pub scale: f64,
+    /// The space the cubes are laid out in.
+    pub space: Space,
+}
+
+impl SolidCheckered {
+
+    ///
+    /// Returns a SolidCheckered texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `texture1` - The texture's texture1 field.
+    /// * `texture2` - The texture's texture2 field.
+    /// * `scale` - The texture's scale field.
+    /// * `space` - The texture's space field.
+    ///
+    pub fn new(texture1: Box<dyn Texture>, texture2: Box<dyn Texture>, scale: f64, space: Space) -> Self {
+        Self {
+            texture1: texture1,
+            texture2: texture2,
+            scale: scale,
+            space: space,
+        }
+    }
+}
+
+impl Texture for SolidCheckered {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+
+        let point = surface.get_point(self.space) * self.scale;
+        let (dpdx, dpdy) = (surface.footprint.dpdx * self.scale, surface.footprint.dpdy * self.scale);
+
+        // Each axis lands on odd cubes some fraction of the footprint, and
+        // texture2 shows where an odd number of axes do.
+        let mut even_minus_odd = 1.0;
+        for axis in 0..3 {
+            let width = dpdx[axis].abs().max(dpdy[axis].abs());
+            even_minus_odd *= 1.0 - 2.0 * get_odd_fraction(point[axis], width);
+        }
+        let t = 0.5 * (1.0 - even_minus_odd);
+
+        if t <= 0.0 {
+            self.texture1.get_color(surface)
+        } else if 1.0 <= t {
+            self.texture2.get_color(surface)
+        } else {
+            (1.0 - t) * self.texture1.get_color(surface) + t * self.texture2.get_color(surface)
+        }
+    }
+}
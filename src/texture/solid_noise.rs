@@ -0,0 +1,131 @@
+use crate::color::Color;
+use crate::texture::get_octave_weight;
+use crate::texture::Space;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+use crate::vector3::Point3;
+
+use noise::NoiseFn;
+use noise::Perlin;
+
+/// Represents a noisy texture that samples 3D Perlin noise at the point being
+/// colored, rather than 2D noise at its uv coordinates. The noise is
+/// continuous through space, so it has no seams and doesn't stretch at the
+/// poles of a Sphere.
+#[derive(Clone, Copy)]
+pub struct SolidNoise {
+    /// The perlin noise.
+    pub perlin: Perlin,
+    /// The perlin noise's scale. Larger values create smaller details.
+    pub scale: f64,
+    /// The number of times the perlin noise will be layered over itself.
+    pub layers: u32,
+    /// If the noise is turbulent.
+    pub is_turbulent: bool,
+    /// The texture's color.
+    pub color: Color,
+    /// The space the noise is laid out in.
+    pub space: Space,
+}
+
+impl SolidNoise {
+
+    ///
+    /// Returns a SolidNoise texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `seed` - The seed of the texture's perlin noise.
+    /// * `scale` - The texture's scale field.
+    /// * `layers` - The texture's layers field.
+    /// * `is_turbulent` - The texture's is_turbulent field.
+    /// * `color` - The texture's color field.
+    /// * `space` - The texture's space field.
+    ///
+    pub fn new(seed: u32, scale: f64, layers: u32, is_turbulent: bool, color: Color, space: Space) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale: scale,
+            layers: layers,
+            is_turbulent: is_turbulent,
+            color: color,
+            space: space,
+        }
+    }
+}
+
+/// The average absolute value of perlin noise, which a turbulent layer fades
+/// to when it is finer than the footprint.
+const MEAN_ABSOLUTE_NOISE: f64 = 0.26;
+
+///
+/// Returns the sum of several layers of the absolute value of the given
+/// perlin noise at the given point, each layer at twice the frequency and half
+/// the weight of the one before. The result is between [0, 2). Layers finer
+/// than the footprint fade to their average instead of being sampled.
+///
+/// # Arguments
+/// * `perlin` - The perlin noise.
+/// * `point` - The point to sample the noise at.
+/// * `layers` - The number of layers.
+/// * `footprint_width` - The width of the footprint, in the noise's units.
+///
+pub fn get_turbulence(perlin: &Perlin, point: Point3, layers: u32, footprint_width: f64) -> f64 {
+
+    let mut accum = 0.0;
+    let mut point = point;
+    let mut weight = 1.0;
+    let mut frequency = 1.0;
+
+    for _ in 0..layers {
+        let octave_weight = get_octave_weight(frequency, footprint_width);
+        let mut noise = (1.0 - octave_weight) * MEAN_ABSOLUTE_NOISE;
+        if 0.0 < octave_weight {
+            noise += octave_weight * perlin.get([point.x, point.y, point.z]).abs();
+        }
+        accum += weight * noise;
+        weight *= 0.5;
+        point *= 2.0;
+        frequency *= 2.0;
+    }
+
+    accum
+}
+
+impl Texture for SolidNoise {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+
+        let point = surface.get_point(self.space) * self.scale;
+        let footprint_width = surface.footprint.get_width() * self.scale;
+
+        let noise = if self.is_turbulent {
+            get_turbulence(&self.perlin, point, self.layers, footprint_width)
+        } else {
+            let mut accum = 0.0;
+            let mut point = point;
+            let mut weight = 1.0;
+            let mut frequency = 1.0;
+            for _ in 0..self.layers {
+                let octave_weight = get_octave_weight(frequency, footprint_width);
+                let mut noise = 0.0;
+                if 0.0 < octave_weight {
+                    noise = octave_weight * self.perlin.get([point.x, point.y, point.z]);
+                }
+                accum += weight * 0.5 * (noise + 1.0);
+                weight *= 0.5;
+                point *= 2.0;
+                frequency *= 2.0;
+            }
+            accum
+        };
+
+        self.color * noise
+    }
+}
@@ -0,0 +1,122 @@
+use crate::color::Color;
+use crate::texture::Space;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+
+/// Represents a texture that is projected onto a surface along the x, y and z
+/// axes and blended by how much the surface faces each axis, for Hittables
+/// without good uv coordinates of their own, such as boxes, SDF shapes and
+/// heightfields. Each projection is looked up with its own uv coordinates,
+/// which are oriented so that the texture is not mirrored on opposite sides.
+/// Tangent space normal maps are bent in the Hittable's own tangent frame, so
+/// projected bump heights blend better than projected normal maps.
+pub struct Triplanar {
+    /// The texture being projected.
+    pub texture: Box<dyn Texture>,
+    /// How big one tile of uv coordinates is in space.
+    pub scale: f64,
+    /// How quickly the blend turns from one projection to the next. 1 blends
+    /// softly, and higher values tighten the blend towards the edges.
+    pub sharpness: f64,
+    /// The space the texture is projected in.
+    pub space: Space,
+}
+
+impl Triplanar {
+
+    ///
+    /// Returns a Triplanar texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `texture` - The Triplanar texture's texture field.
+    /// * `scale` - The Triplanar texture's scale field. Must be above 0.
+    /// * `sharpness` - The Triplanar texture's sharpness field. Must be above 0.
+    /// * `space` - The Triplanar texture's space field.
+    ///
+    pub fn new(texture: Box<dyn Texture>, scale: f64, sharpness: f64, space: Space) -> Self {
+        assert!(0.0 < scale, "Triplanar needs a positive scale");
+        assert!(0.0 < sharpness, "Triplanar needs a positive sharpness");
+        Self {
+            texture: texture,
+            scale: scale,
+            sharpness: sharpness,
+            space: space,
+        }
+    }
+
+    ///
+    /// Returns the surface as seen by each of the three projections, with its
+    /// weight in the blend. The weights add up to 1, and projections the
+    /// surface barely faces get a weight of 0 so that they can be skipped.
+    ///
+    /// # Arguments
+    /// * `&self` - The Triplanar texture.
+    /// * `surface` - The point on the Hittable's surface.
+    ///
+    fn get_projections(&self, surface: SurfacePoint) -> [(SurfacePoint, f64); 3] {
+
+        let normal = surface.get_normal(self.space);
+        let (x, y, z) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+
+        // The weights are taken relative to the largest, so that high
+        // sharpness can't round every weight down to 0.
+        let max = x.max(y).max(z);
+        let get_weight = |component: f64| {
+            let weight = (component / max).powf(self.sharpness);
+            if weight < 1e-3 { 0.0 } else { weight }
+        };
+        let weights = [get_weight(x), get_weight(y), get_weight(z)];
+        let total: f64 = weights.iter().sum();
+
+        // u runs to the right and v runs up, as seen looking at each side.
+        let space = self.space;
+        let scale = self.scale;
+        let sign = |component: f64| if component < 0.0 { -1.0 } else { 1.0 };
+        let (sx, sy, sz) = (sign(normal.x), sign(normal.y), sign(normal.z));
+
+        [
+            (surface.with_uv_mapping(false, |point| {
+                let point = surface.get_nearby_point(point, space);
+                (-sx * point.z / scale, point.y / scale)
+            }), weights[0] / total),
+            (surface.with_uv_mapping(false, |point| {
+                let point = surface.get_nearby_point(point, space);
+                (point.x / scale, -sy * point.z / scale)
+            }), weights[1] / total),
+            (surface.with_uv_mapping(false, |point| {
+                let point = surface.get_nearby_point(point, space);
+                (sz * point.x / scale, point.y / scale)
+            }), weights[2] / total),
+        ]
+    }
+}
+
+impl Texture for Triplanar {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+        self.get_projections(surface).iter()
+            .filter(|(_, weight)| 0.0 < *weight)
+            .fold(Color::new(0.0, 0.0, 0.0), |color, (surface, weight)| color + *weight * self.texture.get_color(*surface))
+    }
+
+    ///
+    /// Returns the texture's value, blended the same way as its color.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the value exists.
+    ///
+    fn get_value(&self, surface: SurfacePoint) -> f64 {
+        self.get_projections(surface).iter()
+            .filter(|(_, weight)| 0.0 < *weight)
+            .map(|(surface, weight)| weight * self.texture.get_value(*surface))
+            .sum()
+    }
+}
@@ -0,0 +1,118 @@
+use crate::color::Color;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+use crate::vector3::Vector3;
+
+/// Represents a texture that scales, rotates and then offsets the uv
+/// coordinates another texture is looked up with.
+pub struct UvTransform {
+    /// The texture being transformed.
+    pub texture: Box<dyn Texture>,
+    /// What to multiply u and v by. Above 1 tiles the texture more often.
+    pub scale: (f64, f64),
+    /// How far to rotate the uv coordinates around (0, 0), in radians.
+    pub rotation: f64,
+    /// What to add to u and v after scaling and rotating.
+    pub offset: (f64, f64),
+}
+
+impl UvTransform {
+
+    ///
+    /// Returns a UvTransform texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `texture` - The UvTransform texture's texture field.
+    /// * `scale` - The UvTransform texture's scale field.
+    /// * `rotation` - The UvTransform texture's rotation field.
+    /// * `offset` - The UvTransform texture's offset field.
+    ///
+    pub fn new(texture: Box<dyn Texture>, scale: (f64, f64), rotation: f64, offset: (f64, f64)) -> Self {
+        Self {
+            texture: texture,
+            scale: scale,
+            rotation: rotation,
+            offset: offset,
+        }
+    }
+}
+
+impl Texture for UvTransform {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, mut surface: SurfacePoint) -> Color {
+
+        let (sin, cos) = self.rotation.sin_cos();
+        let transform = |(u, v): (f64, f64)| {
+            let (u, v) = (u * self.scale.0, v * self.scale.1);
+            (cos * u - sin * v, sin * u + cos * v)
+        };
+
+        let (u, v) = transform(surface.uv);
+        surface.uv = (u + self.offset.0, v + self.offset.1);
+
+        // The footprint is scaled and rotated along with the uv coordinates.
+        surface.footprint.duvdx = transform(surface.footprint.duvdx);
+        surface.footprint.duvdy = transform(surface.footprint.duvdy);
+        self.texture.get_color(surface)
+    }
+}
+
+/// Represents a texture that looks another texture up at a point pushed around
+/// by a third, warp texture, which bends straight features into organic ones.
+/// The warp texture's red, green and blue channels push the point along x, y
+/// and z, and its red and green channels push u and v. Channels are centered
+/// on 0.5, so that textures between [0, 1] push both ways. The footprint is
+/// not warped, so strong warps may still alias in the distance.
+pub struct DomainWarp {
+    /// The texture being warped.
+    pub texture: Box<dyn Texture>,
+    /// The texture that pushes the point around.
+    pub warp: Box<dyn Texture>,
+    /// How far a channel of 1 pushes the point.
+    pub strength: f64,
+}
+
+impl DomainWarp {
+
+    ///
+    /// Returns a DomainWarp texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `texture` - The DomainWarp texture's texture field.
+    /// * `warp` - The DomainWarp texture's warp field.
+    /// * `strength` - The DomainWarp texture's strength field.
+    ///
+    pub fn new(texture: Box<dyn Texture>, warp: Box<dyn Texture>, strength: f64) -> Self {
+        Self {
+            texture: texture,
+            warp: warp,
+            strength: strength,
+        }
+    }
+}
+
+impl Texture for DomainWarp {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, mut surface: SurfacePoint) -> Color {
+        let push = self.warp.get_color(surface);
+        let push = Vector3::new(push.r - 0.5, push.g - 0.5, push.b - 0.5) * self.strength;
+        surface.point += push;
+        surface.object_point += push;
+        surface.uv = (surface.uv.0 + push.x, surface.uv.1 + push.y);
+        self.texture.get_color(surface)
+    }
+}
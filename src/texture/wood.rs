@@ -0,0 +1,75 @@
+use crate::color::Color;
+use crate::texture::Space;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+
+use noise::NoiseFn;
+use noise::Perlin;
+
+use std::f64::consts::PI;
+
+/// Represents a wood texture: growth rings around the y axis, like a log
+/// standing upright, wobbled by noise so they aren't perfect circles.
+#[derive(Clone, Copy)]
+pub struct Wood {
+    /// The perlin noise that wobbles the rings.
+    pub perlin: Perlin,
+    /// The number of rings per unit of distance from the axis.
+    pub scale: f64,
+    /// How far, in rings, the noise pushes the rings around.
+    pub distortion: f64,
+    /// The color of the wood between the rings.
+    pub light_color: Color,
+    /// The color of the rings.
+    pub dark_color: Color,
+    /// The space the wood is laid out in.
+    pub space: Space,
+}
+
+impl Wood {
+
+    ///
+    /// Returns a Wood texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `seed` - The seed of the texture's perlin noise.
+    /// * `scale` - The texture's scale field.
+    /// * `distortion` - The texture's distortion field.
+    /// * `light_color` - The texture's light_color field.
+    /// * `dark_color` - The texture's dark_color field.
+    /// * `space` - The texture's space field.
+    ///
+    pub fn new(seed: u32, scale: f64, distortion: f64, light_color: Color, dark_color: Color, space: Space) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale: scale,
+            distortion: distortion,
+            light_color: light_color,
+            dark_color: dark_color,
+            space: space,
+        }
+    }
+}
+
+impl Texture for Wood {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+
+        let point = surface.get_point(self.space) * self.scale;
+
+        // The noise is stretched along the axis, the way grain runs along a log.
+        let noise = self.perlin.get([point.x, 0.1 * point.y, point.z]);
+        let rings = (point.x * point.x + point.z * point.z).sqrt() + self.distortion * noise;
+
+        // Each ring fades from light to dark and back, sharpened towards the dark edge.
+        let t = (0.5 * (1.0 - (2.0 * PI * rings).cos())).powi(3);
+        (1.0 - t) * self.light_color + t * self.dark_color
+    }
+}
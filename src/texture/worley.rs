@@ -0,0 +1,121 @@
+use crate::color::Color;
+use crate::texture::color_ramp::ColorRamp;
+use crate::texture::get_cell_random;
+use crate::texture::Space;
+use crate::texture::SurfacePoint;
+use crate::texture::Texture;
+use crate::vector3::Point3;
+
+/// Which distance a Worley texture colors a point by.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WorleyMode {
+    /// The distance to the closest feature point. Gives round blobs that are
+    /// darkest at their centers.
+    F1,
+    /// The distance to the second closest feature point.
+    F2,
+    /// How much further the second closest feature point is than the closest.
+    /// Zero along the borders between cells, which gives a network of cracks
+    /// like cobblestones or dried mud.
+    F2MinusF1,
+}
+
+/// Represents a cellular texture, also called Worley or Voronoi noise. Space
+/// is cut into unit cubes, each holding one feature point at a random spot,
+/// and each point is colored by its distance to the nearest of them.
+#[derive(Clone)]
+pub struct Worley {
+    /// The seed that places the feature points.
+    pub seed: u32,
+    /// The number of cells per unit of distance.
+    pub scale: f64,
+    /// Which distance colors a point.
+    pub mode: WorleyMode,
+    /// The colors of the distances. A distance of 0 is the start of the ramp,
+    /// and a distance of one cell is its end.
+    pub ramp: ColorRamp,
+    /// The space the cells are laid out in.
+    pub space: Space,
+}
+
+impl Worley {
+
+    ///
+    /// Returns a Worley texture constructed from the given arguments.
+    ///
+    /// # Arguments
+    /// * `seed` - The texture's seed field.
+    /// * `scale` - The texture's scale field.
+    /// * `mode` - The texture's mode field.
+    /// * `ramp` - The texture's ramp field.
+    /// * `space` - The texture's space field.
+    ///
+    pub fn new(seed: u32, scale: f64, mode: WorleyMode, ramp: ColorRamp, space: Space) -> Self {
+        Self {
+            seed: seed,
+            scale: scale,
+            mode: mode,
+            ramp: ramp,
+            space: space,
+        }
+    }
+
+    ///
+    /// Returns the distances from the given point to its closest and second
+    /// closest feature points, in cells.
+    ///
+    /// # Arguments
+    /// * `&self` - The Worley texture.
+    /// * `point` - The point, already scaled so that cells are one unit wide.
+    ///
+    fn get_distances(&self, point: Point3) -> (f64, f64) {
+
+        let cell = [point.x.floor() as i64, point.y.floor() as i64, point.z.floor() as i64];
+        let mut closest = f64::INFINITY;
+        let mut second_closest = f64::INFINITY;
+
+        // The closest feature points are almost always in the 27 cells around
+        // the point, so only those are checked.
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let neighbor = [cell[0] + dx, cell[1] + dy, cell[2] + dz];
+                    let feature = Point3::new(
+                        neighbor[0] as f64 + get_cell_random(neighbor, self.seed, 0),
+                        neighbor[1] as f64 + get_cell_random(neighbor, self.seed, 1),
+                        neighbor[2] as f64 + get_cell_random(neighbor, self.seed, 2),
+                    );
+                    let distance = (feature - point).mag();
+                    if distance < closest {
+                        second_closest = closest;
+                        closest = distance;
+                    } else if distance < second_closest {
+                        second_closest = distance;
+                    }
+                }
+            }
+        }
+
+        (closest, second_closest)
+    }
+}
+
+impl Texture for Worley {
+
+    ///
+    /// Returns the Color value for the texture using the given arguments.
+    ///
+    /// # Arguments
+    /// * `&self` - The texture.
+    /// * `surface` - The point on the Hittable's surface where the color exists.
+    ///
+    fn get_color(&self, surface: SurfacePoint) -> Color {
+        let (f1, f2) = self.get_distances(surface.get_point(self.space) * self.scale);
+        let distance = match self.mode {
+            WorleyMode::F1 => f1,
+            WorleyMode::F2 => f2,
+            WorleyMode::F2MinusF1 => f2 - f1,
+        };
+        self.ramp.get_color(distance)
+    }
+}
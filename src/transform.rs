@@ -0,0 +1,353 @@
+use crate::hittable::aabb::AABB;
+use crate::matrix3::Matrix3;
+use crate::matrix4::Matrix4;
+use crate::quaternion::Quaternion;
+use crate::ray::Ray;
+use crate::ray::RayDifferentials;
+use crate::vector3::Point3;
+use crate::vector3::Vector3;
+
+use std::ops::Mul;
+
+/// Represents an affine transformation along with its inverse. Keeping both
+/// means the inverse only has to be found once, no matter how many times the
+/// transformation is used.
+#[derive(Clone, Copy)]
+pub struct Transform {
+    /// The transformation's matrix.
+    pub matrix: Matrix4,
+    /// The inverse of the transformation's matrix.
+    pub inverse: Matrix4,
+}
+
+impl Transform {
+
+    ///
+    /// Returns the transformation that changes nothing.
+    ///
+    pub fn identity() -> Self {
+        Self {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+
+    ///
+    /// Returns the transformation with the given matrix, or None if the matrix
+    /// can't be inverted.
+    ///
+    /// # Arguments
+    /// * `matrix` - The transformation's matrix.
+    ///
+    pub fn from_matrix(matrix: Matrix4) -> Option<Self> {
+        Some(Self {
+            matrix: matrix,
+            inverse: matrix.inverse()?,
+        })
+    }
+
+    ///
+    /// Returns a transformation that translates by the given offset.
+    ///
+    /// # Arguments
+    /// * `offset` - The translation.
+    ///
+    pub fn translation(offset: Vector3) -> Self {
+        Self {
+            matrix: Matrix4::translation(offset),
+            inverse: Matrix4::translation(-offset),
+        }
+    }
+
+    ///
+    /// Returns a transformation that scales each axis by the given factors.
+    /// None of the factors may be zero.
+    ///
+    /// # Arguments
+    /// * `factors` - The scale along the x, y and z axes.
+    ///
+    pub fn scaling(factors: Vector3) -> Self {
+        Self {
+            matrix: Matrix4::scaling(factors),
+            inverse: Matrix4::scaling(Vector3::new(1.0 / factors.x, 1.0 / factors.y, 1.0 / factors.z)),
+        }
+    }
+
+    ///
+    /// Returns a transformation that rotates counter-clockwise around the
+    /// given axis.
+    ///
+    /// # Arguments
+    /// * `axis` - The axis of rotation. Does not need to be a unit vector.
+    /// * `degrees` - The angle of rotation in degrees.
+    ///
+    pub fn rotation(axis: Vector3, degrees: f64) -> Self {
+        Self::from_quaternion(Quaternion::from_axis_angle(axis, degrees))
+    }
+
+    ///
+    /// Returns a transformation that applies the rotation of the given unit
+    /// quaternion.
+    ///
+    /// # Arguments
+    /// * `rotation` - The rotation.
+    ///
+    pub fn from_quaternion(rotation: Quaternion) -> Self {
+        let linear = rotation.to_matrix3();
+        let origin = Vector3::new(0.0, 0.0, 0.0);
+        Self {
+            matrix: Matrix4::from_matrix3(linear, origin),
+            inverse: Matrix4::from_matrix3(linear.transpose(), origin),
+        }
+    }
+
+    ///
+    /// Returns a transformation that scales, then rotates, then translates.
+    /// This is the usual way of placing an object in a scene.
+    ///
+    /// # Arguments
+    /// * `translation` - Where the object's origin ends up.
+    /// * `rotation` - The object's orientation.
+    /// * `scale` - The scale along the object's x, y and z axes.
+    ///
+    pub fn from_trs(translation: Vector3, rotation: Quaternion, scale: Vector3) -> Self {
+        Self::translation(translation) * Self::from_quaternion(rotation) * Self::scaling(scale)
+    }
+
+    ///
+    /// Returns the camera to world transformation of a camera at eye looking
+    /// towards target. See Matrix4::look_at.
+    ///
+    /// # Arguments
+    /// * `eye` - The camera's location in space.
+    /// * `target` - The point in space the camera is looking to.
+    /// * `up` - The camera's vertical up direction.
+    ///
+    pub fn look_at(eye: Point3, target: Point3, up: Vector3) -> Self {
+        Self::from_matrix(Matrix4::look_at(eye, target, up)).expect("the up direction must not be parallel to the view")
+    }
+
+    ///
+    /// Returns the inverse transformation.
+    ///
+    /// # Arguments
+    /// * `&self` - The transformation.
+    ///
+    pub fn inverse(&self) -> Self {
+        Self {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    ///
+    /// Returns the given point transformed. Points are affected by translation.
+    ///
+    /// # Arguments
+    /// * `&self` - The transformation.
+    /// * `point` - The point to transform.
+    ///
+    pub fn transform_point(&self, point: Point3) -> Point3 {
+        self.matrix.transform_point(point)
+    }
+
+    ///
+    /// Returns the given vector transformed. Vectors are not affected by translation.
+    ///
+    /// # Arguments
+    /// * `&self` - The transformation.
+    /// * `vector` - The vector to transform.
+    ///
+    pub fn transform_vector(&self, vector: Vector3) -> Vector3 {
+        self.matrix.transform_vector(vector)
+    }
+
+    ///
+    /// Returns the given surface normal transformed. Normals are multiplied by
+    /// the inverse transpose of the matrix so they stay perpendicular to their
+    /// surface under non-uniform scaling. The result is not normalized.
+    ///
+    /// # Arguments
+    /// * `&self` - The transformation.
+    /// * `normal` - The normal to transform.
+    ///
+    pub fn transform_normal(&self, normal: Vector3) -> Vector3 {
+        self.inverse.transpose().transform_vector(normal)
+    }
+
+    ///
+    /// Returns the given Ray transformed, along with its differentials. The
+    /// direction is not normalized, so distances along the transformed Ray
+    /// match distances along the original.
+    ///
+    /// # Arguments
+    /// * `&self` - The transformation.
+    /// * `ray` - The Ray to transform.
+    ///
+    pub fn transform_ray(&self, ray: Ray) -> Ray {
+        let origin = self.transform_point(ray.origin);
+        let direction = self.transform_vector(ray.direction);
+        match ray.differentials {
+            Some(differentials) => Ray::with_differentials(origin, direction, RayDifferentials {
+                x_origin: self.transform_point(differentials.x_origin),
+                x_direction: self.transform_vector(differentials.x_direction),
+                y_origin: self.transform_point(differentials.y_origin),
+                y_direction: self.transform_vector(differentials.y_direction),
+            }),
+            None => Ray::new(origin, direction),
+        }
+    }
+
+    ///
+    /// Returns an AABB that surrounds the given AABB after it has been
+    /// transformed. All eight corners are transformed, so the result stays
+    /// correct under rotation.
+    ///
+    /// # Arguments
+    /// * `&self` - The transformation.
+    /// * `bounding_box` - The AABB to transform.
+    ///
+    pub fn transform_aabb(&self, bounding_box: AABB) -> AABB {
+
+        let mut min = Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut max = Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+
+        for i in 0..8 {
+            let corner = Point3::new(
+                if i & 1 == 0 { bounding_box.min.x } else { bounding_box.max.x },
+                if i & 2 == 0 { bounding_box.min.y } else { bounding_box.max.y },
+                if i & 4 == 0 { bounding_box.min.z } else { bounding_box.max.z },
+            );
+            let p = self.transform_point(corner);
+            min = Point3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+            max = Point3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+        }
+
+        AABB::new(min, max)
+    }
+
+    ///
+    /// Returns the linear (rotation and scale) part of the transformation.
+    ///
+    /// # Arguments
+    /// * `&self` - The transformation.
+    ///
+    pub fn get_linear(&self) -> Matrix3 {
+        self.matrix.get_matrix3()
+    }
+}
+
+impl Mul for Transform {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            matrix: self.matrix * rhs.matrix,
+            inverse: rhs.inverse * self.inverse,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn assert_vector_eq(a: Vector3, b: Vector3) {
+        assert!((a - b).mag() < 1e-9, "({}, {}, {}) != ({}, {}, {})", a.x, a.y, a.z, b.x, b.y, b.z);
+    }
+
+    fn get_transform() -> Transform {
+        Transform::from_trs(
+            Vector3::new(1.0, -2.0, 3.0),
+            Quaternion::from_euler(20.0, 45.0, -60.0),
+            Vector3::new(2.0, 0.5, 3.0),
+        )
+    }
+
+    #[test]
+    fn inverse_times_transform_is_identity() {
+        let transform = get_transform();
+        for product in [transform.inverse() * transform, transform * transform.inverse()] {
+            for row in 0..4 {
+                for col in 0..4 {
+                    let expected = if row == col { 1.0 } else { 0.0 };
+                    assert!((product.matrix.m[row][col] - expected).abs() < 1e-9);
+                    assert!((product.inverse.m[row][col] - expected).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn from_trs_scales_then_rotates_then_translates() {
+        let rotation = Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), 90.0);
+        let transform = Transform::from_trs(Vector3::new(10.0, 0.0, 0.0), rotation, Vector3::new(2.0, 1.0, 1.0));
+        // (1, 0, 0) is scaled to (2, 0, 0), rotated to (0, 2, 0), then moved.
+        assert_vector_eq(transform.transform_point(Point3::new(1.0, 0.0, 0.0)), Point3::new(10.0, 2.0, 0.0));
+        let chained = Transform::translation(Vector3::new(10.0, 0.0, 0.0)) * Transform::from_quaternion(rotation) * Transform::scaling(Vector3::new(2.0, 1.0, 1.0));
+        let point = Point3::new(0.3, -0.7, 1.1);
+        assert_vector_eq(transform.transform_point(point), chained.transform_point(point));
+    }
+
+    #[test]
+    fn identity_changes_nothing() {
+        let point = Point3::new(0.3, -0.7, 1.1);
+        assert_vector_eq(Transform::identity().transform_point(point), point);
+        assert_vector_eq(Transform::identity().transform_normal(point), point);
+        let placement = Transform::from_trs(Vector3::new(0.0, 0.0, 0.0), Quaternion::identity(), Vector3::new(1.0, 1.0, 1.0));
+        assert_vector_eq(placement.transform_point(point), point);
+    }
+
+    #[test]
+    fn points_move_and_vectors_do_not() {
+        let transform = Transform::translation(Vector3::new(1.0, 2.0, 3.0));
+        assert_vector_eq(transform.transform_point(Point3::new(0.0, 0.0, 0.0)), Point3::new(1.0, 2.0, 3.0));
+        assert_vector_eq(transform.transform_vector(Vector3::new(1.0, 0.0, 0.0)), Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scale() {
+        // A 45 degree slope, squashed along x.
+        let transform = Transform::scaling(Vector3::new(0.25, 1.0, 1.0));
+        let tangent = Vector3::new(1.0, 1.0, 0.0);
+        let normal = Vector3::new(1.0, -1.0, 0.0);
+        let tangent = transform.transform_vector(tangent);
+        assert!(transform.transform_normal(normal).dot(tangent).abs() < 1e-9);
+        // Treating the normal as a vector would tip it off the surface.
+        assert!(1e-3 < transform.transform_vector(normal).dot(tangent).abs());
+
+        let transform = get_transform();
+        let (tangent, bitangent) = (Vector3::new(0.2, 0.9, -0.4), Vector3::new(-1.0, 0.3, 0.6));
+        let normal = transform.transform_normal(tangent.cross(bitangent));
+        assert!(normal.dot(transform.transform_vector(tangent)).abs() < 1e-9);
+        assert!(normal.dot(transform.transform_vector(bitangent)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rays_keep_their_distances() {
+        let transform = get_transform();
+        let ray = Ray::new(Point3::new(0.5, 1.0, -2.0), Vector3::new(0.3, -0.2, 1.0));
+        let moved = transform.transform_ray(ray);
+        for distance in [0.0, 1.5, 4.0] {
+            assert_vector_eq(moved.get_point(distance), transform.transform_point(ray.get_point(distance)));
+        }
+    }
+
+    #[test]
+    fn transformed_aabb_contains_transformed_corners() {
+        let transform = get_transform();
+        let bounding_box = AABB::new(Point3::new(-1.0, 0.0, -0.5), Point3::new(1.0, 2.0, 0.5));
+        let moved = transform.transform_aabb(bounding_box);
+        for i in 0..8 {
+            let corner = Point3::new(
+                if i & 1 == 0 { -1.0 } else { 1.0 },
+                if i & 2 == 0 { 0.0 } else { 2.0 },
+                if i & 4 == 0 { -0.5 } else { 0.5 },
+            );
+            let p = transform.transform_point(corner);
+            assert!(moved.min.x <= p.x && p.x <= moved.max.x);
+            assert!(moved.min.y <= p.y && p.y <= moved.max.y);
+            assert!(moved.min.z <= p.z && p.z <= moved.max.z);
+        }
+    }
+}
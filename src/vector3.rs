@@ -4,6 +4,7 @@ use std::ops::Add;
 use std::ops::AddAssign;
 use std::ops::Div;
 use std::ops::DivAssign;
+use std::ops::Index;
 use std::ops::Mul;
 use std::ops::MulAssign;
 use std::ops::Neg;
@@ -165,6 +166,22 @@ pub fn rand_vector2() -> Vector3 {
     if 1.0 <= v.mag() { rand_vector2() } else { v }
 }
 
+///
+/// Returns two unit Vector3s that form an orthonormal basis together with the
+/// given normal. The returned vectors are orthogonal to the normal and to each
+/// other.
+///
+/// # Arguments
+/// * `normal` - The vector the basis is built around. Does not need to be a unit vector.
+///
+pub fn orthonormal_basis(normal: Vector3) -> (Vector3, Vector3) {
+    let n = normal.unit();
+    let helper = if n.x.abs() > 0.9 { Vector3::new(0.0, 1.0, 0.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+    let tangent = n.cross(helper).unit();
+    let bitangent = n.cross(tangent);
+    (tangent, bitangent)
+}
+
 impl Add for Vector3 {
     type Output = Self;
     fn add(self, rhs: Self) -> Self {
@@ -207,6 +224,18 @@ impl DivAssign<f64> for Vector3 {
     }
 }
 
+impl Index<usize> for Vector3 {
+    type Output = f64;
+    fn index(&self, axis: usize) -> &f64 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vector3 axis {} is out of range", axis),
+        }
+    }
+}
+
 impl Mul<f64> for Vector3 {
     type Output = Self;
     fn mul(self, rhs: f64) -> Self {